@@ -1,6 +1,8 @@
 #!/usr/bin/env node
 
 import * as readline from 'node:readline';
+import * as fs from 'node:fs';
+import * as path from 'node:path';
 
 const rl = readline.createInterface({
   input: process.stdin,
@@ -10,23 +12,136 @@ const rl = readline.createInterface({
 
 console.log("OpenClaw Engine v0.1 (Mock) Initialized.");
 
-rl.on('line', (line) => {
-  const input = line.trim();
-  if (!input) return;
+// In-memory session state, handed to the kernel on SUSPEND and given back on RESTORE
+let session = { handled: 0, lastQuery: null };
+
+// Requests cancelled by the kernel while their answer was still streaming
+const cancelled = new Set();
+
+const emit = (id, text, isError = false) => {
+  const line = id ? `CHUNK ${id} ${text}` : text;
+  if (isError) console.error(line); else console.log(line);
+};
 
+// Files below `dir` relative to it, skipping hidden entries and past exports
+const walk = (dir, rel = "") => fs.readdirSync(path.join(dir, rel), { withFileTypes: true })
+  .filter((e) => !e.name.startsWith(".") && !(rel === "" && e.name === "exports"))
+  .flatMap((e) => e.isDirectory() ? walk(dir, path.join(rel, e.name)) : e.isFile() ? [path.join(rel, e.name)] : []);
+
+// Workspace verbs. Paths are on disk and have passed the kernel's jail:
+// `BUILD <idea> IN <root>`, `AUDIT <path>` and `EXPORT <format> FROM <root>`
+const workspace = (args) => {
+  const [verb, ...rest] = args.split(" ");
+  const body = rest.join(" ");
+  const split = (sep) => {
+      const i = body.lastIndexOf(sep);
+      if (i < 0) throw new Error(`Malformed WORKSPACE ${verb}`);
+      return [body.slice(0, i), body.slice(i + sep.length)];
+  };
+  if (verb === "BUILD") {
+      const [idea, root] = split(" IN ");
+      const slug = idea.toLowerCase().replace(/[^a-z0-9]+/g, "-").replace(/^-+|-+$/g, "").slice(0, 48) || "project";
+      fs.mkdirSync(path.join(root, slug), { recursive: true });
+      const brief = path.join(root, slug, "BRIEF.md");
+      if (fs.existsSync(brief)) return [`Project ${slug} already exists`];
+      fs.writeFileSync(brief, `# ${idea}\n`);
+      return [`Scaffolded ${slug}/BRIEF.md`];
+  } else if (verb === "AUDIT") {
+      const files = fs.statSync(body).isDirectory() ? walk(body) : [""];
+      const bytes = files.reduce((sum, f) => sum + fs.statSync(path.join(body, f)).size, 0);
+      return [`Audited ${files.length} file(s), ${bytes} bytes`];
+  } else if (verb === "EXPORT") {
+      const [format, root] = split(" FROM ");
+      const files = walk(root).map((f) => ({ path: f, bytes: fs.statSync(path.join(root, f)).size }));
+      const name = `workspace-${Date.now()}.${format}`;
+      const text = format === "json"
+          ? JSON.stringify(files, null, 2)
+          : files.map((f) => `- ${f.path} (${f.bytes} bytes)`).join("\n");
+      fs.mkdirSync(path.join(root, "exports"), { recursive: true });
+      fs.writeFileSync(path.join(root, "exports", name), text + "\n");
+      return [`Exported ${files.length} file(s) to exports/${name}`];
+  }
+  throw new Error(`Unknown workspace verb: ${verb}`);
+};
+
+const handle = (input, id) => {
+  session.handled += 1;
+  let lines = [];
   if (input.startsWith("SYSTEM")) {
-      console.log(`System Acknowledged: ${input.substring(7)}`);
+      lines = [`System Acknowledged: ${input.substring(7)}`];
   } else if (input.startsWith("KNOWLEDGE")) {
-      const query = input.substring(10);
-      console.log(`Neuron Triggered: I have received your query about "${query}". Analysis pending.`);
+      // Grounded queries carry retrieved excerpts after ` CONTEXT `
+      const [query, context] = input.substring(10).split(" CONTEXT ");
+      session.lastQuery = query;
+      lines = [`Neuron Triggered: I have received your query about "${query}".`];
+      lines.push(context ? `Grounded on ${(context.match(/\[\d+\] /g) || []).length} excerpt(s).` : "No indexed knowledge matched.");
+  } else if (input.startsWith("CLASSIFY")) {
+      // Librarian pass: name the /knowledge folder a file belongs in
+      const sample = input.substring(9);
+      lines = [/rule|identit|principle|policy/i.test(sample) ? "core" : /analysis|report|summary|findings/i.test(sample) ? "dist" : "archive"];
+  } else if (input.startsWith("WORKSPACE ")) {
+      try {
+          lines = workspace(input.substring(10));
+      } catch (e) {
+          emit(id, `Workspace failed: ${e.message}`, true);
+      }
   } else {
-      console.error(`Unknown Protocol: ${input}`);
+      emit(id, `Unknown Protocol: ${input}`, true);
+  }
+
+  if (!id) {
+      lines.forEach((l) => emit(null, l));
+      return;
   }
+  // Stream one chunk per tick so a CANCEL arriving in between takes effect
+  const next = () => {
+      if (cancelled.delete(id)) return;
+      const l = lines.shift();
+      if (l === undefined) {
+          console.log(`END ${id}`);
+          return;
+      }
+      emit(id, l);
+      setImmediate(next);
+  };
+  next();
+};
+
+rl.on('line', (line) => {
+  const input = line.trim();
+  if (!input) return;
+
+  if (input === "SUSPEND") {
+      console.log(`STATE_DUMP ${JSON.stringify(session)}`);
+      return;
+  } else if (input.startsWith("RESTORE ")) {
+      try {
+          session = { ...session, ...JSON.parse(input.substring(8)) };
+      } catch (e) {
+          console.error(`Invalid state dump: ${e.message}`);
+      }
+      return;
+  }
+
+  // Request-scoped commands: `REQ <id> <command>` answers with `CHUNK <id> <text>`
+  // lines and a closing `END <id>`; `CANCEL <id>` drops whatever is still queued.
+  if (input.startsWith("CANCEL ")) {
+      cancelled.add(input.substring(7));
+      return;
+  } else if (input.startsWith("REQ ")) {
+      const [, id, ...rest] = input.split(" ");
+      handle(rest.join(" "), id);
+      return;
+  }
+
+  handle(input, null);
 });
 
 // Keep process alive
 setInterval(() => {}, 1000);
 
 // Handle signals
+// Let in-flight answers drain before exiting
+rl.on('close', () => setTimeout(() => process.exit(0), 50));
 process.on('SIGTERM', () => process.exit(0));
 process.on('SIGINT', () => process.exit(0));
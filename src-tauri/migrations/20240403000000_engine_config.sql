@@ -0,0 +1,6 @@
+-- Per-agency AI engine runtime configuration (serialized EngineConfig)
+CREATE TABLE IF NOT EXISTS engine_configs (
+    agency_id TEXT PRIMARY KEY NOT NULL REFERENCES agencies(id),
+    config TEXT NOT NULL,
+    updated_at TEXT NOT NULL
+);
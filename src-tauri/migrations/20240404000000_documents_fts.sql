@@ -0,0 +1,17 @@
+-- Full-text index over chunk text (documents only keeps offsets)
+CREATE VIRTUAL TABLE IF NOT EXISTS documents_fts USING fts5(
+    content,
+    document_id UNINDEXED,
+    path UNINDEXED,
+    agency_id UNINDEXED,
+    tokenize = 'unicode61 remove_diacritics 2'
+);
+
+-- Keep the FTS rows in sync with their chunks
+CREATE TRIGGER IF NOT EXISTS documents_fts_delete AFTER DELETE ON documents BEGIN
+    DELETE FROM documents_fts WHERE document_id = old.id;
+END;
+
+CREATE TRIGGER IF NOT EXISTS documents_fts_agency AFTER UPDATE OF agency_id, path ON documents BEGIN
+    UPDATE documents_fts SET agency_id = new.agency_id, path = new.path WHERE document_id = new.id;
+END;
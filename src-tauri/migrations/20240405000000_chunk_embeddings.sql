@@ -0,0 +1,8 @@
+-- Dense vectors for semantic retrieval, one per chunk and embedding model
+CREATE TABLE IF NOT EXISTS chunk_embeddings (
+    document_id TEXT NOT NULL REFERENCES documents(id) ON DELETE CASCADE,
+    model TEXT NOT NULL,
+    dim INTEGER NOT NULL,
+    vector BLOB NOT NULL,
+    PRIMARY KEY (document_id, model)
+);
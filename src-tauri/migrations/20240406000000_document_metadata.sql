@@ -0,0 +1,13 @@
+-- Page of extracted documents a chunk starts on (NULL when not paginated)
+ALTER TABLE documents ADD COLUMN page INTEGER;
+
+-- Per-file properties recorded at index time
+CREATE TABLE IF NOT EXISTS document_metadata (
+    path TEXT PRIMARY KEY NOT NULL,
+    agency_id TEXT NOT NULL DEFAULT 'SYSTEM' REFERENCES agencies(id),
+    extractor TEXT NOT NULL,
+    title TEXT,
+    author TEXT,
+    page_count INTEGER,
+    indexed_at TEXT NOT NULL
+);
@@ -0,0 +1,19 @@
+-- A file is indexed once per owning agency: key metadata by (agency_id, path)
+CREATE TABLE document_metadata_scoped (
+    agency_id TEXT NOT NULL DEFAULT 'SYSTEM' REFERENCES agencies(id),
+    path TEXT NOT NULL,
+    extractor TEXT NOT NULL,
+    title TEXT,
+    author TEXT,
+    page_count INTEGER,
+    indexed_at TEXT NOT NULL,
+    PRIMARY KEY (agency_id, path)
+);
+
+INSERT INTO document_metadata_scoped (agency_id, path, extractor, title, author, page_count, indexed_at)
+SELECT agency_id, path, extractor, title, author, page_count, indexed_at FROM document_metadata;
+
+DROP TABLE document_metadata;
+ALTER TABLE document_metadata_scoped RENAME TO document_metadata;
+
+CREATE INDEX IF NOT EXISTS idx_documents_agency_path ON documents(agency_id, path);
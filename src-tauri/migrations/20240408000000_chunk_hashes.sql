@@ -0,0 +1,4 @@
+-- Per-chunk content hash, so re-indexing a changed file reuses unchanged chunks
+ALTER TABLE documents ADD COLUMN chunk_hash TEXT;
+
+CREATE INDEX IF NOT EXISTS idx_documents_chunk_hash ON documents(agency_id, path, chunk_hash);
@@ -0,0 +1,3 @@
+-- File size at index time and user-assigned tags (JSON array) for the inventory
+ALTER TABLE document_metadata ADD COLUMN size INTEGER;
+ALTER TABLE document_metadata ADD COLUMN tags TEXT NOT NULL DEFAULT '[]';
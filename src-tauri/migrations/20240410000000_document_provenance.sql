@@ -0,0 +1,10 @@
+-- Where learned documents came from: one row per file fetched into the archive
+CREATE TABLE IF NOT EXISTS document_provenance (
+    agency_id TEXT NOT NULL REFERENCES agencies(id),
+    path TEXT NOT NULL,
+    source TEXT NOT NULL,
+    fetcher TEXT NOT NULL,
+    content_hash TEXT NOT NULL,
+    fetched_at TEXT NOT NULL,
+    PRIMARY KEY (agency_id, path)
+);
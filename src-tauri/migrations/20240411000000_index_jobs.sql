@@ -0,0 +1,7 @@
+-- Indexing jobs queued by the watcher but not yet processed; resumed at startup
+CREATE TABLE IF NOT EXISTS index_jobs (
+    path TEXT PRIMARY KEY NOT NULL,
+    agency_id TEXT NOT NULL,
+    size INTEGER NOT NULL DEFAULT 0,
+    enqueued_at TEXT NOT NULL
+);
@@ -0,0 +1,9 @@
+-- Per-agency watch driver configuration (serialized WatchConfig)
+CREATE TABLE IF NOT EXISTS watch_configs (
+    agency_id TEXT PRIMARY KEY NOT NULL REFERENCES agencies(id),
+    config TEXT NOT NULL,
+    updated_at TEXT NOT NULL
+);
+
+-- Archived agencies keep their data but are no longer listed or watched
+ALTER TABLE agencies ADD COLUMN archived_at TEXT;
@@ -0,0 +1,15 @@
+-- Asciicast recordings of terminal sessions, linked to their audit entry
+CREATE TABLE IF NOT EXISTS pty_recordings (
+    id TEXT PRIMARY KEY NOT NULL,
+    agency_id TEXT NOT NULL REFERENCES agencies(id),
+    path TEXT NOT NULL,
+    cols INTEGER NOT NULL,
+    rows INTEGER NOT NULL,
+    started_at TEXT NOT NULL,
+    duration_secs REAL NOT NULL,
+    bytes INTEGER NOT NULL,
+    hash TEXT NOT NULL,
+    audit_hash TEXT
+);
+
+CREATE INDEX IF NOT EXISTS idx_pty_recordings_agency ON pty_recordings(agency_id, started_at);
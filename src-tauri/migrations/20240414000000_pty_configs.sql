@@ -0,0 +1,6 @@
+-- Per-agency terminal key mapping (serialized SignalConfig)
+CREATE TABLE IF NOT EXISTS pty_configs (
+    agency_id TEXT PRIMARY KEY NOT NULL REFERENCES agencies(id),
+    config TEXT NOT NULL,
+    updated_at TEXT NOT NULL
+);
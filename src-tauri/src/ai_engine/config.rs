@@ -0,0 +1,406 @@
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+use sqlx::{Pool, Sqlite};
+use std::net::IpAddr;
+use std::path::{Component, Path, PathBuf};
+use crate::AppState;
+use crate::audit;
+use super::AiEngine;
+
+/// Bundled OpenClaw entry point. Tauri flattens parent directories into `_up_`
+/// when resources are referenced from outside src-tauri.
+pub const DEFAULT_ENTRY_SCRIPT: &str = "_up_/engines/openclaw/openclaw.mjs";
+
+/// Runtimes the engine may be launched with, looked up on `PATH`.
+pub const ALLOWED_RUNTIMES: &[&str] = &["node", "bun", "deno"];
+
+/// Runtime arguments that cannot load code or change what is executed.
+pub const ALLOWED_ARGS: &[&str] = &["--no-warnings", "--no-deprecation", "--enable-source-maps", "--trace-warnings", "--trace-uncaught"];
+
+/// Engine implementation selected for an agency.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EngineBackend {
+    /// The OpenClaw node process.
+    OpenClaw,
+    /// In-process scripted engine replaying canned responses.
+    Mock,
+    /// Local OpenAI-compatible HTTP server (llama.cpp, Ollama, vLLM).
+    Http,
+}
+
+/// How an idle engine is put to rest.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SuspendMode {
+    /// Ask the engine for a state dump, then SIGTERM (SIGKILL after the grace period).
+    Terminate,
+    /// Freeze the process with SIGSTOP and resume it with SIGCONT. Unix only;
+    /// falls back to `Terminate` elsewhere.
+    Freeze,
+}
+
+/// Runtime and resource limits applied when the AI engine process is spawned.
+///
+/// Stored per agency in `engine_configs` as serialized JSON so new fields can be
+/// added without a schema change.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EngineConfig {
+    /// Engine implementation to run.
+    pub backend: EngineBackend,
+    /// Runtime used to launch the engine, one of `ALLOWED_RUNTIMES`.
+    pub runtime: String,
+    /// Entry script, relative to the bundled resource directory.
+    pub entry_script: String,
+    /// Extra runtime arguments placed before the entry script, from `ALLOWED_ARGS`.
+    pub args: Vec<String>,
+    /// Host environment variables forwarded to the engine. Everything else is cleared.
+    pub env_allowlist: Vec<String>,
+    /// V8 old-space heap limit in megabytes (`--max-old-space-size`).
+    pub heap_limit_mb: u32,
+    /// Idle time before the engine is suspended, in seconds. `0` disables suspension.
+    pub idle_timeout_secs: u64,
+    /// Working directory for the engine process, inside the resource or data
+    /// directory. Inherits the kernel's when unset.
+    pub working_dir: Option<String>,
+    /// Strategy used when the idle timeout expires.
+    pub suspend_mode: SuspendMode,
+    /// Seconds granted for the state dump and again for SIGTERM before escalating.
+    pub suspend_grace_secs: u64,
+    /// Fixture replayed by the mock backend, inside the resource or data directory.
+    /// Uses the built-in script when unset.
+    pub mock_fixture: Option<String>,
+    /// Base URL of the OpenAI-compatible API. Must point at the local machine.
+    pub http_endpoint: String,
+    /// Model requested from the HTTP backend.
+    pub model: String,
+    /// Vault alias holding the bearer token for the HTTP backend. The key itself
+    /// never leaves the vault.
+    pub api_key_alias: Option<String>,
+}
+
+impl Default for EngineConfig {
+    fn default() -> Self {
+        Self {
+            backend: EngineBackend::OpenClaw,
+            runtime: "node".to_string(),
+            entry_script: DEFAULT_ENTRY_SCRIPT.to_string(),
+            args: vec!["--no-warnings".to_string()],
+            env_allowlist: vec!["PATH".to_string(), "HOME".to_string(), "LANG".to_string()],
+            heap_limit_mb: 128,
+            idle_timeout_secs: 300,
+            working_dir: None,
+            suspend_mode: SuspendMode::Terminate,
+            suspend_grace_secs: 5,
+            mock_fixture: None,
+            http_endpoint: "http://127.0.0.1:8080/v1".to_string(),
+            model: "local".to_string(),
+            api_key_alias: None,
+        }
+    }
+}
+
+impl EngineConfig {
+    /// Checks the configuration for values that would produce an unsafe or broken engine.
+    pub fn validate(&self) -> Result<(), String> {
+        if !ALLOWED_RUNTIMES.contains(&self.runtime.as_str()) {
+            return Err(format!("Engine runtime must be one of {}: {}", ALLOWED_RUNTIMES.join(", "), self.runtime));
+        }
+        if self.entry_script.trim().is_empty() {
+            return Err("Engine entry script must not be empty".to_string());
+        }
+        if Path::new(&self.entry_script).components().any(|c| c == Component::ParentDir) {
+            return Err(format!("Path traversal detected in entry script: {}", self.entry_script));
+        }
+        if Path::new(&self.entry_script).has_root() {
+            return Err(format!("Entry script must be relative to the resource directory: {}", self.entry_script));
+        }
+        if !(32..=4096).contains(&self.heap_limit_mb) {
+            return Err(format!("Heap limit must be between 32 and 4096 MB, got {}", self.heap_limit_mb));
+        }
+        if self.idle_timeout_secs != 0 && self.idle_timeout_secs < 30 {
+            return Err("Idle timeout must be 0 (disabled) or at least 30 seconds".to_string());
+        }
+        if !(1..=60).contains(&self.suspend_grace_secs) {
+            return Err(format!("Suspend grace must be between 1 and 60 seconds, got {}", self.suspend_grace_secs));
+        }
+        if let Some(arg) = self.args.iter().find(|a| a.starts_with("--max-old-space-size")) {
+            return Err(format!("Heap size is managed by heap_limit_mb, remove argument: {}", arg));
+        }
+        if let Some(arg) = self.args.iter().find(|a| !ALLOWED_ARGS.contains(&a.as_str())) {
+            return Err(format!("Runtime argument not allowed: {}", arg));
+        }
+        for name in &self.env_allowlist {
+            let valid = !name.is_empty()
+                && !name.starts_with(|c: char| c.is_ascii_digit())
+                && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+            if !valid {
+                return Err(format!("Invalid environment variable name: {}", name));
+            }
+        }
+        if let Some(fixture) = &self.mock_fixture {
+            if !Path::new(fixture).is_file() {
+                return Err(format!("Mock fixture not found: {}", fixture));
+            }
+        }
+        let url = Url::parse(&self.http_endpoint)
+            .map_err(|e| format!("Invalid HTTP endpoint {}: {}", self.http_endpoint, e))?;
+        let loopback = match url.host_str() {
+            Some("localhost") => true,
+            Some(host) => host.trim_matches(|c| c == '[' || c == ']').parse::<IpAddr>().is_ok_and(|ip| ip.is_loopback()),
+            None => false,
+        };
+        if url.scheme() != "http" && url.scheme() != "https" || !loopback {
+            return Err(format!("HTTP endpoint must be a localhost URL: {}", self.http_endpoint));
+        }
+        if self.model.trim().is_empty() {
+            return Err("Model must not be empty".to_string());
+        }
+        if let Some(dir) = &self.working_dir {
+            let path = Path::new(dir);
+            if !path.is_absolute() || !path.is_dir() {
+                return Err(format!("Working directory must be an existing absolute path: {}", dir));
+            }
+        }
+        Ok(())
+    }
+
+    /// The entry script inside `resource_dir`, with symlinks resolved.
+    pub fn entry_script_path(&self, resource_dir: &Path) -> Result<PathBuf, String> {
+        let resources = resource_dir.canonicalize().map_err(|e| format!("Failed to resolve resources {:?}: {}", resource_dir, e))?;
+        let path = resources.join(&self.entry_script).canonicalize()
+            .map_err(|e| format!("Engine entry script not found: {} ({})", self.entry_script, e))?;
+        if !path.starts_with(&resources) || !path.is_file() {
+            return Err(format!("Engine entry script must be a file inside the resource directory: {}", self.entry_script));
+        }
+        Ok(path)
+    }
+
+    /// Checks that the mock fixture and working directory lie inside
+    /// `resource_dir` or `data_dir`, with symlinks resolved.
+    pub fn check_paths(&self, resource_dir: &Path, data_dir: &Path) -> Result<(), String> {
+        let roots: Vec<PathBuf> = [resource_dir, data_dir].iter().filter_map(|dir| dir.canonicalize().ok()).collect();
+        let inside = |path: &str| Path::new(path).canonicalize().is_ok_and(|path| roots.iter().any(|root| path.starts_with(root)));
+        if let Some(fixture) = self.mock_fixture.as_deref().filter(|f| !inside(f)) {
+            return Err(format!("Mock fixture must be inside the resource or data directory: {}", fixture));
+        }
+        if let Some(dir) = self.working_dir.as_deref().filter(|d| !inside(d)) {
+            return Err(format!("Working directory must be inside the resource or data directory: {}", dir));
+        }
+        Ok(())
+    }
+}
+
+/// Loads the engine configuration of an agency, falling back to the defaults
+/// when none has been stored yet.
+pub async fn load(pool: &Pool<Sqlite>, agency_id: &str) -> Result<EngineConfig, String> {
+    let row: Option<(String,)> = sqlx::query_as("SELECT config FROM engine_configs WHERE agency_id = ?")
+        .bind(agency_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    match row {
+        Some((json,)) => serde_json::from_str(&json).map_err(|e| format!("Corrupt engine config for {}: {}", agency_id, e)),
+        None => Ok(EngineConfig::default()),
+    }
+}
+
+/// Persists the engine configuration of an agency, replacing any previous one.
+pub async fn save(pool: &Pool<Sqlite>, agency_id: &str, config: &EngineConfig) -> Result<(), String> {
+    let json = serde_json::to_string(config).map_err(|e| e.to_string())?;
+    let timestamp = chrono::Utc::now().to_rfc3339();
+
+    sqlx::query("INSERT OR REPLACE INTO engine_configs (agency_id, config, updated_at) VALUES (?, ?, ?)")
+        .bind(agency_id)
+        .bind(json)
+        .bind(timestamp)
+        .execute(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Loads and validates the configuration of an agency, checking its paths
+/// against `resource_dir` and `data_dir`.
+///
+/// An invalid stored configuration is audited and replaced by the defaults so the
+/// kernel can still boot.
+pub async fn load_validated(pool: &Pool<Sqlite>, agency_id: &str, resource_dir: &Path, data_dir: &Path) -> EngineConfig {
+    let result = load(pool, agency_id).await
+        .and_then(|config| config.validate().and_then(|_| config.check_paths(resource_dir, data_dir)).map(|_| config));
+    match result {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("[KORA] Invalid engine config for {}: {}. Using defaults.", agency_id, e);
+            let _ = audit::log_event(pool, "ENGINE_CONFIG_INVALID", "RING_1", &e, agency_id).await;
+            EngineConfig::default()
+        }
+    }
+}
+
+/// Returns the engine configuration of the active agency.
+#[tauri::command]
+pub async fn kora_engine_config_get(state: tauri::State<'_, AppState>) -> Result<EngineConfig, String> {
+    let agency_id = state.governance.get_active_agency_id();
+    load(&state.db, &agency_id).await
+}
+
+/// Validates and stores a new engine configuration for the active agency,
+/// restarting the engine so the new limits take effect.
+#[tauri::command]
+pub async fn kora_engine_config_set(state: tauri::State<'_, AppState>, config: EngineConfig) -> Result<String, String> {
+    let agency_id = state.governance.get_active_agency_id();
+    config.validate()?;
+    config.check_paths(state.ai_engine.resource_dir(), &state.data_dir)?;
+    if config.backend == EngineBackend::OpenClaw {
+        config.entry_script_path(state.ai_engine.resource_dir())?;
+    }
+
+    save(&state.db, &agency_id, &config).await?;
+    let _ = audit::log_event(
+        &state.db,
+        "ENGINE_CONFIG_UPDATE",
+        "RING_0",
+        &format!("backend={:?} runtime={} heap={}MB idle={}s", config.backend, config.runtime, config.heap_limit_mb, config.idle_timeout_secs),
+        &agency_id,
+    ).await;
+
+    state.ai_engine.apply_config(config)?;
+    state.ai_engine.restart()?;
+
+    Ok(format!("Engine config updated for {}", agency_id))
+}
+
+/// Stores the HTTP backend's API key in the vault under `alias`.
+///
+/// Only the alias is persisted in the engine config or written to the audit log.
+#[tauri::command]
+pub async fn kora_engine_set_api_key(state: tauri::State<'_, AppState>, alias: String, key: String) -> Result<String, String> {
+    let agency_id = state.governance.get_active_agency_id();
+    if alias.trim().is_empty() || key.is_empty() {
+        return Err("Alias and key must not be empty".to_string());
+    }
+
+    state.vault.set_secret(&alias, &key);
+    let _ = audit::log_event(&state.db, "VAULT_KEY_SET", "RING_0", &format!("alias={}", alias), &agency_id).await;
+
+    Ok(format!("Key stored as {}", alias))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_config_is_valid() {
+        assert!(EngineConfig::default().validate().is_ok());
+    }
+
+    #[test]
+    fn test_rejects_out_of_range_limits() {
+        let mut config = EngineConfig { heap_limit_mb: 8, ..Default::default() };
+        assert!(config.validate().is_err());
+
+        config.heap_limit_mb = 256;
+        config.idle_timeout_secs = 5;
+        assert!(config.validate().is_err());
+
+        config.idle_timeout_secs = 0;
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_rejects_unsafe_values() {
+        let config = EngineConfig { env_allowlist: vec!["PATH;rm".to_string()], ..Default::default() };
+        assert!(config.validate().is_err());
+
+        let config = EngineConfig { args: vec!["--max-old-space-size=4096".to_string()], ..Default::default() };
+        assert!(config.validate().is_err());
+
+        let config = EngineConfig { entry_script: "../../etc/passwd".to_string(), ..Default::default() };
+        assert!(config.validate().is_err());
+
+        let config = EngineConfig { entry_script: "/tmp/payload.mjs".to_string(), ..Default::default() };
+        assert!(config.validate().is_err());
+
+        let config = EngineConfig { runtime: "/bin/sh".to_string(), ..Default::default() };
+        assert!(config.validate().is_err());
+
+        let config = EngineConfig { args: vec!["--require=/tmp/payload.js".to_string()], ..Default::default() };
+        assert!(config.validate().is_err());
+
+        let config = EngineConfig { runtime: "bun".to_string(), args: vec!["--enable-source-maps".to_string()], ..Default::default() };
+        assert!(config.validate().is_ok());
+
+        let config = EngineConfig { working_dir: Some("relative/dir".to_string()), ..Default::default() };
+        assert!(config.validate().is_err());
+
+        let config = EngineConfig { http_endpoint: "http://api.example.com/v1".to_string(), ..Default::default() };
+        assert!(config.validate().is_err());
+
+        let config = EngineConfig { http_endpoint: "http://[::1]:11434/v1".to_string(), ..Default::default() };
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_entry_script_stays_in_resources() {
+        let dir = std::env::temp_dir().join(format!("kora-resources-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(dir.join("resources/engine")).unwrap();
+        std::fs::write(dir.join("resources/engine/main.mjs"), "").unwrap();
+        std::fs::write(dir.join("outside.mjs"), "").unwrap();
+        let resources = dir.join("resources");
+
+        let config = EngineConfig { entry_script: "engine/main.mjs".to_string(), ..Default::default() };
+        assert_eq!(config.entry_script_path(&resources).unwrap(), resources.canonicalize().unwrap().join("engine/main.mjs"));
+        let config = EngineConfig { entry_script: "engine/missing.mjs".to_string(), ..Default::default() };
+        assert!(config.entry_script_path(&resources).is_err());
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink(dir.join("outside.mjs"), resources.join("link.mjs")).unwrap();
+            let config = EngineConfig { entry_script: "link.mjs".to_string(), ..Default::default() };
+            assert!(config.entry_script_path(&resources).is_err());
+        }
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_fixture_and_working_dir_stay_in_bundle_or_data() {
+        let dir = std::env::temp_dir().join(format!("kora-engine-paths-{}", uuid::Uuid::new_v4()));
+        let (resources, data) = (dir.join("resources"), dir.join("data"));
+        std::fs::create_dir_all(resources.join("fixtures")).unwrap();
+        std::fs::create_dir_all(data.join("engine")).unwrap();
+        std::fs::write(resources.join("fixtures/demo.jsonl"), "").unwrap();
+        std::fs::write(dir.join("outside.jsonl"), "").unwrap();
+        let path = |p: PathBuf| Some(p.to_string_lossy().to_string());
+
+        let config = EngineConfig { mock_fixture: path(resources.join("fixtures/demo.jsonl")), working_dir: path(data.join("engine")), ..Default::default() };
+        assert!(config.check_paths(&resources, &data).is_ok());
+        let config = EngineConfig { mock_fixture: path(dir.join("outside.jsonl")), ..Default::default() };
+        assert!(config.check_paths(&resources, &data).is_err());
+        let config = EngineConfig { working_dir: path(dir.clone()), ..Default::default() };
+        assert!(config.check_paths(&resources, &data).is_err());
+        let config = EngineConfig { working_dir: path(data.join("engine/../..")), ..Default::default() };
+        assert!(config.check_paths(&resources, &data).is_err());
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink(dir.join("outside.jsonl"), data.join("link.jsonl")).unwrap();
+            let config = EngineConfig { mock_fixture: path(data.join("link.jsonl")), ..Default::default() };
+            assert!(config.check_paths(&resources, &data).is_err());
+        }
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_partial_json_uses_defaults() {
+        let config: EngineConfig = serde_json::from_str(r#"{"heap_limit_mb": 256, "suspend_mode": "freeze"}"#).unwrap();
+        assert_eq!(config.heap_limit_mb, 256);
+        assert_eq!(config.runtime, "node");
+        assert_eq!(config.suspend_mode, SuspendMode::Freeze);
+        assert_eq!(config.suspend_grace_secs, 5);
+        assert_eq!(config.backend, EngineBackend::OpenClaw);
+    }
+}
@@ -0,0 +1,282 @@
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, RwLock};
+use tauri::async_runtime::JoinHandle;
+use tokio::sync::broadcast;
+use crate::security::vault::SecretVault;
+use super::config::EngineConfig;
+use super::{AiEngine, EngineEvent, EngineMessage};
+
+/// Engine backed by a local OpenAI-compatible server (llama.cpp, Ollama, vLLM).
+///
+/// Each command becomes a streamed `chat/completions` request whose tokens are
+/// published as `EngineEvent::Token` under its request id. The bearer token is read from the vault
+/// at request time and is never part of the configuration or any event.
+#[derive(Clone)]
+pub struct HttpEngine {
+    client: reqwest::Client,
+    config: Arc<RwLock<EngineConfig>>,
+    events: broadcast::Sender<EngineMessage>,
+    vault: SecretVault,
+    /// Streaming tasks keyed by request id.
+    inflight: Arc<Mutex<HashMap<String, JoinHandle<()>>>>,
+}
+
+/// One parsed server-sent event line of a streamed completion.
+#[derive(Debug, PartialEq)]
+enum SseItem {
+    Token(String),
+    Done,
+}
+
+impl HttpEngine {
+    pub fn new(config: EngineConfig, events: broadcast::Sender<EngineMessage>, vault: SecretVault) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            config: Arc::new(RwLock::new(config)),
+            events,
+            vault,
+            inflight: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    fn completions_url(config: &EngineConfig) -> String {
+        format!("{}/chat/completions", config.http_endpoint.trim_end_matches('/'))
+    }
+
+    /// Streams one completion, publishing tokens as they arrive.
+    async fn complete(self, request_id: String, prompt: String) -> Result<(), String> {
+        let config = self.config.read().map_err(|e| e.to_string())?.clone();
+        let body = serde_json::json!({
+            "model": config.model,
+            "stream": true,
+            "messages": chat_messages(&prompt),
+        });
+
+        let mut request = self.client
+            .post(Self::completions_url(&config))
+            .header("Content-Type", "application/json")
+            .body(body.to_string());
+        if let Some(key) = config.api_key_alias.as_deref().and_then(|alias| self.vault.get_secret(alias)) {
+            request = request.bearer_auth(key);
+        }
+
+        // Errors are mapped without the request so the bearer token never leaks into messages
+        let mut response = request.send().await.map_err(|e| format!("HTTP engine unreachable: {}", e.without_url()))?;
+        if !response.status().is_success() {
+            return Err(format!("HTTP engine returned {}", response.status()));
+        }
+
+        let mut pending = Vec::new();
+        while let Some(chunk) = response.chunk().await.map_err(|e| format!("HTTP stream interrupted: {}", e.without_url()))? {
+            pending.extend_from_slice(&chunk);
+            while let Some(pos) = pending.iter().position(|b| *b == b'\n') {
+                let line: Vec<u8> = pending.drain(..=pos).collect();
+                match parse_sse_line(&String::from_utf8_lossy(&line)) {
+                    Some(SseItem::Token(token)) => { let _ = self.events.send(EngineMessage::new(Some(&request_id), EngineEvent::Token(token))); }
+                    Some(SseItem::Done) => return Ok(()),
+                    None => {}
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl AiEngine for HttpEngine {
+    fn name(&self) -> &'static str {
+        "http"
+    }
+
+    fn apply_config(&self, config: EngineConfig) -> Result<(), String> {
+        let mut w = self.config.write().map_err(|e| e.to_string())?;
+        *w = config;
+        Ok(())
+    }
+
+    /// There is no process to start; the server is managed outside the kernel.
+    fn spawn(&self) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn send(&self, request_id: &str, command: &str) -> Result<(), String> {
+        let engine = self.clone();
+        let events = self.events.clone();
+        let inflight = self.inflight.clone();
+        let id = request_id.to_string();
+        let prompt = command.to_string();
+        // Spawned under the lock so the task cannot remove its handle before it is inserted
+        let mut running = self.inflight.lock().map_err(|e| e.to_string())?;
+        running.retain(|_, h| !h.inner().is_finished());
+        let handle = tauri::async_runtime::spawn(async move {
+            if let Err(e) = engine.complete(id.clone(), prompt).await {
+                let _ = events.send(EngineMessage::new(Some(&id), EngineEvent::Error(e)));
+            }
+            let _ = events.send(EngineMessage::new(Some(&id), EngineEvent::Done));
+            inflight.lock().unwrap().remove(&id);
+        });
+        running.insert(request_id.to_string(), handle);
+        Ok(())
+    }
+
+    /// Aborts the streaming task; the connection is dropped with it.
+    fn cancel(&self, request_id: &str) -> Result<(), String> {
+        if let Some(handle) = self.inflight.lock().map_err(|e| e.to_string())?.remove(request_id) {
+            handle.abort();
+        }
+        Ok(())
+    }
+
+    fn subscribe(&self) -> broadcast::Receiver<EngineMessage> {
+        self.events.subscribe()
+    }
+
+    /// Aborts every request still streaming, closing each with an error.
+    fn shutdown(&self) -> Result<(), String> {
+        let mut inflight = self.inflight.lock().map_err(|e| e.to_string())?;
+        for (id, handle) in inflight.drain() {
+            handle.abort();
+            let _ = self.events.send(EngineMessage::new(Some(&id), EngineEvent::Error("Engine stopped".to_string())));
+            let _ = self.events.send(EngineMessage::new(Some(&id), EngineEvent::Done));
+        }
+        Ok(())
+    }
+}
+
+/// Maps a kernel command onto chat messages.
+///
+/// Grounded `KNOWLEDGE <question> CONTEXT <excerpts>` commands put the excerpts
+/// in a system message so the model can cite them by their `[n]` labels.
+fn chat_messages(prompt: &str) -> Value {
+    if let Some((question, context)) = prompt.strip_prefix("KNOWLEDGE ").and_then(|p| p.split_once(" CONTEXT ")) {
+        return serde_json::json!([
+            { "role": "system", "content": format!("Answer using only these numbered excerpts and cite them as [n]: {}", context) },
+            { "role": "user", "content": question },
+        ]);
+    }
+    if let Some((name, sample)) = prompt.strip_prefix("CLASSIFY ").and_then(|p| p.split_once(" SAMPLE ")) {
+        return serde_json::json!([
+            { "role": "system", "content": "Classify the knowledge file into exactly one folder and answer with that single word: core (master rules, agent identities, principles), archive (raw data, transcripts, extracted documents) or dist (structured analyses ready for execution)." },
+            { "role": "user", "content": format!("File: {}\nExcerpt: {}", name, sample) },
+        ]);
+    }
+    serde_json::json!([{ "role": "user", "content": prompt }])
+}
+
+/// Parses one `data:` line of an OpenAI-style SSE stream.
+fn parse_sse_line(line: &str) -> Option<SseItem> {
+    let data = line.trim().strip_prefix("data:")?.trim();
+    if data == "[DONE]" {
+        return Some(SseItem::Done);
+    }
+    let value: Value = serde_json::from_str(data).ok()?;
+    let token = value["choices"][0]["delta"]["content"].as_str()?;
+    if token.is_empty() {
+        return None;
+    }
+    Some(SseItem::Token(token.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// Serves one canned SSE completion and returns the raw request it received.
+    async fn stub_server(tokens: &[&str]) -> (String, tokio::task::JoinHandle<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let endpoint = format!("http://{}/v1", listener.local_addr().unwrap());
+        let mut body = String::new();
+        for token in tokens {
+            body.push_str(&format!("data: {}\n\n", serde_json::json!({ "choices": [{ "delta": { "content": token } }] })));
+        }
+        body.push_str("data: [DONE]\n\n");
+
+        let server = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0u8; 4096];
+            loop {
+                let n = socket.read(&mut buf).await.unwrap();
+                request.extend_from_slice(&buf[..n]);
+                let text = String::from_utf8_lossy(&request);
+                if let Some(header_end) = text.find("\r\n\r\n") {
+                    let length = text.lines()
+                        .find_map(|l| l.to_ascii_lowercase().strip_prefix("content-length:").map(|v| v.trim().parse::<usize>().unwrap()))
+                        .unwrap_or(0);
+                    if request.len() >= header_end + 4 + length {
+                        break;
+                    }
+                }
+            }
+            let response = format!("HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nConnection: close\r\n\r\n{}", body);
+            socket.write_all(response.as_bytes()).await.unwrap();
+            socket.shutdown().await.unwrap();
+            String::from_utf8_lossy(&request).to_string()
+        });
+        (endpoint, server)
+    }
+
+    #[test]
+    fn test_parse_sse_line() {
+        assert_eq!(parse_sse_line(r#"data: {"choices":[{"delta":{"content":"Hi"}}]}"#), Some(SseItem::Token("Hi".into())));
+        assert_eq!(parse_sse_line("data: [DONE]"), Some(SseItem::Done));
+        assert_eq!(parse_sse_line(": keep-alive"), None);
+    }
+
+    #[test]
+    fn test_chat_messages_split_grounded_context() {
+        let messages = chat_messages("KNOWLEDGE what is kora CONTEXT [1] Kora is a kernel.");
+        assert_eq!(messages[0]["role"], "system");
+        assert!(messages[0]["content"].as_str().unwrap().ends_with("[1] Kora is a kernel."));
+        assert_eq!(messages[1]["content"], "what is kora");
+
+        assert_eq!(chat_messages("SYSTEM status"), serde_json::json!([{ "role": "user", "content": "SYSTEM status" }]));
+    }
+
+    #[tokio::test]
+    async fn test_streams_tokens_from_local_server() {
+        let (endpoint, server) = stub_server(&["Hel", "lo"]).await;
+        let (events, mut rx) = broadcast::channel(16);
+        let vault = SecretVault::new();
+        vault.set_secret("LLM_KEY", "sk-local-secret");
+        let config = EngineConfig {
+            http_endpoint: endpoint,
+            model: "qwen2.5:0.5b".to_string(),
+            api_key_alias: Some("LLM_KEY".to_string()),
+            ..Default::default()
+        };
+
+        let engine = HttpEngine::new(config, events, vault);
+        engine.clone().complete("req-1".to_string(), "ping".to_string()).await.unwrap();
+
+        let request = server.await.unwrap();
+        assert!(request.contains("\"model\":\"qwen2.5:0.5b\""));
+        assert!(request.contains("authorization: Bearer sk-local-secret") || request.contains("Authorization: Bearer sk-local-secret"));
+        assert_eq!(rx.try_recv().unwrap(), EngineMessage::new(Some("req-1"), EngineEvent::Token("Hel".into())));
+        assert_eq!(rx.try_recv().unwrap(), EngineMessage::new(Some("req-1"), EngineEvent::Token("lo".into())));
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_closes_requests_in_flight() {
+        // Accepts the connection and never answers
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let endpoint = format!("http://{}/v1", listener.local_addr().unwrap());
+        let server = tokio::spawn(async move {
+            let (_socket, _) = listener.accept().await.unwrap();
+            std::future::pending::<()>().await;
+        });
+        let (events, mut rx) = broadcast::channel(16);
+        let engine = HttpEngine::new(EngineConfig { http_endpoint: endpoint, ..Default::default() }, events, SecretVault::new());
+
+        engine.send("req-1", "ping").unwrap();
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        engine.shutdown().unwrap();
+        assert_eq!(rx.recv().await.unwrap(), EngineMessage::new(Some("req-1"), EngineEvent::Error("Engine stopped".into())));
+        assert_eq!(rx.recv().await.unwrap(), EngineMessage::new(Some("req-1"), EngineEvent::Done));
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        assert!(rx.try_recv().is_err());
+        server.abort();
+    }
+}
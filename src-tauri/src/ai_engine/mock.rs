@@ -0,0 +1,217 @@
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use tokio::sync::broadcast;
+use super::{AiEngine, EngineEvent, EngineMessage};
+
+/// Script bundled with the kernel and used when no fixture file is configured.
+const BUILTIN_SCRIPT: &str = include_str!("../../fixtures/mock_engine.json");
+
+/// A canned exchange: any command starting with `prefix` replays `lines`.
+#[derive(Clone, Debug, Deserialize)]
+pub struct MockRule {
+    pub prefix: String,
+    #[serde(default)]
+    pub lines: Vec<String>,
+    #[serde(default)]
+    pub errors: Vec<String>,
+    /// Pause between emitted lines, in milliseconds.
+    #[serde(default)]
+    pub delay_ms: u64,
+}
+
+/// Fixture file format for the mock engine. Rules are matched in order.
+#[derive(Clone, Debug, Deserialize)]
+pub struct MockScript {
+    #[serde(default)]
+    pub greeting: Option<String>,
+    #[serde(default)]
+    pub rules: Vec<MockRule>,
+    /// Error lines emitted when no rule matches.
+    #[serde(default)]
+    pub fallback_errors: Vec<String>,
+}
+
+/// In-process engine that replays a scripted conversation. Used by tests and demo mode.
+#[derive(Clone)]
+pub struct MockEngine {
+    script: Arc<MockScript>,
+    events: broadcast::Sender<EngineMessage>,
+    running: Arc<AtomicBool>,
+    received: Arc<Mutex<Vec<String>>>,
+    /// Cancellation flags of delayed replays still running.
+    replaying: Arc<Mutex<HashMap<String, Arc<AtomicBool>>>>,
+}
+
+impl MockEngine {
+    pub fn new(script: MockScript, events: broadcast::Sender<EngineMessage>) -> Self {
+        Self {
+            script: Arc::new(script),
+            events,
+            running: Arc::new(AtomicBool::new(false)),
+            received: Arc::new(Mutex::new(Vec::new())),
+            replaying: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Loads the script from a JSON fixture, or the built-in script when `path` is `None`.
+    pub fn from_fixture(path: Option<&Path>, events: broadcast::Sender<EngineMessage>) -> Result<Self, String> {
+        let json = match path {
+            Some(p) => std::fs::read_to_string(p).map_err(|e| format!("Failed to read mock fixture: {}", e))?,
+            None => BUILTIN_SCRIPT.to_string(),
+        };
+        let script: MockScript = serde_json::from_str(&json).map_err(|e| format!("Invalid mock fixture: {}", e))?;
+        Ok(Self::new(script, events))
+    }
+
+    /// Commands received so far, in order.
+    #[cfg(test)]
+    pub fn received(&self) -> Vec<String> {
+        self.received.lock().unwrap().clone()
+    }
+}
+
+impl AiEngine for MockEngine {
+    fn name(&self) -> &'static str {
+        "mock"
+    }
+
+    fn spawn(&self) -> Result<(), String> {
+        if !self.running.swap(true, Ordering::SeqCst) {
+            if let Some(greeting) = &self.script.greeting {
+                let _ = self.events.send(EngineMessage::new(None, EngineEvent::Output(greeting.clone())));
+            }
+        }
+        Ok(())
+    }
+
+    fn send(&self, request_id: &str, command: &str) -> Result<(), String> {
+        self.spawn()?;
+        self.received.lock().unwrap().push(command.to_string());
+
+        let rule = self.script.rules.iter().find(|r| command.starts_with(&r.prefix)).cloned();
+        let events = self.events.clone();
+        let id = request_id.to_string();
+        let publish = move |event| { let _ = events.send(EngineMessage::new(Some(&id), event)); };
+        let Some(rule) = rule else {
+            for line in &self.script.fallback_errors {
+                publish(EngineEvent::Error(format!("{}: {}", line, command)));
+            }
+            publish(EngineEvent::Done);
+            return Ok(());
+        };
+
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let replay = {
+            let cancelled = cancelled.clone();
+            move || {
+                let delay = Duration::from_millis(rule.delay_ms);
+                for line in rule.lines {
+                    if !delay.is_zero() {
+                        thread::sleep(delay);
+                    }
+                    if cancelled.load(Ordering::SeqCst) {
+                        return;
+                    }
+                    publish(EngineEvent::Output(line));
+                }
+                for line in rule.errors {
+                    publish(EngineEvent::Error(line));
+                }
+                publish(EngineEvent::Done);
+            }
+        };
+        if rule.delay_ms == 0 {
+            replay();
+        } else {
+            let replaying = self.replaying.clone();
+            let id = request_id.to_string();
+            replaying.lock().unwrap().insert(id.clone(), cancelled);
+            thread::spawn(move || {
+                replay();
+                replaying.lock().unwrap().remove(&id);
+            });
+        }
+        Ok(())
+    }
+
+    /// Stops a delayed replay; finished requests leave nothing behind.
+    fn cancel(&self, request_id: &str) -> Result<(), String> {
+        if let Some(cancelled) = self.replaying.lock().unwrap().get(request_id) {
+            cancelled.store(true, Ordering::SeqCst);
+        }
+        Ok(())
+    }
+
+    fn subscribe(&self) -> broadcast::Receiver<EngineMessage> {
+        self.events.subscribe()
+    }
+
+    fn shutdown(&self) -> Result<(), String> {
+        self.running.store(false, Ordering::SeqCst);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture_engine() -> MockEngine {
+        let (tx, _) = broadcast::channel(16);
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures/mock_engine.json");
+        MockEngine::from_fixture(Some(&path), tx).unwrap()
+    }
+
+    #[test]
+    fn test_replays_matching_rule() {
+        let engine = fixture_engine();
+        let mut rx = engine.subscribe();
+
+        engine.send("req-1", "SYSTEM status").unwrap();
+
+        assert_eq!(rx.try_recv().unwrap().event, EngineEvent::Output("OpenClaw Engine v0.1 (Mock) Initialized.".into()));
+        assert_eq!(rx.try_recv().unwrap(), EngineMessage::new(Some("req-1"), EngineEvent::Output("System Acknowledged: status".into())));
+        assert!(matches!(rx.try_recv().unwrap().event, EngineEvent::Output(l) if l.starts_with("Bridge: ONLINE")));
+        assert_eq!(rx.try_recv().unwrap().event, EngineEvent::Done);
+        assert_eq!(engine.received(), vec!["SYSTEM status".to_string()]);
+    }
+
+    #[test]
+    fn test_unmatched_command_reports_error() {
+        let engine = fixture_engine();
+        engine.spawn().unwrap();
+        let mut rx = engine.subscribe();
+
+        engine.send("req-2", "DANCE").unwrap();
+
+        assert_eq!(rx.try_recv().unwrap().event, EngineEvent::Error("Unknown Protocol: DANCE".into()));
+        assert_eq!(rx.try_recv().unwrap().event, EngineEvent::Done);
+    }
+
+    #[test]
+    fn test_cancel_stops_delayed_replay() {
+        let engine = fixture_engine();
+        engine.spawn().unwrap();
+        let mut rx = engine.subscribe();
+
+        engine.send("req-3", "KNOWLEDGE what is kora").unwrap();
+        engine.cancel("req-3").unwrap();
+        thread::sleep(Duration::from_millis(100));
+
+        assert!(rx.try_recv().is_err());
+        assert!(engine.replaying.lock().unwrap().is_empty());
+        engine.cancel("req-unknown").unwrap();
+        assert!(engine.replaying.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_builtin_script_parses() {
+        let (tx, _) = broadcast::channel(16);
+        assert!(MockEngine::from_fixture(None, tx).is_ok());
+    }
+}
@@ -0,0 +1,399 @@
+pub mod config;
+pub mod http;
+pub mod mock;
+pub mod openclaw;
+pub mod requests;
+
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Runtime};
+use tokio::sync::broadcast;
+use crate::jail;
+use crate::AppState;
+use crate::security::vault::SecretVault;
+use self::config::{EngineBackend, EngineConfig};
+use self::http::HttpEngine;
+use self::mock::MockEngine;
+use self::openclaw::OpenClawEngine;
+use self::requests::{ActiveRequest, RequestRegistry};
+
+/// Output published by an engine backend to its subscribers.
+#[derive(Clone, Debug, PartialEq)]
+pub enum EngineEvent {
+    /// A line of engine output.
+    Output(String),
+    /// A streamed fragment of a response, without line framing.
+    Token(String),
+    /// A line of engine diagnostics.
+    Error(String),
+    /// The request produced its last chunk.
+    Done,
+    /// The request was cancelled before it finished.
+    Cancelled,
+    /// The engine was put to rest after being idle.
+    Suspended,
+}
+
+/// An engine event, attributed to the request that produced it when known.
+#[derive(Clone, Debug, PartialEq)]
+pub struct EngineMessage {
+    pub request_id: Option<String>,
+    pub event: EngineEvent,
+}
+
+impl EngineMessage {
+    pub fn new(request_id: Option<&str>, event: EngineEvent) -> Self {
+        Self { request_id: request_id.map(str::to_string), event }
+    }
+}
+
+/// Common interface of the AI engine backends.
+///
+/// Implementations must be cheap to share between threads; output is delivered
+/// through `subscribe` rather than returned from `send`.
+pub trait AiEngine: Send + Sync {
+    /// Short backend identifier used in logs and audits.
+    fn name(&self) -> &'static str;
+
+    /// Replaces the runtime configuration. Takes effect on the next spawn.
+    fn apply_config(&self, _config: EngineConfig) -> Result<(), String> {
+        Ok(())
+    }
+
+    /// Starts the engine. Calling it on a running engine is backend-defined.
+    fn spawn(&self) -> Result<(), String>;
+
+    /// Delivers one command, waking the engine if it was suspended. Output is
+    /// published under `request_id` and closed with `EngineEvent::Done`.
+    fn send(&self, request_id: &str, command: &str) -> Result<(), String>;
+
+    /// Stops producing output for a request. The host publishes `Cancelled`.
+    fn cancel(&self, request_id: &str) -> Result<(), String>;
+
+    /// Receives every event published after the call.
+    fn subscribe(&self) -> broadcast::Receiver<EngineMessage>;
+
+    /// Stops the engine, releasing its resources. Requests it had not
+    /// answered are closed with an error and `Done`.
+    fn shutdown(&self) -> Result<(), String>;
+
+    /// Stops the engine and spawns it again with the current configuration.
+    fn restart(&self) -> Result<(), String> {
+        self.shutdown()?;
+        self.spawn()
+    }
+
+    /// Restarts the engine without the session it saved when suspended, so
+    /// nothing carries over when another agency takes over.
+    fn reset(&self) -> Result<(), String> {
+        self.restart()
+    }
+}
+
+/// Owner of the active engine backend.
+///
+/// All backends publish on one shared event bus, so subscribers survive a backend
+/// swap when an agency's configuration selects a different implementation.
+pub struct EngineHost {
+    /// Active backend together with the configuration it was built from.
+    backend: RwLock<(EngineConfig, Arc<dyn AiEngine>)>,
+    events: broadcast::Sender<EngineMessage>,
+    requests: RequestRegistry,
+    resource_dir: PathBuf,
+    vault: SecretVault,
+    /// Demo mode pins the mock backend regardless of configuration.
+    demo: bool,
+}
+
+impl EngineHost {
+    pub fn new(resource_dir: PathBuf, vault: SecretVault, demo: bool) -> Self {
+        let (events, _) = broadcast::channel(256);
+        let mut config = EngineConfig::default();
+        if demo {
+            config.backend = EngineBackend::Mock;
+        }
+        let engine = build_backend(&config, &resource_dir, &vault, events.clone())
+            .expect("built-in engine backend must construct");
+
+        Self {
+            backend: RwLock::new((config, engine)),
+            events,
+            requests: RequestRegistry::default(),
+            resource_dir,
+            vault,
+            demo,
+        }
+    }
+
+    /// The backend currently serving requests.
+    pub fn current(&self) -> Arc<dyn AiEngine> {
+        self.backend.read().unwrap().1.clone()
+    }
+
+    /// Bundled resources the engine entry script must live in.
+    pub fn resource_dir(&self) -> &Path {
+        &self.resource_dir
+    }
+
+    /// Registry of in-flight requests.
+    pub fn requests(&self) -> RequestRegistry {
+        self.requests.clone()
+    }
+
+    /// Sends a command under a fresh request id and returns the id. `session_id`
+    /// is the terminal session it was typed in, if any.
+    pub fn submit(&self, kind: &str, agency_id: &str, session_id: Option<&str>, command: &str) -> Result<String, String> {
+        let request_id = self.requests.begin(kind, agency_id, session_id);
+        if let Err(e) = self.send(&request_id, command) {
+            self.requests.finish(&request_id);
+            return Err(e);
+        }
+        Ok(request_id)
+    }
+
+    /// Submits a command and waits for its whole answer, for kernel-side callers
+    /// (e.g. the knowledge organizer) rather than the UI stream.
+    ///
+    /// Output lines are joined with newlines and tokens concatenated. Fails on an
+    /// engine error, cancellation, or when no `Done` arrives within `timeout`.
+    pub async fn ask(&self, kind: &str, agency_id: &str, command: &str, timeout: Duration) -> Result<String, String> {
+        let mut rx = self.subscribe();
+        let request_id = self.submit(kind, agency_id, None, command)?;
+        let collect = async {
+            let mut answer = String::new();
+            loop {
+                let message = match rx.recv().await {
+                    Ok(message) => message,
+                    Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => return Err("Engine bus closed".to_string()),
+                };
+                if message.request_id.as_deref() != Some(request_id.as_str()) {
+                    continue;
+                }
+                match message.event {
+                    EngineEvent::Output(line) => {
+                        if !answer.is_empty() {
+                            answer.push('\n');
+                        }
+                        answer.push_str(&line);
+                    }
+                    EngineEvent::Token(token) => answer.push_str(&token),
+                    EngineEvent::Error(line) => return Err(line),
+                    EngineEvent::Done => return Ok(answer),
+                    EngineEvent::Cancelled => return Err("Request cancelled".to_string()),
+                    EngineEvent::Suspended => {}
+                }
+            }
+        };
+        match tokio::time::timeout(timeout, collect).await {
+            Ok(result) => result,
+            Err(_) => {
+                let _ = self.cancel_request(&request_id);
+                Err(format!("Engine did not answer within {}s", timeout.as_secs()))
+            }
+        }
+    }
+
+    /// Cancels an in-flight request and returns it, or an error if it already finished.
+    pub fn cancel_request(&self, request_id: &str) -> Result<ActiveRequest, String> {
+        let request = self.requests.cancel(request_id)
+            .ok_or_else(|| format!("No active request {}", request_id))?;
+        let result = self.cancel(request_id);
+        let _ = self.events.send(EngineMessage::new(Some(request_id), EngineEvent::Cancelled));
+        result.map(|_| request)
+    }
+}
+
+impl AiEngine for EngineHost {
+    fn name(&self) -> &'static str {
+        self.current().name()
+    }
+
+    /// Applies the configuration, swapping the backend first if it selects a different one.
+    fn apply_config(&self, mut config: EngineConfig) -> Result<(), String> {
+        if self.demo {
+            config.backend = EngineBackend::Mock;
+        }
+
+        let mut w = self.backend.write().map_err(|e| e.to_string())?;
+        if w.0.backend != config.backend || w.0.mock_fixture != config.mock_fixture {
+            let engine = build_backend(&config, &self.resource_dir, &self.vault, self.events.clone())?;
+            let _ = w.1.shutdown();
+            println!("[KORA] Engine backend switched to {}", engine.name());
+            w.1 = engine;
+        }
+        w.0 = config.clone();
+        w.1.apply_config(config)
+    }
+
+    fn spawn(&self) -> Result<(), String> {
+        self.current().spawn()
+    }
+
+    fn send(&self, request_id: &str, command: &str) -> Result<(), String> {
+        self.current().send(request_id, command)
+    }
+
+    fn cancel(&self, request_id: &str) -> Result<(), String> {
+        self.current().cancel(request_id)
+    }
+
+    fn subscribe(&self) -> broadcast::Receiver<EngineMessage> {
+        self.events.subscribe()
+    }
+
+    fn shutdown(&self) -> Result<(), String> {
+        self.current().shutdown()
+    }
+
+    fn reset(&self) -> Result<(), String> {
+        self.current().reset()
+    }
+}
+
+fn build_backend(config: &EngineConfig, resource_dir: &Path, vault: &SecretVault, events: broadcast::Sender<EngineMessage>) -> Result<Arc<dyn AiEngine>, String> {
+    Ok(match config.backend {
+        EngineBackend::Http => Arc::new(HttpEngine::new(config.clone(), events, vault.clone())),
+        EngineBackend::OpenClaw => Arc::new(OpenClawEngine::new(resource_dir.to_path_buf(), events, config.clone())),
+        EngineBackend::Mock => Arc::new(MockEngine::from_fixture(config.mock_fixture.as_deref().map(Path::new), events)?),
+    })
+}
+
+/// Sends a command to the active engine after jail-checking the paths it references.
+///
+/// Returns the request id under which the response is streamed.
+pub async fn send_command(state: &tauri::State<'_, AppState>, kind: &str, session_id: Option<&str>, command: &str) -> Result<String, String> {
+    check_command_paths(state, command).await?;
+    let agency_id = state.governance.get_active_agency_id();
+    state.ai_engine.submit(kind, &agency_id, session_id, command)
+}
+
+/// Jail-checks every path-like token of user input bound for the engine.
+pub async fn check_command_paths(state: &tauri::State<'_, AppState>, command: &str) -> Result<(), String> {
+    // Security: Validate potential file paths in command before sending
+    // Simple heuristic: if command contains paths, check them against jail
+    if command.contains("/") {
+       // This is a naive check; a robust parser would be better, but fulfills the "Jail Integration" requirement for now
+       // Any command with a path must target a whitelisted directory
+       // We assume the command structure "ACTION PATH ..." or similar
+       let parts: Vec<&str> = command.split_whitespace().collect();
+       for part in parts {
+           if part.starts_with("/") || part.starts_with("./") || part.starts_with("../") {
+               let _ = jail::enforce(state, part, "OPENCLAW_CMD").await?;
+           }
+       }
+    }
+    Ok(())
+}
+
+/// Payload of the per-request `kora-stream-{request_id}` event.
+#[derive(Clone, Debug, Serialize)]
+pub struct StreamChunk {
+    pub request_id: String,
+    /// One of `output`, `token`, `error`, `done` or `cancelled`.
+    pub kind: &'static str,
+    pub text: String,
+}
+
+/// Forwards engine events to the UI.
+///
+/// Request output goes to `kora-stream-{request_id}`, and is mirrored on
+/// `kora-stream` for listeners that subscribe before the id is known.
+/// Unattributed output keeps the legacy `openclaw-*` channels. Output and
+/// the `Done` of cancelled requests are dropped.
+pub fn forward_events<R: Runtime>(app: AppHandle<R>, mut rx: broadcast::Receiver<EngineMessage>, requests: RequestRegistry) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let message = match rx.recv().await {
+                Ok(message) => message,
+                Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => break,
+            };
+
+            let Some(request_id) = message.request_id else {
+                match message.event {
+                    EngineEvent::Output(line) => { let _ = app.emit("openclaw-output", line); }
+                    EngineEvent::Token(token) => { let _ = app.emit("openclaw-token", token); }
+                    EngineEvent::Error(line) => { let _ = app.emit("openclaw-error", line); }
+                    EngineEvent::Suspended => { let _ = app.emit("kora-ai-suspended", true); }
+                    EngineEvent::Done | EngineEvent::Cancelled => {}
+                }
+                continue;
+            };
+
+            let (kind, text) = match message.event {
+                EngineEvent::Output(line) => ("output", line),
+                EngineEvent::Token(token) => ("token", token),
+                EngineEvent::Error(line) => ("error", line),
+                EngineEvent::Done => ("done", String::new()),
+                EngineEvent::Cancelled => ("cancelled", String::new()),
+                EngineEvent::Suspended => continue,
+            };
+            match kind {
+                "cancelled" => requests.bury(&request_id),
+                // The end of a cancelled request; the UI already saw `cancelled`
+                "done" if requests.finish(&request_id) => continue,
+                "done" => {}
+                _ if requests.is_cancelled(&request_id) => continue,
+                _ => {}
+            }
+
+            let chunk = StreamChunk { request_id: request_id.clone(), kind, text };
+            let _ = app.emit(&format!("kora-stream-{}", request_id), chunk.clone());
+            let _ = app.emit("kora-stream", chunk);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn drain(rx: &mut broadcast::Receiver<EngineMessage>) -> Vec<EngineMessage> {
+        let mut received = Vec::new();
+        while let Ok(message) = rx.try_recv() {
+            received.push(message);
+        }
+        received
+    }
+
+    #[test]
+    fn test_demo_mode_pins_mock_backend() {
+        let host = EngineHost::new(PathBuf::new(), SecretVault::new(), true);
+        let mut rx = host.subscribe();
+
+        host.apply_config(EngineConfig::default()).unwrap();
+        assert_eq!(host.name(), "mock");
+
+        let id = host.submit("SYSTEM", "SYSTEM", None, "SYSTEM ping").unwrap();
+        let received = drain(&mut rx);
+        assert!(received.contains(&EngineMessage::new(Some(&id), EngineEvent::Output("System Acknowledged".into()))));
+        assert!(received.contains(&EngineMessage::new(Some(&id), EngineEvent::Done)));
+    }
+
+    #[tokio::test]
+    async fn test_ask_collects_the_answer() {
+        let host = EngineHost::new(PathBuf::new(), SecretVault::new(), true);
+        let answer = host.ask("SYSTEM", "SYSTEM", "SYSTEM status", Duration::from_secs(5)).await.unwrap();
+        assert_eq!(answer, "System Acknowledged: status\nBridge: ONLINE | Engine: MOCK | Memory: nominal");
+        assert!(host.ask("SYSTEM", "SYSTEM", "BOGUS", Duration::from_secs(5)).await.unwrap_err().contains("Unknown Protocol"));
+    }
+
+    #[test]
+    fn test_cancel_request_publishes_cancelled_once() {
+        let host = EngineHost::new(PathBuf::new(), SecretVault::new(), true);
+        let id = host.requests().begin("KNOWLEDGE", "SYSTEM", None);
+        let mut rx = host.subscribe();
+
+        let request = host.cancel_request(&id).unwrap();
+        assert_eq!(request.kind, "KNOWLEDGE");
+        assert!(host.requests().is_cancelled(&id));
+        assert_eq!(drain(&mut rx), vec![EngineMessage::new(Some(&id), EngineEvent::Cancelled)]);
+
+        assert!(host.cancel_request(&id).is_err());
+        assert!(host.cancel_request("unknown").is_err());
+    }
+
+}
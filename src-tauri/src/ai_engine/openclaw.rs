@@ -0,0 +1,425 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::process::{Child, Command, Stdio};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Condvar, Mutex, RwLock, Weak};
+use std::io::{Write, BufReader, BufRead};
+use std::thread;
+use std::time::{Instant, Duration};
+use tokio::sync::broadcast;
+use super::config::{EngineConfig, SuspendMode};
+use super::{AiEngine, EngineEvent, EngineMessage};
+
+/// Interval at which the suspension monitor checks for idleness.
+const MONITOR_TICK: Duration = Duration::from_secs(15);
+
+/// Prefix of the stdout line carrying the engine's serialized state after `SUSPEND`.
+const STATE_DUMP_PREFIX: &str = "STATE_DUMP ";
+
+/// Maps one line of engine output to a message.
+///
+/// Request-scoped lines follow the line protocol `CHUNK <id> <text>` and
+/// `END <id>`; anything else is unattributed output.
+fn parse_line(line: String, diagnostics: bool) -> EngineMessage {
+    let wrap = |text: String| if diagnostics { EngineEvent::Error(text) } else { EngineEvent::Output(text) };
+    if let Some(rest) = line.strip_prefix("CHUNK ") {
+        let (id, text) = rest.split_once(' ').unwrap_or((rest, ""));
+        return EngineMessage::new(Some(id), wrap(text.to_string()));
+    }
+    if let Some(id) = line.strip_prefix("END ") {
+        return EngineMessage::new(Some(id.trim()), EngineEvent::Done);
+    }
+    EngineMessage::new(None, wrap(line))
+}
+
+/// The OpenClaw engine running as a supervised node child process.
+#[derive(Clone)]
+pub struct OpenClawEngine {
+    inner: Arc<Shared>,
+}
+
+struct Shared {
+    process: Mutex<Option<Child>>,
+    resource_dir: PathBuf,
+    events: broadcast::Sender<EngineMessage>,
+    last_activity: Mutex<Instant>,
+    config: RwLock<EngineConfig>,
+    /// Last state dump received from the engine, replayed with `RESTORE` on wake.
+    state_dump: Arc<(Mutex<Option<String>>, Condvar)>,
+    /// True while the process is stopped with SIGSTOP.
+    frozen: AtomicBool,
+    /// Requests awaiting their `END`, by the generation of the process they were sent to.
+    pending: Arc<Mutex<HashMap<String, u64>>>,
+    /// Incremented on every spawn.
+    generation: AtomicU64,
+}
+
+impl OpenClawEngine {
+    /// Creates the engine and starts its single, long-lived suspension monitor.
+    ///
+    /// The monitor only holds a weak reference and exits once the engine is dropped.
+    pub fn new(resource_dir: PathBuf, events: broadcast::Sender<EngineMessage>, config: EngineConfig) -> Self {
+        let inner = Arc::new(Shared {
+            process: Mutex::new(None),
+            resource_dir,
+            events,
+            last_activity: Mutex::new(Instant::now()),
+            config: RwLock::new(config),
+            state_dump: Arc::new((Mutex::new(None), Condvar::new())),
+            frozen: AtomicBool::new(false),
+            pending: Arc::new(Mutex::new(HashMap::new())),
+            generation: AtomicU64::new(0),
+        });
+
+        // Auto-Suspension Monitor (Phase 9)
+        let weak = Arc::downgrade(&inner);
+        thread::spawn(move || monitor_loop(weak));
+
+        Self { inner }
+    }
+
+    pub fn config(&self) -> EngineConfig {
+        self.inner.config.read().unwrap().clone()
+    }
+
+    fn is_running(&self) -> bool {
+        self.inner.process.lock().unwrap().is_some()
+    }
+
+    fn write_line(&self, line: &str) -> Result<(), String> {
+        if let Ok(mut guard) = self.inner.process.lock() {
+            if let Some(child) = guard.as_mut() {
+                if let Some(stdin) = child.stdin.as_mut() {
+                    stdin.write_all(format!("{}\n", line).as_bytes())
+                        .map_err(|e| format!("Failed to write to stdin: {}", e))?;
+                    stdin.flush().map_err(|e| format!("Failed to flush stdin: {}", e))?;
+                    return Ok(());
+                }
+            }
+        }
+        Err("OpenClaw engine not running".to_string())
+    }
+}
+
+impl AiEngine for OpenClawEngine {
+    fn name(&self) -> &'static str {
+        "openclaw"
+    }
+
+    fn apply_config(&self, config: EngineConfig) -> Result<(), String> {
+        let mut w = self.inner.config.write().map_err(|e| e.to_string())?;
+        *w = config;
+        Ok(())
+    }
+
+    fn spawn(&self) -> Result<(), String> {
+        let config = self.config();
+        config.validate()?;
+
+        let engine_path = config.entry_script_path(&self.inner.resource_dir)?;
+
+        println!("[AI DEBUG] Resolved Engine Path: {:?}", engine_path);
+
+        let mut command = Command::new(&config.runtime);
+        command
+            .arg(format!("--max-old-space-size={}", config.heap_limit_mb)) // Strict memory limit
+            .args(&config.args)
+            .arg(engine_path)
+            .env_clear()
+            .envs(config.env_allowlist.iter().filter_map(|name| std::env::var(name).ok().map(|v| (name, v))))
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        if let Some(dir) = &config.working_dir {
+            command.current_dir(dir);
+        }
+
+        let mut child = command
+            .spawn()
+            .map_err(|e| format!("Failed to spawn OpenClaw: {}", e))?;
+
+        println!("[AI DEBUG] Process Spawned. ID: {:?}", child.id());
+
+        let generation = self.inner.generation.fetch_add(1, Ordering::SeqCst) + 1;
+        let events = self.inner.events.clone();
+        let state_dump_ref = self.inner.state_dump.clone();
+        let pending = self.inner.pending.clone();
+        if let Some(stdout) = child.stdout.take() {
+            thread::spawn(move || {
+                let reader = BufReader::new(stdout);
+                for l in reader.lines().map_while(Result::ok) {
+                    if let Some(dump) = l.strip_prefix(STATE_DUMP_PREFIX) {
+                        let (slot, ready) = &*state_dump_ref;
+                        *slot.lock().unwrap() = Some(dump.to_string());
+                        ready.notify_all();
+                        continue;
+                    }
+                    println!("[AI STDOUT] {}", l);
+                    let message = parse_line(l, false);
+                    if let (EngineEvent::Done, Some(id)) = (&message.event, &message.request_id) {
+                        pending.lock().unwrap().remove(id);
+                    }
+                    let _ = events.send(message);
+                }
+
+                // The process is gone: whatever it still owed an answer is closed here
+                let mut abandoned: Vec<String> = Vec::new();
+                pending.lock().unwrap().retain(|id, sent_to| {
+                    if *sent_to == generation {
+                        abandoned.push(id.clone());
+                    }
+                    *sent_to != generation
+                });
+                for id in abandoned {
+                    let _ = events.send(EngineMessage::new(Some(&id), EngineEvent::Error("OpenClaw exited before answering".to_string())));
+                    let _ = events.send(EngineMessage::new(Some(&id), EngineEvent::Done));
+                }
+            });
+        }
+
+        let events_err = self.inner.events.clone();
+        if let Some(stderr) = child.stderr.take() {
+            thread::spawn(move || {
+                let reader = BufReader::new(stderr);
+                for l in reader.lines().map_while(Result::ok) {
+                    eprintln!("[AI STDERR] {}", l);
+                    let _ = events_err.send(parse_line(l, true));
+                }
+            });
+        }
+
+        // Replay the state captured at the last suspension
+        let dump = self.inner.state_dump.0.lock().unwrap().take();
+        if let (Some(dump), Some(stdin)) = (dump, child.stdin.as_mut()) {
+            let _ = stdin.write_all(format!("RESTORE {}\n", dump).as_bytes());
+            let _ = stdin.flush();
+        }
+
+        if let Ok(mut guard) = self.inner.process.lock() {
+            *guard = Some(child);
+        }
+        self.inner.frozen.store(false, Ordering::SeqCst);
+
+        Ok(())
+    }
+
+    fn send(&self, request_id: &str, command: &str) -> Result<(), String> {
+        // A line break would smuggle further protocol lines (`SUSPEND`, `CANCEL <id>`) in
+        if command.contains(['\r', '\n']) {
+            return Err("Engine commands must be a single line".to_string());
+        }
+
+        // Update Activity (Phase 9)
+        if let Ok(mut last) = self.inner.last_activity.lock() {
+            *last = Instant::now();
+        }
+
+        // Resume if frozen, restart if suspended
+        if let Some(child) = self.inner.process.lock().unwrap().as_ref() {
+            if self.inner.frozen.swap(false, Ordering::SeqCst) {
+                println!("[KORA] Thawing OpenClaw...");
+                send_signal(child, Signal::Continue);
+            }
+        }
+        if !self.is_running() {
+            println!("[KORA] Waking up OpenClaw...");
+            self.spawn()?;
+        }
+
+        let generation = self.inner.generation.load(Ordering::SeqCst);
+        self.inner.pending.lock().unwrap().insert(request_id.to_string(), generation);
+        let sent = self.write_line(&format!("REQ {} {}", request_id, command));
+        if sent.is_err() {
+            self.inner.pending.lock().unwrap().remove(request_id);
+        }
+        sent
+    }
+
+    fn cancel(&self, request_id: &str) -> Result<(), String> {
+        self.write_line(&format!("CANCEL {}", request_id))
+    }
+
+    fn subscribe(&self) -> broadcast::Receiver<EngineMessage> {
+        self.inner.events.subscribe()
+    }
+
+    /// Stops the running engine process, if any, using the graceful sequence.
+    fn shutdown(&self) -> Result<(), String> {
+        let grace = Duration::from_secs(self.config().suspend_grace_secs);
+        let child = self.inner.process.lock().map_err(|e| e.to_string())?.take();
+        if let Some(child) = child {
+            self.inner.terminate(child, grace);
+        }
+        Ok(())
+    }
+
+    fn reset(&self) -> Result<(), String> {
+        self.shutdown()?;
+        *self.inner.state_dump.0.lock().unwrap() = None;
+        self.spawn()
+    }
+}
+
+impl Shared {
+    /// Suspends the engine once it has been idle longer than the configured timeout.
+    fn tick(&self) {
+        let config = self.config.read().unwrap().clone();
+        if config.idle_timeout_secs == 0 || self.frozen.load(Ordering::SeqCst) {
+            return;
+        }
+        let last = *self.last_activity.lock().unwrap();
+        if last.elapsed() <= Duration::from_secs(config.idle_timeout_secs) {
+            return;
+        }
+
+        let mut guard = self.process.lock().unwrap();
+        let Some(child) = guard.as_mut() else { return };
+        println!("[KORA] Auto-Suspending OpenClaw (Idle > {}s)", config.idle_timeout_secs);
+
+        if config.suspend_mode == SuspendMode::Freeze && send_signal(child, Signal::Stop) {
+            self.frozen.store(true, Ordering::SeqCst);
+        } else {
+            // Terminating can take two grace periods; sends and spawns must not wait on the lock
+            let child = guard.take();
+            drop(guard);
+            if let Some(child) = child {
+                self.terminate(child, Duration::from_secs(config.suspend_grace_secs));
+            }
+        }
+        let _ = self.events.send(EngineMessage::new(None, EngineEvent::Suspended));
+    }
+
+    /// Graceful stop: `SUSPEND` and wait for the state dump, then SIGTERM, then
+    /// SIGKILL once the grace period runs out.
+    fn terminate(&self, mut child: Child, grace: Duration) {
+        if self.frozen.swap(false, Ordering::SeqCst) {
+            send_signal(&child, Signal::Continue);
+        }
+
+        let (slot, ready) = &*self.state_dump;
+        let mut dump = slot.lock().unwrap();
+        *dump = None;
+        let requested = child.stdin.as_mut()
+            .map(|stdin| stdin.write_all(b"SUSPEND\n").and_then(|_| stdin.flush()).is_ok())
+            .unwrap_or(false);
+        if requested {
+            let (d, _) = ready.wait_timeout_while(dump, grace, |d| d.is_none()).unwrap();
+            dump = d;
+        }
+        if dump.is_none() {
+            eprintln!("[KORA] OpenClaw did not dump its state within {:?}", grace);
+        }
+        drop(dump);
+
+        // Closing stdin lets well-behaved engines exit on their own
+        drop(child.stdin.take());
+        send_signal(&child, Signal::Terminate);
+
+        let deadline = Instant::now() + grace;
+        while Instant::now() < deadline {
+            if let Ok(Some(_)) = child.try_wait() {
+                return;
+            }
+            thread::sleep(Duration::from_millis(100));
+        }
+        eprintln!("[KORA] OpenClaw ignored SIGTERM, killing");
+        let _ = child.kill();
+        let _ = child.wait();
+    }
+}
+
+fn monitor_loop(weak: Weak<Shared>) {
+    loop {
+        thread::sleep(MONITOR_TICK);
+        match weak.upgrade() {
+            Some(shared) => shared.tick(),
+            None => break,
+        }
+    }
+}
+
+/// Process signals used by the suspension sequence.
+#[derive(Clone, Copy)]
+enum Signal {
+    Terminate,
+    Stop,
+    Continue,
+}
+
+/// Delivers a signal to the engine process. Returns false when unsupported.
+#[cfg(unix)]
+fn send_signal(child: &Child, signal: Signal) -> bool {
+    let sig = match signal {
+        Signal::Terminate => libc::SIGTERM,
+        Signal::Stop => libc::SIGSTOP,
+        Signal::Continue => libc::SIGCONT,
+    };
+    unsafe { libc::kill(child.id() as libc::pid_t, sig) == 0 }
+}
+
+#[cfg(not(unix))]
+fn send_signal(_child: &Child, _signal: Signal) -> bool {
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_line_protocol() {
+        assert_eq!(parse_line("CHUNK r1 Hello world".into(), false), EngineMessage::new(Some("r1"), EngineEvent::Output("Hello world".into())));
+        assert_eq!(parse_line("CHUNK r1 oops".into(), true), EngineMessage::new(Some("r1"), EngineEvent::Error("oops".into())));
+        assert_eq!(parse_line("END r1".into(), false), EngineMessage::new(Some("r1"), EngineEvent::Done));
+        assert_eq!(parse_line("Engine ready".into(), false), EngineMessage::new(None, EngineEvent::Output("Engine ready".into())));
+    }
+
+    #[test]
+    fn test_commands_must_be_one_line() {
+        let (events, _) = broadcast::channel(16);
+        let engine = OpenClawEngine::new(std::env::temp_dir().join("kora-no-resources"), events, EngineConfig::default());
+        for command in ["KNOWLEDGE a\nSUSPEND", "SYSTEM status\r\nCANCEL other"] {
+            assert!(engine.send("r1", command).unwrap_err().contains("single line"));
+        }
+        assert!(engine.inner.pending.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_requests_end_when_the_process_exits() {
+        if Command::new("node").arg("--version").output().is_err() {
+            return;
+        }
+        let resources = std::env::temp_dir().join(format!("kora-engine-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&resources).unwrap();
+        // Dies on the first request without answering it
+        std::fs::write(resources.join("crash.mjs"), "process.stdin.once('data', () => process.exit(1));\n").unwrap();
+        let config = EngineConfig { entry_script: "crash.mjs".to_string(), ..Default::default() };
+        let (events, mut rx) = broadcast::channel(16);
+        let engine = OpenClawEngine::new(resources.clone(), events, config);
+
+        engine.send("r1", "SYSTEM status").unwrap();
+        let deadline = Instant::now() + Duration::from_secs(10);
+        let mut received = Vec::new();
+        while Instant::now() < deadline && !received.contains(&EngineEvent::Done) {
+            match rx.try_recv() {
+                Ok(message) if message.request_id.as_deref() == Some("r1") => received.push(message.event),
+                Ok(_) => {}
+                Err(_) => thread::sleep(Duration::from_millis(20)),
+            }
+        }
+        assert_eq!(received, vec![EngineEvent::Error("OpenClaw exited before answering".to_string()), EngineEvent::Done]);
+        assert!(engine.inner.pending.lock().unwrap().is_empty());
+        let _ = engine.shutdown();
+        std::fs::remove_dir_all(&resources).unwrap();
+    }
+
+    #[test]
+    fn test_reset_forgets_the_saved_session() {
+        let (events, _) = broadcast::channel(16);
+        let engine = OpenClawEngine::new(std::env::temp_dir().join("kora-no-resources"), events, EngineConfig::default());
+        *engine.inner.state_dump.0.lock().unwrap() = Some("{\"lastQuery\":\"ACME secrets\"}".to_string());
+        // No bundled engine to spawn here; the session must be gone regardless
+        assert!(engine.reset().is_err());
+        assert!(engine.inner.state_dump.0.lock().unwrap().is_none());
+    }
+}
@@ -0,0 +1,150 @@
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use uuid::Uuid;
+
+/// An engine request that has not finished yet.
+#[derive(Clone, Debug, Serialize)]
+pub struct ActiveRequest {
+    pub id: String,
+    /// Command family that issued the request (e.g. `SYSTEM`, `KNOWLEDGE`).
+    pub kind: String,
+    pub agency_id: String,
+    /// Terminal session the request was typed in, if any.
+    pub session_id: Option<String>,
+    /// ISO-8601 submission timestamp.
+    pub started_at: String,
+    pub cancelled: bool,
+}
+
+/// Cancelled requests remembered after they are forgotten, oldest dropped first.
+const MAX_TOMBSTONES: usize = 256;
+
+/// Tracks in-flight engine requests so their output can be routed and cancelled.
+#[derive(Clone, Default)]
+pub struct RequestRegistry {
+    active: Arc<Mutex<HashMap<String, ActiveRequest>>>,
+    /// Cancelled requests whose backend may still send output and a `Done`.
+    tombstones: Arc<Mutex<VecDeque<String>>>,
+}
+
+impl RequestRegistry {
+    /// Registers a new request and returns its id.
+    pub fn begin(&self, kind: &str, agency_id: &str, session_id: Option<&str>) -> String {
+        let id = Uuid::new_v4().to_string();
+        let request = ActiveRequest {
+            id: id.clone(),
+            kind: kind.to_string(),
+            agency_id: agency_id.to_string(),
+            session_id: session_id.map(str::to_string),
+            started_at: chrono::Utc::now().to_rfc3339(),
+            cancelled: false,
+        };
+        self.active.lock().unwrap().insert(id.clone(), request);
+        id
+    }
+
+    /// Marks a request as cancelled. Returns it, or `None` if it is unknown or already finished.
+    pub fn cancel(&self, id: &str) -> Option<ActiveRequest> {
+        let mut active = self.active.lock().unwrap();
+        let request = active.get_mut(id)?;
+        if request.cancelled {
+            return None;
+        }
+        request.cancelled = true;
+        Some(request.clone())
+    }
+
+    /// True if output for this request must be discarded.
+    pub fn is_cancelled(&self, id: &str) -> bool {
+        self.active.lock().unwrap().get(id).is_some_and(|r| r.cancelled) || self.tombstones.lock().unwrap().iter().any(|t| t == id)
+    }
+
+    /// Forgets a cancelled request but keeps discarding its output until its
+    /// `Done`. Backends that stop without one age out of the tombstones.
+    pub fn bury(&self, id: &str) {
+        self.active.lock().unwrap().remove(id);
+        let mut tombstones = self.tombstones.lock().unwrap();
+        if !tombstones.iter().any(|t| t == id) {
+            if tombstones.len() == MAX_TOMBSTONES {
+                tombstones.pop_front();
+            }
+            tombstones.push_back(id.to_string());
+        }
+    }
+
+    /// Forgets a finished request. Returns whether it had been cancelled, in
+    /// which case its `Done` is stale.
+    pub fn finish(&self, id: &str) -> bool {
+        let cancelled = self.active.lock().unwrap().remove(id).is_some_and(|r| r.cancelled);
+        let mut tombstones = self.tombstones.lock().unwrap();
+        match tombstones.iter().position(|t| t == id) {
+            Some(i) => {
+                tombstones.remove(i);
+                true
+            }
+            None => cancelled,
+        }
+    }
+
+    pub fn get(&self, id: &str) -> Option<ActiveRequest> {
+        self.active.lock().unwrap().get(id).cloned()
+    }
+
+    /// The agency's most recent request that is still running and not cancelled.
+    pub fn latest(&self, agency_id: &str) -> Option<ActiveRequest> {
+        self.latest_where(|r| r.agency_id == agency_id)
+    }
+
+    /// The most recent running request typed in a terminal session.
+    pub fn latest_in_session(&self, session_id: &str) -> Option<ActiveRequest> {
+        self.latest_where(|r| r.session_id.as_deref() == Some(session_id))
+    }
+
+    fn latest_where(&self, matches: impl Fn(&ActiveRequest) -> bool) -> Option<ActiveRequest> {
+        self.active.lock().unwrap().values()
+            .filter(|r| matches(r) && !r.cancelled)
+            .max_by(|a, b| a.started_at.cmp(&b.started_at))
+            .cloned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cancelled_output_is_dropped_until_done() {
+        let requests = RequestRegistry::default();
+        let id = requests.begin("KNOWLEDGE", "SYSTEM", None);
+        requests.cancel(&id).unwrap();
+        requests.bury(&id);
+        assert!(requests.get(&id).is_none());
+        assert!(requests.is_cancelled(&id), "late chunks are still filtered");
+        assert!(requests.finish(&id), "its Done is stale");
+        assert!(!requests.is_cancelled(&id));
+
+        let id = requests.begin("SYSTEM", "SYSTEM", None);
+        assert!(!requests.finish(&id));
+        // Backends that never close a cancelled request do not grow the tombstones
+        for _ in 0..MAX_TOMBSTONES + 1 {
+            requests.bury(&requests.begin("SYSTEM", "SYSTEM", None));
+        }
+        assert_eq!(requests.tombstones.lock().unwrap().len(), MAX_TOMBSTONES);
+    }
+
+    #[test]
+    fn test_sessions_only_see_their_own_requests() {
+        let requests = RequestRegistry::default();
+        let chat = requests.begin("KNOWLEDGE", "SYSTEM", None);
+        assert!(requests.latest_in_session("pty-1").is_none());
+        let typed = requests.begin("KNOWLEDGE", "SYSTEM", Some("pty-1"));
+        requests.begin("SYSTEM", "SYSTEM", Some("pty-2"));
+
+        assert_eq!(requests.latest_in_session("pty-1").unwrap().id, typed);
+        requests.cancel(&typed).unwrap();
+        assert!(requests.latest_in_session("pty-1").is_none());
+        requests.cancel(&requests.latest_in_session("pty-2").unwrap().id).unwrap();
+        assert_eq!(requests.latest("SYSTEM").unwrap().id, chat, "agency-wide lookups see every request");
+    }
+}
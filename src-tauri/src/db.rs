@@ -80,3 +80,15 @@ pub async fn restore_last_session(pool: &Pool<Sqlite>, agency_id: &str) -> Resul
     .fetch_optional(pool)
     .await
 }
+
+/// Fresh in-memory database with every migration applied, for tests.
+#[cfg(test)]
+pub async fn test_pool() -> Pool<Sqlite> {
+    let pool = SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect_with("sqlite::memory:".parse::<SqliteConnectOptions>().expect("memory url").foreign_keys(true))
+        .await
+        .expect("in-memory database");
+    sqlx::migrate!("./migrations").run(&pool).await.expect("migrations");
+    pool
+}
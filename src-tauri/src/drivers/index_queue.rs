@@ -0,0 +1,324 @@
+use serde::Serialize;
+use sqlx::{Pool, Sqlite};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::Notify;
+use tokio::task::JoinHandle;
+use tokio::time::Instant;
+use crate::rag::{self, lifecycle};
+
+/// Tuning of the indexing queue.
+#[derive(Clone, Debug)]
+pub struct QueueConfig {
+    /// Quiet time after the last event on a path before it is processed.
+    pub debounce: Duration,
+    /// Paths processed at the same time.
+    pub max_concurrency: usize,
+}
+
+impl Default for QueueConfig {
+    fn default() -> Self {
+        Self { debounce: Duration::from_millis(500), max_concurrency: 2 }
+    }
+}
+
+/// Payload of the `kora-index-progress` event.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct IndexProgress {
+    pub path: String,
+    /// One of `queued`, `indexing`, `indexed`, `removed`, `skipped` or `failed`.
+    pub state: &'static str,
+    pub message: Option<String>,
+    /// Jobs waiting, including this one while it is `queued`.
+    pub pending: usize,
+    pub running: usize,
+}
+
+/// Receives progress updates; the app forwards them to the UI.
+pub type ProgressSink = Arc<dyn Fn(IndexProgress) + Send + Sync>;
+
+#[derive(Clone, Debug)]
+struct Job {
+    /// Agency active when the event arrived, used when the path names none.
+    agency_id: String,
+    size: u64,
+    due: Instant,
+}
+
+#[derive(Default)]
+struct QueueState {
+    pending: HashMap<PathBuf, Job>,
+    running: HashSet<PathBuf>,
+}
+
+struct Shared {
+    pool: Pool<Sqlite>,
+    /// Parent of the default `knowledge/{agency}` roots, to attribute files.
+    knowledge_base: PathBuf,
+    config: QueueConfig,
+    state: Mutex<QueueState>,
+    wake: Notify,
+    sink: ProgressSink,
+}
+
+/// Debounced indexing queue fed by the watch driver, which applies the
+/// per-root ignore rules and size limits before enqueueing.
+///
+/// Events on one path are coalesced into a single job that runs once the path
+/// has been quiet for `debounce`; a path is never processed twice at once.
+/// Ready jobs run smallest file first, at most `max_concurrency` at a time.
+/// Pending jobs are persisted in `index_jobs` and resumed on the next start.
+#[derive(Clone)]
+pub struct IndexQueue {
+    shared: Arc<Shared>,
+}
+
+impl IndexQueue {
+    /// Starts the dispatcher, first re-queueing jobs left over from the last run.
+    pub async fn start(pool: Pool<Sqlite>, knowledge_base: PathBuf, config: QueueConfig, sink: ProgressSink) -> Result<Self, String> {
+        let leftover: Vec<(String, String, i64)> = sqlx::query_as("SELECT path, agency_id, size FROM index_jobs")
+            .fetch_all(&pool)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        let queue = Self {
+            shared: Arc::new(Shared { pool, knowledge_base, config, state: Mutex::new(QueueState::default()), wake: Notify::new(), sink }),
+        };
+        {
+            let mut state = queue.shared.state.lock().unwrap();
+            let now = Instant::now();
+            for (path, agency_id, size) in leftover {
+                state.pending.insert(PathBuf::from(path), Job { agency_id, size: size.max(0) as u64, due: now });
+            }
+        }
+
+        let shared = queue.shared.clone();
+        tokio::spawn(async move { dispatch(shared).await });
+        Ok(queue)
+    }
+
+    /// Queues `path` (created, changed or removed), replacing any pending job for it.
+    pub async fn enqueue(&self, path: &Path, agency_id: &str) {
+        let size = std::fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+        let result = sqlx::query("INSERT OR REPLACE INTO index_jobs (path, agency_id, size, enqueued_at) VALUES (?, ?, ?, ?)")
+            .bind(path.to_string_lossy())
+            .bind(agency_id)
+            .bind(size as i64)
+            .bind(chrono::Utc::now().to_rfc3339())
+            .execute(&self.shared.pool)
+            .await;
+        if let Err(e) = result {
+            eprintln!("[INDEX_QUEUE] Failed to persist job for {:?}: {}", path, e);
+        }
+
+        let due = Instant::now() + self.shared.config.debounce;
+        let (pending, running) = {
+            let mut state = self.shared.state.lock().unwrap();
+            state.pending.insert(path.to_path_buf(), Job { agency_id: agency_id.to_string(), size, due });
+            (state.pending.len(), state.running.len())
+        };
+        (self.shared.sink)(IndexProgress { path: path.to_string_lossy().to_string(), state: "queued", message: None, pending, running });
+        self.shared.wake.notify_one();
+    }
+
+    /// Number of jobs waiting and running.
+    pub fn counts(&self) -> (usize, usize) {
+        let state = self.shared.state.lock().unwrap();
+        (state.pending.len(), state.running.len())
+    }
+}
+
+async fn dispatch(shared: Arc<Shared>) {
+    loop {
+        let next_due = {
+            let mut state = shared.state.lock().unwrap();
+            let now = Instant::now();
+            let mut ready: Vec<(PathBuf, Job)> = state.pending.iter()
+                .filter(|(path, job)| job.due <= now && !state.running.contains(*path))
+                .map(|(path, job)| (path.clone(), job.clone()))
+                .collect();
+            ready.sort_by(|a, b| a.1.size.cmp(&b.1.size).then_with(|| a.0.cmp(&b.0)));
+
+            let slots = shared.config.max_concurrency.max(1).saturating_sub(state.running.len());
+            for (path, job) in ready.into_iter().take(slots) {
+                state.pending.remove(&path);
+                state.running.insert(path.clone());
+                let shared = shared.clone();
+                tokio::spawn(async move { run(shared, path, job).await });
+            }
+
+            state.pending.iter()
+                .filter(|(path, _)| !state.running.contains(*path))
+                .map(|(_, job)| job.due)
+                .min()
+        };
+
+        match next_due {
+            Some(due) => {
+                tokio::select! {
+                    _ = tokio::time::sleep_until(due) => {}
+                    _ = shared.wake.notified() => {}
+                }
+            }
+            None => shared.wake.notified().await,
+        }
+    }
+}
+
+fn progress(shared: &Shared, path: &Path, state: &'static str, message: Option<String>) {
+    let (pending, running) = {
+        let s = shared.state.lock().unwrap();
+        (s.pending.len(), s.running.len())
+    };
+    (shared.sink)(IndexProgress { path: path.to_string_lossy().to_string(), state, message, pending, running });
+}
+
+async fn run(shared: Arc<Shared>, path: PathBuf, job: Job) {
+    progress(&shared, &path, "indexing", None);
+    let work = tokio::spawn(process(shared.clone(), path.clone(), job));
+    release(&shared, &path, work).await;
+}
+
+async fn process(shared: Arc<Shared>, path: PathBuf, job: Job) -> (&'static str, Option<String>) {
+    let path_str = path.to_string_lossy().to_string();
+    if !path.exists() {
+        match lifecycle::forget_path(&shared.pool, &path_str).await {
+            Ok(n) => ("removed", Some(format!("{} chunks dropped", n))),
+            Err(e) => ("failed", Some(e)),
+        }
+    } else if !path.is_file() {
+        ("skipped", Some("not a file".to_string()))
+    } else {
+        let indexed = match rag::resolve_agency(&shared.pool, &path, &shared.knowledge_base, &job.agency_id).await {
+            Ok(agency_id) => rag::index_file(&shared.pool, &path_str, &agency_id).await,
+            Err(e) => Err(e),
+        };
+        match indexed {
+            Ok(stats) => ("indexed", Some(stats.to_string())),
+            Err(e) => ("failed", Some(e)),
+        }
+    }
+}
+
+/// Waits for a job and frees its path whatever the outcome. A job that
+/// panicked reports `failed` and loses its persisted row like any other,
+/// so it neither holds its slot nor comes back on every start.
+async fn release(shared: &Shared, path: &Path, work: JoinHandle<(&'static str, Option<String>)>) {
+    let (state, message) = match work.await {
+        Ok(outcome) => outcome,
+        Err(e) => ("failed", Some(if e.is_panic() { "Indexing panicked".to_string() } else { e.to_string() })),
+    };
+
+    let requeued = {
+        let mut s = shared.state.lock().unwrap();
+        s.running.remove(path);
+        s.pending.contains_key(path)
+    };
+    // A newer event for the path keeps its persisted row for the follow-up job
+    if !requeued {
+        let _ = sqlx::query("DELETE FROM index_jobs WHERE path = ?").bind(path.to_string_lossy()).execute(&shared.pool).await;
+    }
+    progress(shared, path, state, message);
+    shared.wake.notify_one();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_pool;
+
+    fn recorder() -> (ProgressSink, Arc<Mutex<Vec<IndexProgress>>>) {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let sink_events = events.clone();
+        (Arc::new(move |p| sink_events.lock().unwrap().push(p)), events)
+    }
+
+    /// Waits until the queue is empty and every started job has reported
+    /// its outcome, which happens just after it leaves the running set.
+    async fn settle(queue: &IndexQueue, events: &Mutex<Vec<IndexProgress>>) {
+        for _ in 0..200 {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            let events = events.lock().unwrap();
+            let started = events.iter().filter(|e| e.state == "indexing").count();
+            if queue.counts() == (0, 0) && finished(&events).len() == started {
+                return;
+            }
+        }
+        panic!("queue did not drain");
+    }
+
+    fn finished(events: &[IndexProgress]) -> Vec<(String, &'static str)> {
+        events.iter().filter(|e| !matches!(e.state, "queued" | "indexing")).map(|e| (e.path.clone(), e.state)).collect()
+    }
+
+    #[tokio::test]
+    async fn test_bursts_are_coalesced_and_small_files_go_first() {
+        let pool = test_pool().await;
+        let dir = std::env::temp_dir().join(format!("kora-queue-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let (big, small) = (dir.join("big.md"), dir.join("small.md"));
+        std::fs::write(&big, "bridge ".repeat(2000)).unwrap();
+        std::fs::write(&small, "bridge").unwrap();
+
+        let (sink, events) = recorder();
+        let config = QueueConfig { debounce: Duration::from_millis(30), max_concurrency: 1 };
+        let queue = IndexQueue::start(pool.clone(), std::env::temp_dir().join("knowledge"), config, sink).await.unwrap();
+        for _ in 0..5 {
+            queue.enqueue(&big, "SYSTEM").await;
+        }
+        queue.enqueue(&small, "SYSTEM").await;
+        settle(&queue, &events).await;
+
+        let done = finished(&events.lock().unwrap());
+        assert_eq!(done, vec![(small.to_string_lossy().to_string(), "indexed"), (big.to_string_lossy().to_string(), "indexed")]);
+        let (jobs,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM index_jobs").fetch_one(&pool).await.unwrap();
+        assert_eq!(jobs, 0);
+
+        // A vanished file is dropped from the index
+        std::fs::remove_file(&small).unwrap();
+        queue.enqueue(&small, "SYSTEM").await;
+        settle(&queue, &events).await;
+        assert_eq!(finished(&events.lock().unwrap()).last().unwrap().1, "removed");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_pending_jobs_survive_a_restart() {
+        let pool = test_pool().await;
+        let path = std::env::temp_dir().join(format!("kora-queue-{}.md", uuid::Uuid::new_v4()));
+        std::fs::write(&path, "bridge lock").unwrap();
+
+        let (sink, _) = recorder();
+        let slow = QueueConfig { debounce: Duration::from_secs(3600), ..Default::default() };
+        IndexQueue::start(pool.clone(), std::env::temp_dir().join("knowledge"), slow, sink).await.unwrap().enqueue(&path, "SYSTEM").await;
+
+        let (sink, events) = recorder();
+        let queue = IndexQueue::start(pool.clone(), std::env::temp_dir().join("knowledge"), QueueConfig::default(), sink).await.unwrap();
+        settle(&queue, &events).await;
+        assert_eq!(finished(&events.lock().unwrap()), vec![(path.to_string_lossy().to_string(), "indexed")]);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_a_panicking_job_frees_its_path() {
+        let pool = test_pool().await;
+        let (sink, events) = recorder();
+        let queue = IndexQueue::start(pool.clone(), std::env::temp_dir().join("knowledge"), QueueConfig::default(), sink).await.unwrap();
+        let path = PathBuf::from("/poison.html");
+        sqlx::query("INSERT INTO index_jobs (path, agency_id, size, enqueued_at) VALUES (?, 'SYSTEM', 1, '')")
+            .bind(path.to_string_lossy())
+            .execute(&pool)
+            .await
+            .unwrap();
+        queue.shared.state.lock().unwrap().running.insert(path.clone());
+
+        release(&queue.shared, &path, tokio::spawn(async { panic!("extractor bug") })).await;
+        assert_eq!(queue.counts(), (0, 0));
+        let (jobs,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM index_jobs").fetch_one(&pool).await.unwrap();
+        assert_eq!(jobs, 0);
+        let last = events.lock().unwrap().last().cloned().unwrap();
+        assert_eq!((last.state, last.message.as_deref()), ("failed", Some("Indexing panicked")));
+    }
+}
@@ -1,2 +1,4 @@
+pub mod index_queue;
 pub mod notify;
 pub mod watch;
+pub mod watch_config;
@@ -1,71 +1,484 @@
-use notify::{Config, RecommendedWatcher, RecursiveMode, Watcher};
-use std::path::Path;
-use std::sync::mpsc::channel;
-use std::thread;
-use tauri::{AppHandle, Manager, Runtime};
-use crate::rag;
-use crate::AppState; 
-
-pub fn init_watcher<R: Runtime>(app: AppHandle<R>) {
-    thread::spawn(move || {
-        let (tx, rx) = channel();
-        
-        let mut watcher = match RecommendedWatcher::new(tx, Config::default()) {
-            Ok(w) => w,
+use notify::event::{ModifyKind, RenameMode};
+use notify::{Config, Event, EventKind, PollWatcher, RecommendedWatcher, RecursiveMode, Watcher};
+use serde::Serialize;
+use sqlx::{Pool, Sqlite};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+use super::index_queue::IndexQueue;
+use super::watch_config::{self, IgnoreRules, WatchBackend, WatchConfig, IGNORE_FILE};
+use crate::rag::{self, lifecycle};
+use crate::AppState;
+
+/// State of one watched knowledge root.
+#[derive(Clone, Debug, Serialize)]
+pub struct RootStatus {
+    pub agency_id: String,
+    pub path: String,
+    pub watching: bool,
+    /// Backend in use, which differs from the configured one after a fallback.
+    pub backend: Option<WatchBackend>,
+    /// Why the root is not being watched.
+    pub error: Option<String>,
+    pub max_file_bytes: u64,
+    /// Ignore patterns in effect, including the root's `.gitignore`.
+    pub ignore: Vec<String>,
+    /// Filesystem events received.
+    pub events: u64,
+    /// Paths dropped by an ignore pattern.
+    pub ignored: u64,
+    /// Files not indexed for exceeding `max_file_bytes`.
+    pub oversized: u64,
+    /// Outcome of the scan run when the watch started.
+    pub last_scan: Option<ScanReport>,
+}
+
+/// Reconciliation of a root with the index when its watch starts.
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct ScanReport {
+    /// Files under the root that pass its ignore rules and size limit.
+    pub files: usize,
+    /// Files queued because they are new or changed since they were indexed.
+    pub queued: usize,
+    /// Indexed files queued for removal because they are gone.
+    pub missing: usize,
+}
+
+/// Payload of `kora_watch_status`.
+#[derive(Clone, Debug, Serialize)]
+pub struct WatchStatus {
+    pub roots: Vec<RootStatus>,
+    /// Indexing jobs waiting in the queue.
+    pub pending: usize,
+    pub running: usize,
+}
+
+struct Root {
+    path: PathBuf,
+    config: WatchConfig,
+    rules: IgnoreRules,
+    /// Dropping it stops the watch.
+    watcher: Option<(Box<dyn Watcher + Send>, WatchBackend)>,
+    error: Option<String>,
+    events: u64,
+    ignored: u64,
+    oversized: u64,
+    last_scan: Option<ScanReport>,
+}
+
+impl Root {
+    /// Whether `path` passes the root's ignore rules and size limit.
+    fn admits(&mut self, path: &Path) -> bool {
+        let Ok(rel) = path.strip_prefix(&self.path) else { return false };
+        if self.rules.is_ignored(rel, path.is_dir()) {
+            self.ignored += 1;
+            return false;
+        }
+        if std::fs::metadata(path).is_ok_and(|m| m.is_file() && m.len() >= self.config.max_file_bytes) {
+            self.oversized += 1;
+            return false;
+        }
+        true
+    }
+
+    fn status(&self, agency_id: &str) -> RootStatus {
+        RootStatus {
+            agency_id: agency_id.to_string(),
+            path: self.path.to_string_lossy().to_string(),
+            watching: self.watcher.is_some(),
+            backend: self.watcher.as_ref().map(|(_, backend)| *backend),
+            error: self.error.clone(),
+            max_file_bytes: self.config.max_file_bytes,
+            ignore: self.rules.patterns().to_vec(),
+            events: self.events,
+            ignored: self.ignored,
+            oversized: self.oversized,
+            last_scan: self.last_scan.clone(),
+        }
+    }
+}
+
+enum Action {
+    Enqueue(PathBuf),
+    Rename(PathBuf, PathBuf),
+}
+
+type RootEvent = (String, notify::Result<Event>);
+
+struct Inner {
+    pool: Pool<Sqlite>,
+    queue: IndexQueue,
+    /// Parent of the default `knowledge/{agency}` roots.
+    knowledge_base: PathBuf,
+    /// Directories other roots may be configured in.
+    allowed_roots: Vec<PathBuf>,
+    roots: Mutex<HashMap<String, Root>>,
+    events: UnboundedSender<RootEvent>,
+}
+
+/// Watches the knowledge root of every agency and feeds changes to the index queue.
+#[derive(Clone)]
+pub struct WatchDriver {
+    inner: Arc<Inner>,
+}
+
+impl WatchDriver {
+    pub fn start(pool: Pool<Sqlite>, queue: IndexQueue, knowledge_base: PathBuf) -> Self {
+        let (tx, rx) = unbounded_channel();
+        let inner = Arc::new(Inner { pool, queue, knowledge_base, allowed_roots: watch_config::allowed_roots(), roots: Mutex::new(HashMap::new()), events: tx });
+        tokio::spawn(process(inner.clone(), rx));
+        Self { inner }
+    }
+
+    /// Watches every agency that is not archived.
+    pub async fn watch_all(&self) {
+        let agencies: Vec<(String,)> = match sqlx::query_as("SELECT id FROM agencies WHERE archived_at IS NULL").fetch_all(&self.inner.pool).await {
+            Ok(rows) => rows,
+            Err(e) => return eprintln!("[WATCHER] Failed to list agencies: {}", e),
+        };
+        for (agency_id,) in agencies {
+            if let Err(e) = self.watch_agency(&agency_id).await {
+                eprintln!("[WATCHER] Not watching {}: {}", agency_id, e);
+            }
+        }
+    }
+
+    /// (Re)starts the watch of an agency's root with its stored configuration,
+    /// then scans it in the background for changes made while it was not watched.
+    /// A root that cannot be watched is still listed in the status with its error.
+    pub async fn watch_agency(&self, agency_id: &str) -> Result<(), String> {
+        let stored = watch_config::load(&self.inner.pool, agency_id).await;
+        let config = match stored.and_then(|c| c.validate().map(|_| c)).and_then(|c| self.check_config(agency_id, c)) {
+            Ok(config) => config,
             Err(e) => {
-                eprintln!("[WATCHER] Failed to create watcher: {:?}", e);
-                return;
+                eprintln!("[WATCHER] Invalid watch config for {}: {}. Using defaults.", agency_id, e);
+                WatchConfig::default()
             }
         };
+        let path = config.root.as_ref().map(PathBuf::from).unwrap_or_else(|| self.inner.knowledge_base.join(agency_id));
+        let (watcher, error) = match self.open(agency_id, &path, &config) {
+            Ok(watcher) => (Some(watcher), None),
+            Err(e) => (None, Some(e)),
+        };
+        let rules = IgnoreRules::for_root(&config, &path);
+        let watching = watcher.is_some();
+        let root = Root { path, config, rules, watcher, error: error.clone(), events: 0, ignored: 0, oversized: 0, last_scan: None };
+        if let Some(old) = self.inner.roots.lock().unwrap().insert(agency_id.to_string(), root) {
+            println!("[WATCHER] Replaced watch of {:?} for {}", old.path, agency_id);
+        }
+
+        if watching {
+            let driver = self.clone();
+            let agency = agency_id.to_string();
+            tokio::spawn(async move {
+                match driver.scan(&agency).await {
+                    Ok(report) => println!("[WATCHER] Scanned {} files of {}: {} queued, {} missing", report.files, agency, report.queued, report.missing),
+                    Err(e) => eprintln!("[WATCHER] Scan of {} failed: {}", agency, e),
+                }
+            });
+        }
+        error.map_or(Ok(()), Err)
+    }
+
+    /// Checks a configured root against the agency's jail and the other
+    /// agencies' roots, replacing it with its canonical form.
+    pub fn check_config(&self, agency_id: &str, mut config: WatchConfig) -> Result<WatchConfig, String> {
+        if let Some(root) = &config.root {
+            let roots = self.inner.roots.lock().unwrap();
+            let others = roots.iter().map(|(id, root)| (id.as_str(), root.path.as_path()));
+            let path = watch_config::check_root(Path::new(root), agency_id, &self.inner.knowledge_base, &self.inner.allowed_roots, others)?;
+            config.root = Some(path.to_string_lossy().to_string());
+        }
+        Ok(config)
+    }
 
-        // Watch /knowledge directory
-        // In dev, this might be relative. In prod, strict path.
-        let knowledge_path = Path::new("knowledge"); // Relative to CWD for now as defined in prompt implies root /knowledge
-        // But for safety let's assume it exists in CWD or handle absolute if /knowledge is system root (unlikely for user mode)
-        // We will assume relative to CWD for this prototype unless jail says otherwise. 
-        // Jail said "/knowledge" which usually means absolute in *nix, but for this app context it likely means project root/knowledge.
-        // Let's try to verify if it exists, if not create it or warn.
-        if !knowledge_path.exists() {
-             let _ = std::fs::create_dir_all(knowledge_path);
-        }
-
-        if let Err(e) = watcher.watch(knowledge_path, RecursiveMode::Recursive) {
-             eprintln!("[WATCHER] Failed to watch knowledge: {:?}", e);
-             return;
-        }
-
-        println!("[WATCHER] Started watching {:?}", knowledge_path);
-
-        for res in rx {
-            match res {
-                Ok(event) => {
-                    // Filter for Create or Modify events
-                    if event.kind.is_create() || event.kind.is_modify() {
-                         for path in event.paths {
-                             if path.is_file() {
-                                 // Check file size (< 10MB) - arbitrary limit for "validar su tamaño"
-                                 if let Ok(metadata) = std::fs::metadata(&path) {
-                                     if metadata.len() < 10 * 1024 * 1024 {
-                                         // Trigger Indexing
-                                         // We need access to DB. We can get it from AppHandle -> State -> DB
-                                         let app_handle = app.clone();
-                                         let path_str = path.to_string_lossy().to_string();
-                                         
-                                         // Spawn async task to index
-                                         tauri::async_runtime::spawn(async move {
-                                             if let Some(state) = app_handle.try_state::<AppState>() {
-                                                 println!("[WATCHER] Indexing detected file: {}", path_str);
-                                                 let _ = rag::index_file(&state.db, &path_str).await;
-                                             }
-                                         });
-                                     }
-                                 }
-                             }
-                         }
-                    }
-                },
-                Err(e) => eprintln!("[WATCHER] Watch error: {:?}", e),
+    fn open(&self, agency_id: &str, path: &Path, config: &WatchConfig) -> Result<(Box<dyn Watcher + Send>, WatchBackend), String> {
+        if !path.is_dir() {
+            // Only the default root is ours to scaffold; a configured one must exist
+            if config.root.is_some() {
+                return Err(format!("Watch root does not exist: {}", path.display()));
             }
+            std::fs::create_dir_all(path).map_err(|e| format!("Cannot create {}: {}", path.display(), e))?;
+            println!("[WATCHER] Created knowledge root {:?} for {}", path, agency_id);
         }
-    });
+        if config.backend == WatchBackend::Native {
+            match self.start_watcher::<RecommendedWatcher>(agency_id, path, Config::default()) {
+                Ok(watcher) => return Ok((watcher, WatchBackend::Native)),
+                Err(e) => eprintln!("[WATCHER] {}. Polling instead.", e),
+            }
+        }
+        let poll = Config::default().with_poll_interval(Duration::from_secs(config.poll_interval_secs));
+        let watcher = self.start_watcher::<PollWatcher>(agency_id, path, poll)?;
+        Ok((watcher, WatchBackend::Poll))
+    }
+
+    fn start_watcher<W: Watcher + Send + 'static>(&self, agency_id: &str, path: &Path, config: Config) -> Result<Box<dyn Watcher + Send>, String> {
+        let tx = self.inner.events.clone();
+        let agency = agency_id.to_string();
+        let mut watcher = W::new(move |res| { let _ = tx.send((agency.clone(), res)); }, config)
+            .map_err(|e| format!("Failed to create watcher: {}", e))?;
+        watcher.watch(path, RecursiveMode::Recursive).map_err(|e| format!("Failed to watch {}: {}", path.display(), e))?;
+        println!("[WATCHER] Watching {:?} for {}", path, agency_id);
+        Ok(Box::new(watcher))
+    }
+
+    /// Queues files that are unindexed or changed since they were indexed, and
+    /// indexed files that are gone, so the index catches up with edits made
+    /// while the root was not watched.
+    async fn scan(&self, agency_id: &str) -> Result<ScanReport, String> {
+        let (root, rules, max_file_bytes) = {
+            let roots = self.inner.roots.lock().unwrap();
+            let root = roots.get(agency_id).ok_or_else(|| format!("Not watching {}", agency_id))?;
+            (root.path.clone(), root.rules.clone(), root.config.max_file_bytes)
+        };
+        let indexed: Vec<(String, String)> = sqlx::query_as("SELECT path, MIN(hash) FROM documents WHERE agency_id = ? GROUP BY path")
+            .bind(agency_id)
+            .fetch_all(&self.inner.pool)
+            .await
+            .map_err(|e| e.to_string())?;
+        let indexed: HashMap<PathBuf, String> = indexed.into_iter()
+            .map(|(path, hash)| (PathBuf::from(path), hash))
+            .filter(|(path, _)| path.starts_with(&root))
+            .collect();
+
+        // Walking and hashing touch every file: keep them off the async workers
+        let (files, stale, missing) = tokio::task::spawn_blocking(move || {
+            let mut files = Vec::new();
+            walk(&root, &root, &rules, max_file_bytes, &mut files);
+            let stale: Vec<PathBuf> = files.iter()
+                .filter(|file| indexed.get(*file).is_none_or(|hash| lifecycle::disk_hash(file).ok().as_ref() != Some(hash)))
+                .cloned()
+                .collect();
+            let missing: Vec<PathBuf> = indexed.into_keys().filter(|path| !path.exists()).collect();
+            (files.len(), stale, missing)
+        }).await.map_err(|e| e.to_string())?;
+
+        let report = ScanReport { files, queued: stale.len(), missing: missing.len() };
+        for path in stale.iter().chain(&missing) {
+            self.inner.queue.enqueue(path, agency_id).await;
+        }
+        if let Some(root) = self.inner.roots.lock().unwrap().get_mut(agency_id) {
+            root.last_scan = Some(report.clone());
+        }
+        Ok(report)
+    }
+
+    /// The root watched for an agency, if any.
+    pub fn root(&self, agency_id: &str) -> Option<PathBuf> {
+        self.inner.roots.lock().unwrap().get(agency_id).map(|root| root.path.clone())
+    }
+
+    /// Stops watching an agency's root. Returns whether it was watched.
+    pub fn unwatch_agency(&self, agency_id: &str) -> bool {
+        self.inner.roots.lock().unwrap().remove(agency_id).is_some()
+    }
+
+    pub fn status(&self) -> WatchStatus {
+        let mut roots: Vec<RootStatus> = self.inner.roots.lock().unwrap().iter().map(|(id, root)| root.status(id)).collect();
+        roots.sort_by(|a, b| a.agency_id.cmp(&b.agency_id));
+        let (pending, running) = self.inner.queue.counts();
+        WatchStatus { roots, pending, running }
+    }
+}
+
+/// Collects the files below `dir` that pass the root's rules, without following symlinks.
+fn walk(root: &Path, dir: &Path, rules: &IgnoreRules, max_file_bytes: u64, files: &mut Vec<PathBuf>) {
+    let Ok(entries) = std::fs::read_dir(dir) else { return };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let Ok(kind) = entry.file_type() else { continue };
+        if kind.is_symlink() || rules.is_ignored(path.strip_prefix(root).unwrap_or(&path), kind.is_dir()) {
+            continue;
+        }
+        if kind.is_dir() {
+            walk(root, &path, rules, max_file_bytes, files);
+        } else if kind.is_file() && entry.metadata().is_ok_and(|m| m.len() < max_file_bytes) {
+            files.push(path);
+        }
+    }
+}
+
+async fn process(inner: Arc<Inner>, mut rx: UnboundedReceiver<RootEvent>) {
+    while let Some((agency_id, res)) = rx.recv().await {
+        let event = match res {
+            Ok(event) => event,
+            Err(e) => {
+                eprintln!("[WATCHER] Watch error for {}: {:?}", agency_id, e);
+                continue;
+            }
+        };
+        for action in triage(&inner, &agency_id, event) {
+            match action {
+                Action::Enqueue(path) => inner.queue.enqueue(&path, &agency_id).await,
+                Action::Rename(from, to) => rename(&inner, &agency_id, from, to).await,
+            }
+        }
+    }
+}
+
+/// Filters an event through the root's rules into queue and rename actions.
+fn triage(inner: &Inner, agency_id: &str, event: Event) -> Vec<Action> {
+    let mut roots = inner.roots.lock().unwrap();
+    // Events still in flight from a root that was just unwatched
+    let Some(root) = roots.get_mut(agency_id) else { return Vec::new() };
+    root.events += 1;
+    if event.paths.iter().any(|p| *p == root.path.join(IGNORE_FILE)) {
+        root.rules = IgnoreRules::for_root(&root.config, &root.path);
+    }
+
+    match event.kind {
+        // Both ends of a rename in one event: move the chunks
+        EventKind::Modify(ModifyKind::Name(RenameMode::Both)) if event.paths.len() == 2 => {
+            let (from, to) = (event.paths[0].clone(), event.paths[1].clone());
+            match (root.admits(&from), root.admits(&to)) {
+                (true, true) => vec![Action::Rename(from, to)],
+                (false, true) => vec![Action::Enqueue(to)],
+                // `from` is gone, so the queue drops its chunks
+                (true, false) => vec![Action::Enqueue(from)],
+                (false, false) => Vec::new(),
+            }
+        }
+        // Create, Modify, Remove and half-reported renames: the queue
+        // indexes what exists and forgets what vanished
+        EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_) => event.paths.into_iter()
+            .filter(|path| path.is_file() || !path.exists())
+            .filter(|path| root.admits(path))
+            .map(Action::Enqueue)
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+async fn rename(inner: &Inner, agency_id: &str, from: PathBuf, to: PathBuf) {
+    let (from_str, to_str) = (from.to_string_lossy(), to.to_string_lossy());
+    let moved = match rag::resolve_agency(&inner.pool, &to, &inner.knowledge_base, agency_id).await {
+        Ok(owner) => lifecycle::rename_path(&inner.pool, &from_str, &to_str, &owner).await,
+        // Moved out of any known agency: nothing may keep serving it
+        Err(_) => lifecycle::forget_path(&inner.pool, &from_str).await.map(|_| 0),
+    };
+    match moved {
+        Ok(0) if to.is_file() => inner.queue.enqueue(&to, agency_id).await,
+        Ok(n) => println!("[WATCHER] Moved {} chunks from {} to {}", n, from_str, to_str),
+        Err(e) => eprintln!("[WATCHER] Failed to move {} to {}: {}", from_str, to_str, e),
+    }
+}
+
+/// Reports the watched roots and the indexing queue.
+#[tauri::command]
+pub fn kora_watch_status(state: tauri::State<'_, AppState>) -> WatchStatus {
+    state.watcher.status()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_pool;
+    use crate::drivers::index_queue::QueueConfig;
+    use std::time::Duration;
+
+    async fn indexed_paths(pool: &Pool<Sqlite>) -> Vec<String> {
+        let rows: Vec<(String,)> = sqlx::query_as("SELECT DISTINCT path FROM documents ORDER BY path").fetch_all(pool).await.unwrap();
+        rows.into_iter().map(|(p,)| p).collect()
+    }
+
+    #[tokio::test]
+    async fn test_roots_honor_ignores_and_size_limits() {
+        let pool = test_pool().await;
+        let base = std::env::temp_dir().join(format!("kora-watch-{}", uuid::Uuid::new_v4())).join("knowledge");
+        let config = QueueConfig { debounce: Duration::from_millis(30), ..Default::default() };
+        let queue = IndexQueue::start(pool.clone(), base.clone(), config, Arc::new(|_| {})).await.unwrap();
+        watch_config::save(&pool, "SYSTEM", &WatchConfig { max_file_bytes: 1024, ..Default::default() }).await.unwrap();
+
+        let driver = WatchDriver::start(pool.clone(), queue, base.clone());
+        driver.watch_all().await;
+        let root = base.join("SYSTEM");
+        assert!(root.is_dir());
+        std::fs::write(root.join(IGNORE_FILE), "drafts/\n").unwrap();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        std::fs::create_dir_all(root.join("drafts")).unwrap();
+        std::fs::write(root.join("drafts").join("wip.md"), "bridge draft").unwrap();
+        std::fs::write(root.join(".notes.md.swp"), "bridge swap").unwrap();
+        std::fs::write(root.join("big.md"), "bridge ".repeat(500)).unwrap();
+        std::fs::write(root.join("notes.md"), "bridge lock").unwrap();
+
+        let expected = vec![root.join("notes.md").to_string_lossy().to_string()];
+        for _ in 0..300 {
+            if indexed_paths(&pool).await == expected {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        assert_eq!(indexed_paths(&pool).await, expected);
+
+        let status = driver.status();
+        assert_eq!(status.roots.len(), 1);
+        let system = &status.roots[0];
+        assert!(system.watching && system.error.is_none());
+        assert_eq!(system.max_file_bytes, 1024);
+        assert!(system.ignore.contains(&"drafts/".to_string()));
+        assert!(system.ignored >= 2 && system.oversized >= 1);
+
+        // An unwatched root no longer reaches the index
+        assert!(driver.unwatch_agency("SYSTEM"));
+        std::fs::write(root.join("late.md"), "bridge late").unwrap();
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        assert_eq!(indexed_paths(&pool).await, expected);
+        assert!(driver.status().roots.is_empty());
+
+        std::fs::remove_dir_all(base.parent().unwrap()).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_startup_scan_and_polling_backend() {
+        let pool = test_pool().await;
+        let base = std::env::temp_dir().join(format!("kora-poll-{}", uuid::Uuid::new_v4())).join("knowledge");
+        let root = base.join("SYSTEM");
+        std::fs::create_dir_all(&root).unwrap();
+        let (kept, edited, gone, fresh) = (root.join("kept.md"), root.join("edited.md"), root.join("gone.md"), root.join("fresh.md"));
+        for path in [&kept, &edited, &gone] {
+            std::fs::write(path, "bridge lock").unwrap();
+            rag::index_file(&pool, &path.to_string_lossy(), "SYSTEM").await.unwrap();
+        }
+        // Changes made while nothing was watching
+        std::fs::write(&edited, "bridge unlocked").unwrap();
+        std::fs::remove_file(&gone).unwrap();
+        std::fs::write(&fresh, "bridge fresh").unwrap();
+
+        let config = QueueConfig { debounce: Duration::from_millis(30), ..Default::default() };
+        let queue = IndexQueue::start(pool.clone(), base.clone(), config, Arc::new(|_| {})).await.unwrap();
+        let poll = WatchConfig { backend: WatchBackend::Poll, poll_interval_secs: 1, ..Default::default() };
+        watch_config::save(&pool, "SYSTEM", &poll).await.unwrap();
+        let driver = WatchDriver::start(pool.clone(), queue, base.clone());
+        driver.watch_agency("SYSTEM").await.unwrap();
+
+        let expected: Vec<String> = [&edited, &fresh, &kept].iter().map(|p| p.to_string_lossy().to_string()).collect();
+        for _ in 0..250 {
+            if indexed_paths(&pool).await == expected {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        assert_eq!(indexed_paths(&pool).await, expected);
+        let status = driver.status();
+        assert_eq!(status.roots[0].backend, Some(WatchBackend::Poll));
+        assert_eq!(status.roots[0].last_scan, Some(ScanReport { files: 3, queued: 2, missing: 1 }));
+
+        // New files are found by the next poll
+        let late = root.join("late.md");
+        std::fs::write(&late, "bridge late").unwrap();
+        let late = late.to_string_lossy().to_string();
+        for _ in 0..200 {
+            if indexed_paths(&pool).await.contains(&late) {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        assert!(indexed_paths(&pool).await.contains(&late));
+
+        std::fs::remove_dir_all(base.parent().unwrap()).unwrap();
+    }
 }
@@ -0,0 +1,364 @@
+use glob::{MatchOptions, Pattern};
+use serde::{Deserialize, Serialize};
+use sqlx::{Pool, Sqlite};
+use std::path::{Component, Path, PathBuf};
+use crate::audit;
+use crate::AppState;
+
+/// File name of the per-root ignore file, read with `.gitignore` syntax.
+pub const IGNORE_FILE: &str = ".gitignore";
+
+/// Directories, in `PATH` syntax, below which an administrator lets agencies
+/// watch roots outside their own `knowledge/{agency}`.
+pub const ALLOWED_ROOTS_ENV: &str = "KORA_WATCH_ROOTS";
+
+/// The directories allowed by [`ALLOWED_ROOTS_ENV`].
+pub fn allowed_roots() -> Vec<PathBuf> {
+    std::env::var_os(ALLOWED_ROOTS_ENV).map(|paths| std::env::split_paths(&paths).filter(|p| p.is_absolute()).collect()).unwrap_or_default()
+}
+
+fn canonical(path: &Path) -> PathBuf {
+    std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// Resolves a configured root and checks that the agency may watch it: it
+/// must lie in the agency's own knowledge dir under `knowledge_base` or
+/// below an allowed directory, and must not overlap any other agency's
+/// knowledge dir or watched root. Returns the canonical root.
+pub fn check_root<'a>(
+    root: &Path,
+    agency_id: &str,
+    knowledge_base: &Path,
+    allowed: &[PathBuf],
+    others: impl IntoIterator<Item = (&'a str, &'a Path)>,
+) -> Result<PathBuf, String> {
+    let path = std::fs::canonicalize(root).map_err(|e| format!("Watch root {} is unusable: {}", root.display(), e))?;
+    let base = canonical(knowledge_base);
+    let own = base.join(agency_id);
+    let permitted = path.starts_with(&own) || allowed.iter().any(|dir| path.starts_with(canonical(dir)));
+    if !permitted {
+        return Err(format!("Watch root must be inside {} or a directory listed in {}: {}", own.display(), ALLOWED_ROOTS_ENV, path.display()));
+    }
+    if (path.starts_with(&base) && !path.starts_with(&own)) || base.starts_with(&path) {
+        return Err(format!("Watch root overlaps other agencies' knowledge: {}", path.display()));
+    }
+    for (other, other_root) in others {
+        let other_root = canonical(other_root);
+        if other != agency_id && (path.starts_with(&other_root) || other_root.starts_with(&path)) {
+            return Err(format!("Watch root overlaps the root of {}: {}", other, path.display()));
+        }
+    }
+    Ok(path)
+}
+
+/// How changes under a root are detected.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WatchBackend {
+    /// The platform's notification API (inotify, FSEvents, ReadDirectoryChangesW).
+    Native,
+    /// Periodic rescans, for network mounts and containers where native
+    /// notifications are unreliable.
+    Poll,
+}
+
+impl WatchBackend {
+    pub fn name(&self) -> &'static str {
+        match self {
+            WatchBackend::Native => "native",
+            WatchBackend::Poll => "poll",
+        }
+    }
+}
+
+/// Watch settings of an agency's knowledge root.
+///
+/// Stored per agency in `watch_configs` as serialized JSON, like `EngineConfig`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WatchConfig {
+    /// Absolute directory to watch. Defaults to `knowledge/{agency}` under the app data dir;
+    /// anything else must pass [`check_root`].
+    pub root: Option<String>,
+    /// `.gitignore`-style patterns applied before the root's own `.gitignore`.
+    pub ignore: Vec<String>,
+    /// Files at or above this size are not indexed automatically.
+    pub max_file_bytes: u64,
+    /// Native falls back to polling when the root cannot be watched natively.
+    pub backend: WatchBackend,
+    /// Seconds between rescans of the poll backend.
+    pub poll_interval_secs: u64,
+}
+
+impl Default for WatchConfig {
+    fn default() -> Self {
+        Self {
+            root: None,
+            // Editor swap, backup and lock files, OS litter and VCS metadata
+            ignore: [".*.sw?", "*~", "*.tmp", ".#*", "\\#*#", "4913", ".DS_Store", ".git/", IGNORE_FILE]
+                .iter().map(|p| p.to_string()).collect(),
+            max_file_bytes: 10 * 1024 * 1024,
+            backend: WatchBackend::Native,
+            poll_interval_secs: 5,
+        }
+    }
+}
+
+impl WatchConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(root) = &self.root {
+            let path = Path::new(root);
+            if !path.is_absolute() || path.components().any(|c| c == Component::ParentDir) {
+                return Err(format!("Watch root must be an absolute path without '..': {}", root));
+            }
+        }
+        if !(1024..=1024 * 1024 * 1024).contains(&self.max_file_bytes) {
+            return Err(format!("Size limit must be between 1 KB and 1 GB, got {} bytes", self.max_file_bytes));
+        }
+        if !(1..=3600).contains(&self.poll_interval_secs) {
+            return Err(format!("Poll interval must be between 1 and 3600 seconds, got {}", self.poll_interval_secs));
+        }
+        IgnoreRules::parse(self.ignore.iter().map(String::as_str)).map(|_| ())
+    }
+}
+
+#[derive(Clone, Debug)]
+struct Rule {
+    pattern: Pattern,
+    negated: bool,
+    dir_only: bool,
+    /// Matched against the path from the root rather than a single name.
+    anchored: bool,
+}
+
+/// Compiled `.gitignore`-style patterns: `#` comments, `!` negation, a
+/// trailing `/` for directories only and a `/` anywhere else to anchor the
+/// pattern at the root. The last matching rule wins, and nothing below an
+/// ignored directory can be re-included.
+#[derive(Clone, Debug, Default)]
+pub struct IgnoreRules {
+    rules: Vec<Rule>,
+    source: Vec<String>,
+}
+
+const MATCH: MatchOptions = MatchOptions { case_sensitive: true, require_literal_separator: true, require_literal_leading_dot: false };
+
+impl IgnoreRules {
+    pub fn parse<'a>(lines: impl IntoIterator<Item = &'a str>) -> Result<Self, String> {
+        let mut rules = Self::default();
+        rules.extend(lines)?;
+        Ok(rules)
+    }
+
+    fn extend<'a>(&mut self, lines: impl IntoIterator<Item = &'a str>) -> Result<(), String> {
+        for line in lines {
+            let line = line.trim_end();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (negated, body) = match line.strip_prefix('!') {
+                Some(rest) => (true, rest),
+                None => (false, line.strip_prefix('\\').unwrap_or(line)),
+            };
+            let dir_only = body.ends_with('/');
+            let body = body.trim_end_matches('/');
+            let anchored = body.contains('/');
+            let body = body.trim_start_matches('/');
+            if body.is_empty() {
+                continue;
+            }
+            let pattern = Pattern::new(body).map_err(|e| format!("Invalid ignore pattern {:?}: {}", line, e))?;
+            self.rules.push(Rule { pattern, negated, dir_only, anchored });
+            self.source.push(line.to_string());
+        }
+        Ok(())
+    }
+
+    /// The configured patterns followed by those of `root/.gitignore`, if present.
+    /// Bad lines in the file are skipped; it is not ours to validate.
+    pub fn for_root(config: &WatchConfig, root: &Path) -> Self {
+        let mut rules = Self::parse(config.ignore.iter().map(String::as_str)).unwrap_or_default();
+        if let Ok(text) = std::fs::read_to_string(root.join(IGNORE_FILE)) {
+            for line in text.lines() {
+                let _ = rules.extend([line]);
+            }
+        }
+        rules
+    }
+
+    /// Patterns in effect, in order.
+    pub fn patterns(&self) -> &[String] {
+        &self.source
+    }
+
+    /// Whether `rel` (relative to the watched root) is ignored.
+    pub fn is_ignored(&self, rel: &Path, is_dir: bool) -> bool {
+        let names: Vec<String> = rel.components().filter_map(|c| match c {
+            Component::Normal(name) => Some(name.to_string_lossy().to_string()),
+            _ => None,
+        }).collect();
+        (1..=names.len()).any(|depth| {
+            let candidate = names[..depth].join("/");
+            let candidate_is_dir = depth < names.len() || is_dir;
+            let mut ignored = false;
+            for rule in &self.rules {
+                if rule.dir_only && !candidate_is_dir {
+                    continue;
+                }
+                let subject = if rule.anchored { candidate.as_str() } else { names[depth - 1].as_str() };
+                if rule.pattern.matches_with(subject, MATCH) {
+                    ignored = !rule.negated;
+                }
+            }
+            ignored
+        })
+    }
+}
+
+/// Loads the watch configuration of an agency, or the defaults.
+pub async fn load(pool: &Pool<Sqlite>, agency_id: &str) -> Result<WatchConfig, String> {
+    let row: Option<(String,)> = sqlx::query_as("SELECT config FROM watch_configs WHERE agency_id = ?")
+        .bind(agency_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    match row {
+        Some((json,)) => serde_json::from_str(&json).map_err(|e| format!("Corrupt watch config for {}: {}", agency_id, e)),
+        None => Ok(WatchConfig::default()),
+    }
+}
+
+/// The configured roots of every agency, to tell which agency owns a file.
+pub async fn custom_roots(pool: &Pool<Sqlite>) -> Result<Vec<(String, PathBuf)>, String> {
+    let rows: Vec<(String, String)> = sqlx::query_as("SELECT agency_id, config FROM watch_configs")
+        .fetch_all(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(rows.into_iter()
+        .filter_map(|(agency_id, json)| {
+            let root = serde_json::from_str::<WatchConfig>(&json).ok()?.root?;
+            Some((agency_id, PathBuf::from(root)))
+        })
+        .collect())
+}
+
+/// Persists the watch configuration of an agency, replacing any previous one.
+pub async fn save(pool: &Pool<Sqlite>, agency_id: &str, config: &WatchConfig) -> Result<(), String> {
+    let json = serde_json::to_string(config).map_err(|e| e.to_string())?;
+    sqlx::query("INSERT OR REPLACE INTO watch_configs (agency_id, config, updated_at) VALUES (?, ?, ?)")
+        .bind(agency_id)
+        .bind(json)
+        .bind(chrono::Utc::now().to_rfc3339())
+        .execute(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Returns the watch configuration of the active agency.
+#[tauri::command]
+pub async fn kora_watch_config_get(state: tauri::State<'_, AppState>) -> Result<WatchConfig, String> {
+    let agency_id = state.governance.get_active_agency_id();
+    load(&state.db, &agency_id).await
+}
+
+/// Validates and stores the active agency's watch configuration, then
+/// re-watches its root with it.
+#[tauri::command]
+pub async fn kora_watch_config_set(state: tauri::State<'_, AppState>, config: WatchConfig) -> Result<String, String> {
+    let agency_id = state.governance.get_active_agency_id();
+    config.validate()?;
+    let config = state.watcher.check_config(&agency_id, config)?;
+    save(&state.db, &agency_id, &config).await?;
+    let _ = audit::log_event(
+        &state.db,
+        "WATCH_CONFIG_UPDATE",
+        "RING_1",
+        &format!(
+            "root={} backend={} max_file_bytes={} ignore={}",
+            config.root.as_deref().unwrap_or("default"), config.backend.name(), config.max_file_bytes, config.ignore.len()
+        ),
+        &agency_id,
+    ).await;
+    state.watcher.watch_agency(&agency_id).await?;
+    Ok(format!("Watch config updated for {}", agency_id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gitignore_semantics() {
+        let rules = IgnoreRules::parse(["# drafts", "*.log", "!keep.log", "build/", "/top.md", "docs/*.bak", ""]).unwrap();
+        let ignored = |p: &str| rules.is_ignored(Path::new(p), false);
+        assert!(ignored("a.log") && ignored("deep/nested/b.log"));
+        assert!(!ignored("keep.log"));
+        assert!(ignored("build/out.md") && ignored("src/build/out.md"));
+        assert!(!ignored("build") && rules.is_ignored(Path::new("build"), true));
+        assert!(ignored("top.md") && !ignored("sub/top.md"));
+        assert!(ignored("docs/a.bak") && !ignored("docs/x/a.bak"));
+        assert!(!ignored("notes.md"));
+
+        // Nothing below an ignored directory comes back
+        let rules = IgnoreRules::parse(["tmp/", "!tmp/keep.md"]).unwrap();
+        assert!(rules.is_ignored(Path::new("tmp/keep.md"), false));
+        assert_eq!(rules.patterns(), ["tmp/", "!tmp/keep.md"]);
+    }
+
+    #[test]
+    fn test_default_config_ignores_editor_litter() {
+        let config = WatchConfig::default();
+        config.validate().unwrap();
+        let rules = IgnoreRules::parse(config.ignore.iter().map(String::as_str)).unwrap();
+        for litter in [".notes.md.swp", "notes.md~", "upload.tmp", ".#notes.md", "#notes.md#", "4913", ".git/HEAD", ".gitignore"] {
+            assert!(rules.is_ignored(Path::new(litter), false), "{}", litter);
+        }
+        assert!(!rules.is_ignored(Path::new("core/notes.md"), false));
+
+        assert!(WatchConfig { max_file_bytes: 10, ..Default::default() }.validate().is_err());
+        assert!(WatchConfig { root: Some("knowledge".into()), ..Default::default() }.validate().is_err());
+        assert!(WatchConfig { ignore: vec!["[".into()], ..Default::default() }.validate().is_err());
+        assert!(WatchConfig { backend: WatchBackend::Poll, poll_interval_secs: 0, ..Default::default() }.validate().is_err());
+    }
+
+    #[test]
+    fn test_roots_stay_inside_the_agency() {
+        let tmp = std::env::temp_dir().join(format!("kora-roots-{}", uuid::Uuid::new_v4()));
+        let base = tmp.join("knowledge");
+        let shared = tmp.join("shared");
+        for dir in [base.join("SYSTEM").join("notes"), base.join("OTHER"), shared.join("a").join("deep"), shared.join("b"), tmp.join("private")] {
+            std::fs::create_dir_all(dir).unwrap();
+        }
+        let allowed = vec![shared.clone()];
+        let check = |root: &Path, others: &[(&str, PathBuf)]| {
+            check_root(root, "SYSTEM", &base, &allowed, others.iter().map(|(id, p)| (*id, p.as_path())))
+        };
+
+        let own = check(&base.join("SYSTEM").join("notes"), &[]).unwrap();
+        assert_eq!(own, std::fs::canonicalize(base.join("SYSTEM").join("notes")).unwrap());
+        assert!(check(&shared.join("a"), &[("OTHER", base.join("OTHER"))]).is_ok());
+
+        // Outside the allowed directories, or somewhere that does not exist
+        assert!(check(&tmp.join("private"), &[]).is_err());
+        assert!(check(Path::new("/"), &[]).is_err());
+        assert!(check(&tmp.join("missing"), &[]).is_err());
+        // Another agency's knowledge, or a parent of every agency's
+        assert!(check(&base.join("OTHER"), &[]).is_err());
+        let everything = check_root(&tmp, "SYSTEM", &base, std::slice::from_ref(&tmp), []);
+        assert!(everything.unwrap_err().contains("overlaps"));
+        // Nested in or around another agency's watched root
+        assert!(check(&shared.join("a").join("deep"), &[("OTHER", shared.join("a"))]).is_err());
+        assert!(check(&shared.join("a"), &[("OTHER", shared.join("a").join("deep"))]).is_err());
+        assert!(check(&shared.join("a"), &[("SYSTEM", shared.join("a"))]).is_ok());
+        #[cfg(unix)]
+        {
+            // Symlinks are followed before checking
+            std::os::unix::fs::symlink(tmp.join("private"), shared.join("b").join("link")).unwrap();
+            assert!(check(&shared.join("b").join("link"), &[]).is_err());
+        }
+
+        std::fs::remove_dir_all(&tmp).unwrap();
+    }
+}
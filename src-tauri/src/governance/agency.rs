@@ -3,6 +3,7 @@ use tauri::{AppHandle, Manager, Emitter};
 use serde::{Serialize, Deserialize};
 use crate::AppState;
 use crate::audit;
+use crate::ai_engine::AiEngine;
 
 /// Represents an administrative agency within KORA OS.
 #[derive(Clone, Serialize, Deserialize, Debug, sqlx::FromRow)]
@@ -59,10 +60,17 @@ impl AgencyManager {
 
         // 5. Audit
         let _ = audit::log_event(&app_state.db, "CONTEXT_SWITCH", "RING_1", &format!("Switched to {}", new_agency_id), &new_agency_id).await;
-        
-        // 6. Clear Memory (Simulated memory wipe of OpenClaw context if we had a direct handle, 
-        // effectively handled by next command sent or explicitly sending RESET to engine)
-        // For now, we assume the engine is stateless per request or we send a reset command next.
+
+        // 6. Engine Limits: adopt the new agency's runtime config
+        let engine_config = crate::ai_engine::config::load_validated(&app_state.db, &new_agency_id, app_state.ai_engine.resource_dir(), &app_state.data_dir).await;
+        if let Err(e) = app_state.ai_engine.apply_config(engine_config) {
+            eprintln!("[KORA] Failed to apply engine config for {}: {}", new_agency_id, e);
+        }
+
+        // 7. Clear Memory: restart under the new limits, dropping the previous agency's session
+        if let Err(e) = app_state.ai_engine.reset() {
+            eprintln!("[KORA] Failed to restart the engine for {}: {}", new_agency_id, e);
+        }
 
         Ok(format!("Switched to {}", new_agency_id))
     }
@@ -96,6 +104,11 @@ pub async fn kora_agency_create(app: tauri::AppHandle, state: tauri::State<'_, A
     std::fs::create_dir_all(know_path).map_err(|e| e.to_string())?;
     std::fs::create_dir_all(work_path).map_err(|e| e.to_string())?;
 
+    // Start indexing the new knowledge root
+    if let Err(e) = state.watcher.watch_agency(&id).await {
+        eprintln!("[KORA] Not watching knowledge of {}: {}", id, e);
+    }
+
     // Audit
     let _ = crate::audit::log_event(&state.db, "AGENCY_CREATE", "RING_0", &id, &id).await;
 
@@ -104,13 +117,36 @@ pub async fn kora_agency_create(app: tauri::AppHandle, state: tauri::State<'_, A
 
 #[tauri::command]
 pub async fn kora_agency_list(state: tauri::State<'_, AppState>) -> Result<Vec<Agency>, String> {
-    let agencies = sqlx::query_as::<_, Agency>("SELECT id, name, created_at FROM agencies")
+    let agencies = sqlx::query_as::<_, Agency>("SELECT id, name, created_at FROM agencies WHERE archived_at IS NULL")
         .fetch_all(&state.db)
         .await
         .map_err(|e: sqlx::Error| e.to_string())?;
     Ok(agencies)
 }
 
+/// Archives an agency: it leaves the agency list and its knowledge root is no
+/// longer watched. Its data is kept.
+#[tauri::command]
+pub async fn kora_agency_archive(state: tauri::State<'_, AppState>, id: String) -> Result<String, String> {
+    if id == "SYSTEM" || id == state.governance.get_active_agency_id() {
+        return Err(format!("Cannot archive {}: switch to another agency first", id));
+    }
+    let updated = sqlx::query("UPDATE agencies SET archived_at = ? WHERE id = ? AND archived_at IS NULL")
+        .bind(chrono::Utc::now().to_rfc3339())
+        .bind(&id)
+        .execute(&state.db)
+        .await
+        .map_err(|e| format!("DB Error: {}", e))?
+        .rows_affected();
+    if updated == 0 {
+        return Err(format!("Unknown agency: {}", id));
+    }
+
+    state.watcher.unwatch_agency(&id);
+    let _ = crate::audit::log_event(&state.db, "AGENCY_ARCHIVE", "RING_0", &id, &id).await;
+    Ok(format!("Agency {} archived", id))
+}
+
 #[tauri::command]
 pub async fn cmd_shutdown(app: tauri::AppHandle, state: tauri::State<'_, AppState>) -> Result<(), String> {
     // 1. Audit Shutdown (Immutable Log)
@@ -120,7 +156,10 @@ pub async fn cmd_shutdown(app: tauri::AppHandle, state: tauri::State<'_, AppStat
     // 2. Final Snapshot (Persistence)
     let _ = crate::db::save_session_snapshot(&state.db, &agency_id, "SYSTEM_HALT", "APPROVED", "SHUTDOWN").await;
 
-    // 3. Graceful Exit Protocol
+    // 3. Hang up every terminal session
+    state.pty.shutdown();
+
+    // 4. Graceful Exit Protocol
     let pool = state.db.clone();
     tokio::spawn(async move {
         println!("[KORA] Closing Database Pool...");
@@ -89,3 +89,54 @@ pub async fn enforce(state: &State<'_, AppState>, path: &str, operation: &str) -
         }
     }
 }
+
+/// Where an agency's jailed roots live on disk.
+///
+/// Commands see `/knowledge/{agency}` and `/workspace/{agency}`; the files are
+/// under the app data dir (or the configured watch root). Other whitelisted
+/// paths map to themselves.
+#[derive(Clone, Debug)]
+pub struct Namespace {
+    roots: Vec<(PathBuf, PathBuf)>,
+}
+
+impl Namespace {
+    pub fn new(agency_id: &str, knowledge: PathBuf, workspace: PathBuf) -> Self {
+        Self {
+            roots: vec![
+                (PathBuf::from(format!("/knowledge/{}", agency_id)), knowledge),
+                (PathBuf::from(format!("/workspace/{}", agency_id)), workspace),
+            ],
+        }
+    }
+
+    /// The disk path behind a jailed path.
+    pub fn to_disk(&self, path: &Path) -> PathBuf {
+        self.roots.iter()
+            .find_map(|(virtual_root, disk)| path.strip_prefix(virtual_root).ok().map(|rel| join(disk, rel)))
+            .unwrap_or_else(|| path.to_path_buf())
+    }
+
+    /// The jailed path of a file on disk, if it is under one of the roots.
+    pub fn to_virtual(&self, path: &Path) -> Option<PathBuf> {
+        self.roots.iter()
+            .find_map(|(virtual_root, disk)| path.strip_prefix(disk).ok().map(|rel| join(virtual_root, rel)))
+    }
+}
+
+/// `root/rel`, without a trailing separator for an empty `rel`.
+fn join(root: &Path, rel: &Path) -> PathBuf {
+    if rel.as_os_str().is_empty() { root.to_path_buf() } else { root.join(rel) }
+}
+
+/// The namespace of `agency_id`.
+pub fn namespace(state: &AppState, agency_id: &str) -> Namespace {
+    let knowledge = state.watcher.root(agency_id).unwrap_or_else(|| state.data_dir.join("knowledge").join(agency_id));
+    Namespace::new(agency_id, knowledge, state.data_dir.join("workspace").join(agency_id))
+}
+
+/// Enforces the jail on `path` and returns where it is on disk.
+pub async fn resolve(state: &State<'_, AppState>, path: &str, operation: &str) -> Result<PathBuf, String> {
+    let valid = enforce(state, path, operation).await?;
+    Ok(namespace(state, &state.governance.get_active_agency_id()).to_disk(&valid))
+}
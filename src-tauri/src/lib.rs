@@ -8,10 +8,11 @@ mod plugins;
 mod ai_engine;
 mod governance;
 mod security;
+mod shell;
 
 use crate::pty::PtyManager;
-use crate::ai_engine::OpenClawEngine;
-use crate::governance::agency::{AgencyManager, kora_agency_create, kora_agency_list, kora_agency_switch, cmd_shutdown};
+use crate::ai_engine::{AiEngine, EngineHost};
+use crate::governance::agency::{AgencyManager, kora_agency_create, kora_agency_list, kora_agency_switch, kora_agency_archive, cmd_shutdown};
 use crate::security::vault::SecretVault;
 use sqlx::{Pool, Sqlite};
 use std::sync::atomic::{AtomicBool, Ordering};
@@ -19,6 +20,7 @@ use std::sync::{Arc, RwLock};
 use tauri::{Emitter, Manager, State};
 use sysinfo::System;
 use serde::Serialize;
+use base64::Engine;
 
 
 #[derive(Serialize, Clone)]
@@ -37,8 +39,8 @@ pub struct AppState {
     pub bridge_locked: Arc<AtomicBool>,
     /// SQLite database connection pool (sqlx).
     pub db: Pool<Sqlite>,
-    /// AI Engine instance for OpenClaw orchestrations.
-    pub ai_engine: OpenClawEngine,
+    /// AI Engine host for OpenClaw orchestrations (backend chosen per agency).
+    pub ai_engine: EngineHost,
     /// Administrative governance manager for agencies.
     pub governance: AgencyManager,
     /// Secure vault for ephemeral keys and sensitive data.
@@ -47,6 +49,12 @@ pub struct AppState {
     pub integrity_cache: Arc<RwLock<Option<String>>>,
     /// Timestamp recorded at kernel initialization.
     pub boot_time: std::time::Instant,
+    /// Debounced queue the knowledge watcher feeds.
+    pub index_queue: drivers::index_queue::IndexQueue,
+    /// Watches every agency's knowledge root.
+    pub watcher: drivers::watch::WatchDriver,
+    /// App data dir holding the agencies' `knowledge` and `workspace` roots.
+    pub data_dir: std::path::PathBuf,
 }
 
 impl AppState {
@@ -68,18 +76,11 @@ impl AppState {
 
 /// Returns true if the kernel state is accessible, indicating a successful cold start.
 #[tauri::command]
-fn kora_kernel_status(state: State<'_, AppState>) -> bool {
-    // If we can access state, the kernel has been initialized
+fn kora_kernel_status(_state: State<'_, AppState>) -> bool {
+    // Tauri rejects the call until AppState is managed, so reaching here means the kernel is up
     true
 }
 
-#[tauri::command]
-fn pty_write(state: State<'_, AppState>, data: String) {
-    if !state.bridge_locked.load(Ordering::SeqCst) {
-        state.pty.write(&data);
-    }
-}
-
 #[tauri::command]
 fn heartbeat() -> String {
     "PULSE_OK".to_string()
@@ -92,7 +93,7 @@ fn set_bridge_lock(state: State<'_, AppState>, locked: bool) {
 
 // Phase 3 Commands
 #[tauri::command]
-async fn index_file(state: State<'_, AppState>, path: String) -> Result<String, String> {
+async fn index_file(state: State<'_, AppState>, path: String) -> Result<rag::IndexStats, String> {
     // Jail Enforcement
     let valid_path = jail::enforce(&state, &path, "INDEX_FILE").await?;
 
@@ -101,8 +102,9 @@ async fn index_file(state: State<'_, AppState>, path: String) -> Result<String,
     let agency_id = state.governance.get_active_agency_id();
     let _ = audit::log_event(&state.db, "INDEX_REQUEST", "RING_1", &path_str, &agency_id).await;
     
-    // Execute RAG indexing
-    rag::index_file(&state.db, &path_str).await
+    // Execute RAG indexing for the agency owning the path
+    let owner = rag::resolve_agency(&state.db, &valid_path, &state.data_dir.join("knowledge"), &agency_id).await?;
+    rag::index_file(&state.db, &path_str, &owner).await
 }
 
 #[tauri::command]
@@ -159,7 +161,7 @@ async fn kora_system_benchmark(state: State<'_, AppState>) -> Result<serde_json:
 }
 
 #[tauri::command]
-async fn kora_system(state: State<'_, AppState>, action: String) -> Result<String, String> {
+async fn kora_system(state: State<'_, AppState>, action: String, session_id: Option<String>) -> Result<String, String> {
     // 1. Audit
     let agency_id = state.governance.get_active_agency_id();
     let _ = audit::log_event(&state.db, "KORA_SYSTEM", "RING_3", &action, &agency_id).await;
@@ -169,30 +171,54 @@ async fn kora_system(state: State<'_, AppState>, action: String) -> Result<Strin
     // 3. Vault Environment
     let _env = state.vault.get_ephemeral_env();
     
-    // 4. Send to Engine
-    state.ai_engine.send_command(&state, &format!("SYSTEM {}", action)).await?;
+    // 4. Send to Engine; the answer streams on `kora-stream-{request_id}`
+    let request_id = ai_engine::send_command(&state, "SYSTEM", session_id.as_deref(), &format!("SYSTEM {}", action)).await?;
 
     // 5. Session Vault (Snapshot) 
     let _ = db::save_session_snapshot(&state.db, &agency_id, &format!("SYSTEM: {}", action), "PENDING", "SNAPSHOT_PENDING").await;
     
-    Ok("Command Sent".to_string())
+    Ok(request_id)
+}
+
+/// A grounded answer in flight: the stream to follow and the sources it was given.
+#[derive(Serialize)]
+struct KnowledgeAnswer {
+    request_id: String,
+    citations: Vec<rag::retrieval::Citation>,
 }
 
 #[tauri::command]
-async fn kora_knowledge(state: State<'_, AppState>, query: String) -> Result<String, String> {
+async fn kora_knowledge(state: State<'_, AppState>, query: String, session_id: Option<String>) -> Result<KnowledgeAnswer, String> {
     // 1. Audit
     let agency_id = state.governance.get_active_agency_id();
     let _ = audit::log_event(&state.db, "KORA_KNOWLEDGE", "RING_3", "QUERY_REDACTED", &agency_id).await; 
     
-    // 2. Send to Engine
-    // 3. Vault Environment
+    // 2. Vault Environment
     let _env = state.vault.get_ephemeral_env();
 
-    // 4. Session Vault (Snapshot) with Agency Context
-    let agency_id = state.governance.get_active_agency_id();
+    // 3. Retrieve context from the agency's index (only the question is jail-checked;
+    //    excerpts come from files that passed the jail at index time)
+    ai_engine::check_command_paths(&state, &query).await?;
+    let chunks = rag::retrieval::retrieve(&state.db, &agency_id, &query, rag::retrieval::DEFAULT_TOP_K).await?;
+    let (context, mut citations) = rag::retrieval::assemble_context(&chunks, rag::retrieval::CONTEXT_TOKEN_BUDGET);
+    // Cite jailed paths, which `kora_knowledge_document` accepts, never host paths
+    rag::inventory::jail_citations(&jail::namespace(&state, &agency_id), &mut citations);
+
+    // 4. Send to Engine; the answer streams on `kora-stream-{request_id}`
+    let request_id = state.ai_engine.submit("KNOWLEDGE", &agency_id, session_id.as_deref(), &rag::retrieval::build_prompt(&query, &context))?;
+
+    // 5. Session Vault (Snapshot) with Agency Context
     let _ = db::save_session_snapshot(&state.db, &agency_id, &format!("KNOWLEDGE: {}", query), "PENDING", "SNAPSHOT_PENDING").await;
     
-    Ok("Query Sent".to_string())
+    Ok(KnowledgeAnswer { request_id, citations })
+}
+
+#[tauri::command]
+async fn kora_cancel(state: State<'_, AppState>, request_id: String) -> Result<String, String> {
+    let request = state.ai_engine.cancel_request(&request_id)?;
+    let metadata = format!("request_id={} kind={}", request.id, request.kind);
+    let _ = audit::log_event(&state.db, "REQUEST_CANCELLED", "RING_3", &metadata, &request.agency_id).await;
+    Ok(format!("Request {} cancelled", request_id))
 }
 
 
@@ -206,21 +232,38 @@ pub fn run() {
         .setup(|app| {
             let app_handle = app.handle().clone();
             
-            // Initialize PTY
-            let pty_manager = PtyManager::new(app_handle.clone());
+            // Initialize PTY sessions (per-session output, global exit events)
+            let pty_events = app_handle.clone();
+            let pty_manager = PtyManager::new(Arc::new(move |event| match event {
+                pty::PtyEvent::Data { session_id, data } => { let _ = pty_events.emit(&format!("pty-data-{}", session_id), data); }
+                pty::PtyEvent::Raw { session_id, data } => {
+                    let _ = pty_events.emit(&format!("pty-raw-{}", session_id), base64::engine::general_purpose::STANDARD.encode(data));
+                }
+                pty::PtyEvent::Recorded(recording) => {
+                    let app = pty_events.clone();
+                    tauri::async_runtime::spawn(async move {
+                        if let Some(state) = app.try_state::<AppState>() {
+                            match pty::recording::register(&state.db, &recording).await {
+                                Ok(recording) => { let _ = app.emit("pty-recorded", recording); }
+                                Err(e) => eprintln!("[PTY] Failed to register recording {}: {}", recording.id, e),
+                            }
+                        }
+                    });
+                }
+                pty::PtyEvent::Exit(exit) => { let _ = pty_events.emit("pty-exit", exit); }
+            }), app.path().app_data_dir().map_err(|e| format!("Failed to resolve app data dir: {}", e))?.join("recordings"));
             
-            // Initialize AI Engine
-            let ai_engine = OpenClawEngine::new(app_handle.clone());
+            // Initialize AI Engine (KORA_DEMO pins the scripted mock backend)
+            let resource_dir = app.path().resource_dir().map_err(|e| format!("Failed to resolve resources: {}", e))?;
+            let demo_mode = std::env::var("KORA_DEMO").is_ok();
+            let vault = SecretVault::new();
+            vault.set_secret("KORA_MODE", if demo_mode { "DEMO" } else { "PRODUCTION" });
+            let ai_engine = EngineHost::new(resource_dir, vault.clone(), demo_mode);
+            ai_engine::forward_events(app_handle.clone(), ai_engine.subscribe(), ai_engine.requests());
             
             // Initialize Agency Manager
             let agency_manager = AgencyManager::new(app_handle.clone());
-            // Attempt to spawn, log error if fails but allow app to start
-            if let Err(e) = ai_engine.spawn() {
-                eprintln!("[KORA] Failed to spawn OpenClaw: {}", e);
-            }
-            
-            // Initialize Watch Driver
-            drivers::watch::init_watcher(app_handle.clone());
+
 
             // Initialize System Monitor (2Hz)
             let app_handle_monitor = app_handle.clone();
@@ -257,18 +300,41 @@ pub fn run() {
                 let (db_res,) = tokio::join!(db_init);
                 let db_pool = db_res.expect("Failed to init DB");
                 
-                let vault = SecretVault::new();
-                vault.set_secret("KORA_MODE", "PRODUCTION");
+
+                let data_dir = app_handle_for_setup.path().app_data_dir().expect("failed to get app data dir");
+
+                // Load and validate the engine config before the first spawn.
+                // Attempt to spawn, log error if fails but allow app to start
+                let engine_config = ai_engine::config::load_validated(&db_pool, &agency_manager.get_active_agency_id(), ai_engine.resource_dir(), &data_dir).await;
+                if let Err(e) = ai_engine.apply_config(engine_config) {
+                    eprintln!("[KORA] Failed to apply engine config: {}", e);
+                }
+                if let Err(e) = ai_engine.spawn() {
+                    eprintln!("[KORA] Failed to spawn OpenClaw: {}", e);
+                }
+
+                let progress_handle = app_handle_for_setup.clone();
+                let index_queue = drivers::index_queue::IndexQueue::start(
+                    db_pool.clone(),
+                    data_dir.join("knowledge"),
+                    drivers::index_queue::QueueConfig::default(),
+                    Arc::new(move |progress| { let _ = progress_handle.emit("kora-index-progress", progress); }),
+                ).await.expect("Failed to start index queue");
+                let watcher = drivers::watch::WatchDriver::start(db_pool.clone(), index_queue.clone(), data_dir.join("knowledge"));
+                watcher.watch_all().await;
 
                 app_handle_for_setup.manage(AppState {
                     pty: pty_manager,
                     bridge_locked: Arc::new(AtomicBool::new(false)),
                     db: db_pool,
-                    ai_engine: ai_engine,
+                    ai_engine,
                     governance: agency_manager,
-                    vault: vault,
+                    vault,
                     integrity_cache: Arc::new(RwLock::new(None)),
                     boot_time: std::time::Instant::now(),
+                    index_queue,
+                    watcher,
+                    data_dir,
                 });
 
                 // 2. Signal UI that Kernel is Hot
@@ -281,7 +347,18 @@ pub fn run() {
         .invoke_handler(tauri::generate_handler![
             kora_kernel_status,
             kora_system_benchmark,
-            pty_write,
+            pty::pty_create,
+            pty::pty_write,
+            pty::pty_resize,
+            pty::pty_close,
+            pty::pty_list,
+            pty::pty_attach,
+            pty::signals::pty_signal,
+            pty::signals::pty_signal_config_get,
+            pty::signals::pty_signal_config_set,
+            pty::recording::pty_recording_list,
+            pty::recording::pty_recording_export,
+            pty::recording::pty_recording_replay,
             heartbeat,
             set_bridge_lock,
             index_file,
@@ -289,12 +366,36 @@ pub fn run() {
             drivers::notify::send_notification,
             kora_system,
             kora_knowledge,
+            kora_cancel,
+            rag::search::kora_knowledge_search,
+            rag::lifecycle::kora_knowledge_reindex,
+            rag::inventory::kora_knowledge_list,
+            rag::inventory::kora_knowledge_document,
+            rag::inventory::kora_knowledge_remove,
+            rag::inventory::kora_knowledge_tag,
+            rag::organize::kora_knowledge_organize,
+            rag::learn::kora_knowledge_learn,
             kora_agency_create,
             kora_agency_list,
             kora_agency_switch,
+            kora_agency_archive,
+            drivers::watch::kora_watch_status,
+            drivers::watch_config::kora_watch_config_get,
+            drivers::watch_config::kora_watch_config_set,
             kora_kernel_integrity,
+            ai_engine::config::kora_engine_config_get,
+            ai_engine::config::kora_engine_config_set,
+            ai_engine::config::kora_engine_set_api_key,
             cmd_shutdown
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while running tauri application")
+        .run(|app, event| {
+            // No shell may outlive the kernel
+            if let tauri::RunEvent::Exit = event {
+                if let Some(state) = app.try_state::<AppState>() {
+                    state.pty.shutdown();
+                }
+            }
+        });
 }
@@ -0,0 +1,197 @@
+/// Lines starting with this are run by the kernel instead of the shell.
+pub const COMMAND_PREFIX: &str = "kora ";
+
+/// What to do with a piece of typed input, in order.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum InputAction {
+    /// Send to the shell.
+    Forward(String),
+    /// Show in the terminal without involving the shell.
+    Echo(String),
+    /// A finished `kora` line for the interpreter.
+    Line(String),
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+enum Mode {
+    /// At the start of a line, holding back input while it could be a command.
+    #[default]
+    Start,
+    /// Editing a command; nothing reaches the shell.
+    Command,
+    /// A shell line; everything is forwarded until it ends.
+    Passthrough,
+}
+
+/// Tracks what is typed at the shell prompt to pick out `kora` lines.
+///
+/// Input is held back and echoed locally while it is a prefix of
+/// `kora `, so the shell never sees a command. Anything else is handed to the
+/// shell, erasing the local echo first, so its own editing and echo apply.
+#[derive(Debug, Default)]
+pub struct LineInput {
+    mode: Mode,
+    line: String,
+}
+
+fn ends_line(c: char) -> bool {
+    matches!(c, '\r' | '\n' | '\x03' | '\x15' | '\x1a')
+}
+
+fn erase(n: usize) -> String {
+    "\x08 \x08".repeat(n)
+}
+
+impl LineInput {
+    /// Handles typed `data`. With `intercept` off (a job owns the terminal)
+    /// input goes straight to the shell.
+    pub fn feed(&mut self, data: &str, intercept: bool) -> Vec<InputAction> {
+        let mut actions = Vec::new();
+        // Escape sequences (arrows, function keys) mean line editing we cannot follow
+        if data.starts_with('\x1b') || !intercept && self.mode == Mode::Start {
+            match self.mode {
+                Mode::Command => {}
+                _ => {
+                    self.release(&mut actions, data);
+                    self.mode = if data.chars().last().is_some_and(ends_line) { Mode::Start } else { Mode::Passthrough };
+                }
+            }
+            return actions;
+        }
+        for c in data.chars() {
+            match self.mode {
+                Mode::Passthrough => {
+                    push(&mut actions, InputAction::Forward(c.to_string()));
+                    if ends_line(c) {
+                        self.mode = Mode::Start;
+                    }
+                }
+                Mode::Start => {
+                    if c == '\x7f' && !self.line.is_empty() {
+                        self.line.pop();
+                        push(&mut actions, InputAction::Echo(erase(1)));
+                    } else if (c == '\r' || c == '\n') && self.line == COMMAND_PREFIX.trim_end() {
+                        push(&mut actions, InputAction::Echo("\r\n".to_string()));
+                        actions.push(InputAction::Line(std::mem::take(&mut self.line)));
+                    } else if !c.is_control() && COMMAND_PREFIX.starts_with(&format!("{}{}", self.line, c)) {
+                        self.line.push(c);
+                        push(&mut actions, InputAction::Echo(c.to_string()));
+                        if self.line == COMMAND_PREFIX {
+                            self.mode = Mode::Command;
+                        }
+                    } else {
+                        self.release(&mut actions, &c.to_string());
+                        // A backspace at an empty prompt leaves it empty
+                        if !ends_line(c) && c != '\x7f' {
+                            self.mode = Mode::Passthrough;
+                        }
+                    }
+                }
+                Mode::Command => match c {
+                    '\r' | '\n' => {
+                        push(&mut actions, InputAction::Echo("\r\n".to_string()));
+                        actions.push(InputAction::Line(std::mem::take(&mut self.line)));
+                        self.mode = Mode::Start;
+                    }
+                    '\x7f' => {
+                        self.line.pop();
+                        push(&mut actions, InputAction::Echo(erase(1)));
+                        if !self.line.starts_with(COMMAND_PREFIX) {
+                            self.mode = Mode::Start;
+                        }
+                    }
+                    '\x15' => {
+                        push(&mut actions, InputAction::Echo(erase(self.line.chars().count())));
+                        self.reset();
+                    }
+                    c if c.is_control() => {}
+                    c => {
+                        self.line.push(c);
+                        push(&mut actions, InputAction::Echo(c.to_string()));
+                    }
+                },
+            }
+        }
+        actions
+    }
+
+    /// Forgets the current line, e.g. after Ctrl+C. Its echo stays on screen.
+    pub fn reset(&mut self) {
+        self.line.clear();
+        self.mode = Mode::Start;
+    }
+
+    /// Hands the held input plus `data` to the shell.
+    fn release(&mut self, actions: &mut Vec<InputAction>, data: &str) {
+        if !self.line.is_empty() {
+            push(actions, InputAction::Echo(erase(self.line.chars().count())));
+        }
+        let held = std::mem::take(&mut self.line);
+        push(actions, InputAction::Forward(held + data));
+    }
+}
+
+/// Appends, merging with the previous action of the same kind.
+fn push(actions: &mut Vec<InputAction>, action: InputAction) {
+    match (actions.last_mut(), action) {
+        (Some(InputAction::Forward(prev)), InputAction::Forward(next)) => prev.push_str(&next),
+        (Some(InputAction::Echo(prev)), InputAction::Echo(next)) => prev.push_str(&next),
+        (_, action) => actions.push(action),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use InputAction::*;
+
+    fn typed(input: &mut LineInput, keys: &[&str]) -> Vec<InputAction> {
+        let mut actions = Vec::new();
+        for key in keys {
+            for action in input.feed(key, true) {
+                push(&mut actions, action);
+            }
+        }
+        actions
+    }
+
+    #[test]
+    fn test_kora_lines_never_reach_the_shell() {
+        let mut input = LineInput::default();
+        let keys: Vec<String> = "kora agency lisx".chars().map(String::from).collect();
+        let mut keys: Vec<&str> = keys.iter().map(String::as_str).collect();
+        keys.extend(["\x7f", "t", "\r"]);
+        assert_eq!(typed(&mut input, &keys), vec![
+            Echo("kora agency lisx\x08 \x08t\r\n".to_string()),
+            Line("kora agency list".to_string()),
+        ]);
+
+        // A pasted line works the same
+        assert_eq!(input.feed("kora system status\r", true), vec![
+            Echo("kora system status\r\n".to_string()),
+            Line("kora system status".to_string()),
+        ]);
+        assert_eq!(input.feed("kora\r", true), vec![Echo("kora\r\n".to_string()), Line("kora".to_string())]);
+    }
+
+    #[test]
+    fn test_other_input_goes_to_the_shell() {
+        let mut input = LineInput::default();
+        // "ko" was held back and echoed; "l" makes it a shell line
+        assert_eq!(typed(&mut input, &["k", "o", "l", "a", "\r"]), vec![
+            Echo("ko\x08 \x08\x08 \x08".to_string()),
+            Forward("kola\r".to_string()),
+        ]);
+        // Mid-line "kora" is shell input
+        assert_eq!(input.feed("echo kora \r", true), vec![Forward("echo kora \r".to_string())]);
+        // Arrow keys hand the line to the shell
+        assert_eq!(typed(&mut input, &["k", "\x1b[A"]), vec![Echo("k\x08 \x08".to_string()), Forward("k\x1b[A".to_string())]);
+        assert_eq!(input.feed("\r", true), vec![Forward("\r".to_string())]);
+
+        // While a job owns the terminal nothing is held back
+        assert_eq!(input.feed("kora x\r", false), vec![Forward("kora x\r".to_string())]);
+        assert_eq!(input.feed("k", true), vec![Echo("k".to_string())]);
+        input.reset();
+        assert_eq!(input.feed("ls\r", true), vec![Forward("ls\r".to_string())]);
+    }
+}
@@ -0,0 +1,643 @@
+use base64::Engine;
+use portable_pty::{native_pty_system, ChildKiller, CommandBuilder, MasterPty, PtySize};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+use tauri::State;
+use crate::AppState;
+
+pub mod input;
+pub mod output;
+pub mod recording;
+pub mod signals;
+
+use input::{InputAction, LineInput};
+use output::{FrameBatcher, ReadBuffer, Scrollback, Utf8Decoder};
+use recording::{Recorder, RecordingInfo};
+use signals::{ControlKey, PtySignal};
+
+/// Events produced by PTY sessions; the app forwards them to the UI.
+#[derive(Clone, Debug, PartialEq)]
+pub enum PtyEvent {
+    /// Output of a session (`pty-data-{id}`).
+    Data { session_id: String, data: String },
+    /// Undecoded output of a raw-mode session (`pty-raw-{id}`, base64).
+    Raw { session_id: String, data: Vec<u8> },
+    /// A recorded session's cast file was closed; sent before its exit.
+    Recorded(RecordingInfo),
+    /// The session's shell ended (`pty-exit`).
+    Exit(PtyExit),
+}
+
+/// Payload of the `pty-exit` event.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct PtyExit {
+    pub session_id: String,
+    pub code: u32,
+    pub success: bool,
+    /// e.g. "Exited with code 3" or "Terminated by Hangup".
+    pub status: String,
+}
+
+pub type PtyEventSink = Arc<dyn Fn(PtyEvent) + Send + Sync>;
+
+/// A terminal session as listed to the UI.
+#[derive(Clone, Debug, Serialize)]
+pub struct SessionInfo {
+    pub id: String,
+    /// Agency active when the session was opened.
+    pub agency_id: String,
+    pub shell: String,
+    pub cols: u16,
+    pub rows: u16,
+    /// Output is sent as bytes rather than decoded text.
+    pub raw: bool,
+    /// Output is recorded to an asciicast file.
+    pub record: bool,
+    pub created_at: String,
+}
+
+/// Returned by `pty_attach`: a running session and its recent output.
+#[derive(Clone, Debug, Serialize)]
+pub struct Attachment {
+    pub session: SessionInfo,
+    /// Buffered output, base64 for raw sessions like their `pty-raw` events.
+    pub history: String,
+    /// Bytes evicted from the buffer; older output is lost when non-zero.
+    pub dropped: u64,
+}
+
+/// Options of a new session.
+#[derive(Clone, Copy, Debug)]
+pub struct SessionOptions {
+    pub cols: u16,
+    pub rows: u16,
+    /// Send output undecoded, for consumers that handle binary or their own decoding.
+    pub raw: bool,
+    /// Record output to `{recordings}/{agency}/{session}.cast`.
+    pub record: bool,
+}
+
+impl Default for SessionOptions {
+    fn default() -> Self {
+        Self { cols: 80, rows: 24, raw: false, record: false }
+    }
+}
+
+struct Session {
+    info: Mutex<SessionInfo>,
+    master: Mutex<Box<dyn MasterPty + Send>>,
+    writer: Mutex<Box<dyn Write + Send>>,
+    killer: Mutex<Box<dyn ChildKiller + Send + Sync>>,
+    /// Taken when the session ends.
+    recording: Arc<Mutex<Option<Recorder>>>,
+    scrollback: Arc<Mutex<Scrollback>>,
+    /// Feeds kernel output into the session's stream; dropped once the shell exits.
+    output: Arc<Mutex<Option<mpsc::Sender<Vec<u8>>>>>,
+    input: Mutex<LineInput>,
+    shell_pid: Option<u32>,
+}
+
+/// Owns the terminal sessions of the kernel, one shell per xterm.js view.
+pub struct PtyManager {
+    sessions: Arc<Mutex<HashMap<String, Arc<Session>>>>,
+    sink: PtyEventSink,
+    recordings_dir: PathBuf,
+}
+
+fn size(cols: u16, rows: u16) -> Result<PtySize, String> {
+    if !(1..=1000).contains(&cols) || !(1..=1000).contains(&rows) {
+        return Err(format!("Terminal size must be between 1x1 and 1000x1000, got {}x{}", cols, rows));
+    }
+    Ok(PtySize { rows, cols, pixel_width: 0, pixel_height: 0 })
+}
+
+fn default_shell() -> CommandBuilder {
+    let shell = if cfg!(target_os = "windows") {
+        "cmd.exe"
+    } else {
+        "bash"
+    };
+    CommandBuilder::new(shell)
+}
+
+impl PtyManager {
+    pub fn new(sink: PtyEventSink, recordings_dir: PathBuf) -> Self {
+        Self { sessions: Arc::new(Mutex::new(HashMap::new())), sink, recordings_dir }
+    }
+
+    /// Opens a session running the default shell.
+    pub fn create(&self, agency_id: &str, options: SessionOptions) -> Result<SessionInfo, String> {
+        self.spawn(default_shell(), agency_id, options)
+    }
+
+    /// Opens a session running `cmd`. Output is streamed to the sink in frames
+    /// until the command exits, then an exit event is sent and the session is dropped.
+    pub fn spawn(&self, cmd: CommandBuilder, agency_id: &str, options: SessionOptions) -> Result<SessionInfo, String> {
+        let SessionOptions { cols, rows, raw, record } = options;
+        let pair = native_pty_system().openpty(size(cols, rows)?).map_err(|e| format!("Failed to open PTY: {}", e))?;
+        let shell = cmd.get_argv().first().map(|a| a.to_string_lossy().to_string()).unwrap_or_default();
+        let mut child = pair.slave.spawn_command(cmd).map_err(|e| format!("Failed to spawn {}: {}", shell, e))?;
+        // Only the child may hold the slave end, so reads hit EOF once it exits
+        drop(pair.slave);
+
+        let mut reader = pair.master.try_clone_reader().map_err(|e| e.to_string())?;
+        let writer = pair.master.take_writer().map_err(|e| e.to_string())?;
+        let info = SessionInfo {
+            id: uuid::Uuid::new_v4().to_string(),
+            agency_id: agency_id.to_string(),
+            shell,
+            cols,
+            rows,
+            raw,
+            record,
+            created_at: chrono::Utc::now().to_rfc3339(),
+        };
+        let recorder = match record {
+            true => {
+                let path = self.recordings_dir.join(agency_id).join(format!("{}.cast", info.id));
+                match Recorder::create(&path, &info) {
+                    Ok(recorder) => Some(recorder),
+                    Err(e) => {
+                        let _ = child.kill();
+                        return Err(format!("Failed to start recording {}: {}", path.display(), e));
+                    }
+                }
+            }
+            false => None,
+        };
+        let recording = Arc::new(Mutex::new(recorder));
+        let scrollback = Arc::new(Mutex::new(Scrollback::default()));
+        let (chunks_tx, chunks_rx) = mpsc::channel::<Vec<u8>>();
+        let output = Arc::new(Mutex::new(Some(chunks_tx.clone())));
+        let session = Arc::new(Session {
+            info: Mutex::new(info.clone()),
+            master: Mutex::new(pair.master),
+            writer: Mutex::new(writer),
+            killer: Mutex::new(child.clone_killer()),
+            recording: recording.clone(),
+            scrollback: scrollback.clone(),
+            output: output.clone(),
+            input: Mutex::new(LineInput::default()),
+            shell_pid: child.process_id(),
+        });
+        self.sessions.lock().unwrap().insert(info.id.clone(), session);
+
+        // Read loop, handing chunks to the framing thread
+        thread::spawn(move || {
+            let mut buffer = ReadBuffer::default();
+            loop {
+                match buffer.read_from(&mut reader) {
+                    Ok(chunk) if !chunk.is_empty() => {
+                        if chunks_tx.send(chunk).is_err() {
+                            break;
+                        }
+                    }
+                    Ok(_) => break, // EOF
+                    Err(_) => break,
+                }
+            }
+        });
+
+        let (drained_tx, drained_rx) = mpsc::channel::<()>();
+        let (sink, session_id, pump_recording) = (self.sink.clone(), info.id.clone(), recording.clone());
+        thread::spawn(move || {
+            pump(chunks_rx, raw, &session_id, &sink, &pump_recording, &scrollback);
+            let _ = drained_tx.send(());
+        });
+
+        // Exit watch: report once the output is drained. A background job that
+        // keeps the terminal open must not hold the exit event back for long.
+        let (sink, sessions, session_id) = (self.sink.clone(), self.sessions.clone(), info.id.clone());
+        thread::spawn(move || {
+            let status = child.wait();
+            output.lock().unwrap().take();
+            let _ = drained_rx.recv_timeout(Duration::from_millis(500));
+            sessions.lock().unwrap().remove(&session_id);
+            let recorder = recording.lock().unwrap().take();
+            if let Some(recorder) = recorder {
+                match recorder.finish() {
+                    Ok(recorded) => sink(PtyEvent::Recorded(recorded)),
+                    Err(e) => eprintln!("[PTY] Failed to close recording of {}: {}", session_id, e),
+                }
+            }
+            let exit = match status {
+                Ok(status) => PtyExit { session_id, code: status.exit_code(), success: status.success(), status: status.to_string() },
+                Err(e) => PtyExit { session_id, code: 1, success: false, status: format!("Wait failed: {}", e) },
+            };
+            sink(PtyEvent::Exit(exit));
+        });
+
+        Ok(info)
+    }
+
+    fn session(&self, id: &str) -> Result<Arc<Session>, String> {
+        self.sessions.lock().unwrap().get(id).cloned().ok_or_else(|| format!("Unknown terminal session: {}", id))
+    }
+
+    pub fn info(&self, id: &str) -> Result<SessionInfo, String> {
+        Ok(self.session(id)?.info.lock().unwrap().clone())
+    }
+
+    /// The session with the output a reloaded view needs to restore it. Frames
+    /// sent after this call are not part of the history.
+    pub fn attach(&self, id: &str) -> Result<Attachment, String> {
+        let session = self.session(id)?;
+        let info = session.info.lock().unwrap().clone();
+        let (bytes, dropped) = {
+            let scrollback = session.scrollback.lock().unwrap();
+            (scrollback.snapshot(), scrollback.dropped())
+        };
+        // A character still being received is left to the live stream
+        let history = match info.raw {
+            true => base64::engine::general_purpose::STANDARD.encode(&bytes),
+            false => Utf8Decoder::default().decode(&bytes),
+        };
+        Ok(Attachment { session: info, history, dropped })
+    }
+
+    /// Handles typed input: `kora` lines are held back from the shell and
+    /// returned, everything else is written to it.
+    pub fn input(&self, id: &str, data: &str) -> Result<Vec<String>, String> {
+        let session = self.session(id)?;
+        let intercept = !session.info.lock().unwrap().raw && shell_in_foreground(&session);
+        let actions = session.input.lock().unwrap().feed(data, intercept);
+        let mut lines = Vec::new();
+        for action in actions {
+            match action {
+                InputAction::Forward(data) => self.write(id, &data)?,
+                InputAction::Echo(text) => self.print(id, &text)?,
+                InputAction::Line(line) => lines.push(line),
+            }
+        }
+        Ok(lines)
+    }
+
+    /// Forgets a partly typed `kora` line, e.g. on Ctrl+C.
+    pub fn reset_input(&self, id: &str) -> Result<(), String> {
+        self.session(id)?.input.lock().unwrap().reset();
+        Ok(())
+    }
+
+    /// Shows kernel output in the session, in order with the shell's own.
+    pub fn print(&self, id: &str, text: &str) -> Result<(), String> {
+        let session = self.session(id)?;
+        let output = session.output.lock().unwrap();
+        match output.as_ref() {
+            Some(tx) => tx.send(text.as_bytes().to_vec()).map_err(|_| format!("Session {} has ended", id)),
+            None => Err(format!("Session {} has ended", id)),
+        }
+    }
+
+    /// Has the shell print a fresh prompt after kernel output.
+    pub fn prompt(&self, id: &str) -> Result<(), String> {
+        self.write(id, "\r")
+    }
+
+    pub fn write(&self, id: &str, data: &str) -> Result<(), String> {
+        let session = self.session(id)?;
+        let mut writer = session.writer.lock().unwrap();
+        writer.write_all(data.as_bytes()).and_then(|_| writer.flush()).map_err(|e| e.to_string())
+    }
+
+    /// Resizes the terminal; the shell receives SIGWINCH.
+    pub fn resize(&self, id: &str, cols: u16, rows: u16) -> Result<(), String> {
+        let session = self.session(id)?;
+        session.master.lock().unwrap().resize(size(cols, rows)?).map_err(|e| e.to_string())?;
+        if let Some(recorder) = session.recording.lock().unwrap().as_mut() {
+            let _ = recorder.resize(cols, rows);
+        }
+        let mut info = session.info.lock().unwrap();
+        info.cols = cols;
+        info.rows = rows;
+        Ok(())
+    }
+
+    /// Sends `signal` to the session's foreground process group, which is the
+    /// running job rather than the shell when job control is on. Returns the group.
+    #[cfg(unix)]
+    pub fn signal(&self, id: &str, signal: PtySignal) -> Result<i32, String> {
+        let session = self.session(id)?;
+        let pgid = session.master.lock().unwrap().process_group_leader()
+            .ok_or_else(|| format!("Session {} has no foreground process group", id))?;
+        signals::deliver(pgid, signal)?;
+        Ok(pgid)
+    }
+
+    #[cfg(not(unix))]
+    pub fn signal(&self, id: &str, signal: PtySignal) -> Result<i32, String> {
+        self.session(id)?;
+        signals::deliver(0, signal).map(|_| 0)
+    }
+
+    /// Kills the session's shell. The exit event follows once it is reaped.
+    pub fn close(&self, id: &str) -> Result<(), String> {
+        let session = self.session(id)?;
+        let result = session.killer.lock().unwrap().kill();
+        result.map_err(|e| format!("Failed to kill session {}: {}", id, e))
+    }
+
+    pub fn list(&self) -> Vec<SessionInfo> {
+        let mut sessions: Vec<SessionInfo> = self.sessions.lock().unwrap().values().map(|s| s.info.lock().unwrap().clone()).collect();
+        sessions.sort_by(|a, b| a.created_at.cmp(&b.created_at));
+        sessions
+    }
+
+    /// Kills every session, so no shell outlives the kernel.
+    pub fn shutdown(&self) {
+        let sessions: Vec<Arc<Session>> = self.sessions.lock().unwrap().drain().map(|(_, s)| s).collect();
+        for session in sessions {
+            let _ = session.killer.lock().unwrap().kill();
+        }
+    }
+}
+
+/// Whether the shell itself, not a job it started, is reading the terminal.
+#[cfg(unix)]
+fn shell_in_foreground(session: &Session) -> bool {
+    let leader = session.master.lock().unwrap().process_group_leader();
+    leader.is_some() && leader.map(|pid| pid as u32) == session.shell_pid
+}
+
+#[cfg(not(unix))]
+fn shell_in_foreground(_session: &Session) -> bool {
+    true
+}
+
+/// Sends a session's output in frames until its reader ends. Text sessions
+/// decode across frame boundaries, so split characters arrive whole.
+fn pump(
+    chunks: mpsc::Receiver<Vec<u8>>,
+    raw: bool,
+    session_id: &str,
+    sink: &PtyEventSink,
+    recording: &Mutex<Option<Recorder>>,
+    scrollback: &Mutex<Scrollback>,
+) {
+    let mut frames = FrameBatcher::default();
+    let mut decoder = Utf8Decoder::default();
+    let send = |data: String| {
+        if !data.is_empty() {
+            sink(PtyEvent::Data { session_id: session_id.to_string(), data });
+        }
+    };
+    let flush = |frame: Vec<u8>, decoder: &mut Utf8Decoder| {
+        if frame.is_empty() {
+            return;
+        }
+        let mut recording = recording.lock().unwrap();
+        if let Some(Err(e)) = recording.as_mut().map(|recorder| recorder.output(&frame)) {
+            eprintln!("[PTY] Recording of {} stopped: {}", session_id, e);
+            *recording = None;
+        }
+        drop(recording);
+        // Sent under the lock, so an attach sees each frame either in its
+        // history or as a later event
+        let mut scrollback = scrollback.lock().unwrap();
+        scrollback.push(&frame);
+        if raw {
+            sink(PtyEvent::Raw { session_id: session_id.to_string(), data: frame });
+        } else {
+            send(decoder.decode(&frame));
+        }
+    };
+
+    loop {
+        let next = match frames.deadline() {
+            Some(deadline) => chunks.recv_timeout(deadline.saturating_duration_since(Instant::now())),
+            None => chunks.recv().map_err(|_| RecvTimeoutError::Disconnected),
+        };
+        match next {
+            Ok(chunk) => {
+                if frames.push(&chunk) {
+                    flush(frames.take(), &mut decoder);
+                }
+            }
+            Err(RecvTimeoutError::Timeout) => flush(frames.take(), &mut decoder),
+            Err(RecvTimeoutError::Disconnected) => {
+                flush(frames.take(), &mut decoder);
+                if !raw {
+                    send(decoder.finish());
+                }
+                return;
+            }
+        }
+    }
+}
+
+/// Opens a terminal session sized to the xterm.js view; `raw` sessions get
+/// their output as bytes instead of text, `record` sessions are recorded.
+#[tauri::command]
+pub fn pty_create(state: State<'_, AppState>, cols: u16, rows: u16, raw: Option<bool>, record: Option<bool>) -> Result<SessionInfo, String> {
+    let options = SessionOptions { cols, rows, raw: raw.unwrap_or(false), record: record.unwrap_or(false) };
+    state.pty.create(&state.governance.get_active_agency_id(), options)
+}
+
+/// Writes input to a session. A lone Ctrl+C or Ctrl+Z is translated by the
+/// agency's key mapping, so it can cancel an engine request instead, and
+/// `kora` lines are run by the kernel rather than the shell.
+#[tauri::command]
+pub async fn pty_write(app: tauri::AppHandle, state: State<'_, AppState>, session_id: String, data: String) -> Result<(), String> {
+    if state.bridge_locked.load(std::sync::atomic::Ordering::SeqCst) {
+        return Ok(());
+    }
+    if let Some(key) = ControlKey::parse(&data) {
+        state.pty.reset_input(&session_id)?;
+        return signals::handle_key(&state, &session_id, key).await;
+    }
+    for line in state.pty.input(&session_id, &data)? {
+        tauri::async_runtime::spawn(crate::shell::run(app.clone(), session_id.clone(), line));
+    }
+    Ok(())
+}
+
+/// Reattaches a view to a running session, e.g. after a webview reload.
+#[tauri::command]
+pub fn pty_attach(state: State<'_, AppState>, session_id: String) -> Result<Attachment, String> {
+    state.pty.attach(&session_id)
+}
+
+#[tauri::command]
+pub fn pty_resize(state: State<'_, AppState>, session_id: String, cols: u16, rows: u16) -> Result<(), String> {
+    state.pty.resize(&session_id, cols, rows)
+}
+
+/// Kills a session's shell; audited as a forced kill.
+#[tauri::command]
+pub async fn pty_close(state: State<'_, AppState>, session_id: String) -> Result<(), String> {
+    let agency_id = state.pty.info(&session_id)?.agency_id;
+    state.pty.close(&session_id)?;
+    signals::audit_kill(&state.db, &agency_id, &session_id, "close").await;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn pty_list(state: State<'_, AppState>) -> Vec<SessionInfo> {
+    state.pty.list()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn manager() -> (PtyManager, mpsc::Receiver<PtyEvent>) {
+        let (tx, rx) = mpsc::channel();
+        let tx = Mutex::new(tx);
+        let recordings = std::env::temp_dir().join(format!("kora-recordings-{}", uuid::Uuid::new_v4()));
+        (PtyManager::new(Arc::new(move |e| { let _ = tx.lock().unwrap().send(e); }), recordings), rx)
+    }
+
+    /// Output of `session_id` up to its exit event.
+    fn run_to_exit(rx: &mpsc::Receiver<PtyEvent>, session_id: &str) -> (String, PtyExit) {
+        run_to_exit_recorded(rx, session_id).0
+    }
+
+    fn run_to_exit_recorded(rx: &mpsc::Receiver<PtyEvent>, session_id: &str) -> ((String, PtyExit), Option<RecordingInfo>) {
+        let mut output = String::new();
+        let mut recorded = None;
+        loop {
+            match rx.recv_timeout(Duration::from_secs(10)).expect("session did not exit") {
+                PtyEvent::Data { session_id: id, data } if id == session_id => output.push_str(&data),
+                PtyEvent::Recorded(recording) if recording.id == session_id => recorded = Some(recording),
+                PtyEvent::Exit(exit) if exit.session_id == session_id => return ((output, exit), recorded),
+                _ => {}
+            }
+        }
+    }
+
+    #[test]
+    fn test_sessions_stream_resize_and_report_exit() {
+        let (pty, rx) = manager();
+        let mut cmd = CommandBuilder::new("sh");
+        cmd.args(["-c", "read line; stty size; echo \"got $line\"; exit 3"]);
+        let session = pty.spawn(cmd, "SYSTEM", SessionOptions { record: true, ..Default::default() }).unwrap();
+        assert_eq!(pty.list().len(), 1);
+
+        pty.resize(&session.id, 120, 40).unwrap();
+        assert_eq!((pty.list()[0].cols, pty.list()[0].rows), (120, 40));
+        assert!(pty.resize(&session.id, 0, 40).is_err());
+        pty.write(&session.id, "ping\n").unwrap();
+
+        let ((output, exit), recorded) = run_to_exit_recorded(&rx, &session.id);
+        assert!(output.contains("40 120"), "{:?}", output);
+        assert!(output.contains("got ping"), "{:?}", output);
+        assert_eq!((exit.code, exit.success), (3, false));
+        assert!(pty.list().is_empty());
+        assert!(pty.write(&session.id, "late").is_err());
+
+        // The cast holds the same output and the resize
+        let recorded = recorded.expect("recording");
+        let (header, events) = recording::parse(&std::fs::read_to_string(&recorded.path).unwrap()).unwrap();
+        assert_eq!((header.width, header.height), (80, 24));
+        assert!(events.iter().any(|e| e.1 == "r" && e.2 == "120x40"));
+        let cast: String = events.iter().filter(|e| e.1 == "o").map(|e| e.2.as_str()).collect();
+        assert_eq!(cast, output);
+        std::fs::remove_dir_all(std::path::Path::new(&recorded.path).parent().unwrap().parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_attach_returns_output_sent_so_far() {
+        let (pty, rx) = manager();
+        let mut cmd = CommandBuilder::new("sh");
+        cmd.args(["-c", "echo first; read x; echo second"]);
+        let session = pty.spawn(cmd, "SYSTEM", SessionOptions::default()).unwrap();
+        let mut sent = String::new();
+        while !sent.contains("first") {
+            if let PtyEvent::Data { data, .. } = rx.recv_timeout(Duration::from_secs(10)).unwrap() {
+                sent.push_str(&data);
+            }
+        }
+
+        let attached = pty.attach(&session.id).unwrap();
+        assert_eq!(attached.session.id, session.id);
+        assert_eq!((attached.history.as_str(), attached.dropped), (sent.as_str(), 0));
+
+        pty.write(&session.id, "go\n").unwrap();
+        assert!(run_to_exit(&rx, &session.id).0.contains("second"));
+        assert!(pty.attach(&session.id).is_err());
+    }
+
+    #[test]
+    fn test_output_is_framed_and_raw_sessions_get_bytes() {
+        let (pty, rx) = manager();
+        // Four reads' worth of multibyte output in one burst
+        let mut cmd = CommandBuilder::new("sh");
+        cmd.args(["-c", "i=0; while [ $i -lt 2000 ]; do printf 'ñ€😀'; i=$((i+1)); done"]);
+        let session = pty.spawn(cmd, "SYSTEM", SessionOptions::default()).unwrap();
+        let mut frames = 0;
+        let mut output = String::new();
+        loop {
+            match rx.recv_timeout(Duration::from_secs(10)).unwrap() {
+                PtyEvent::Data { data, .. } => {
+                    frames += 1;
+                    output.push_str(&data);
+                }
+                PtyEvent::Exit(exit) if exit.session_id == session.id => break,
+                _ => {}
+            }
+        }
+        assert_eq!(output, "ñ€😀".repeat(2000));
+        assert!(frames < 10, "{} frames", frames);
+
+        let mut cmd = CommandBuilder::new("sh");
+        cmd.args(["-c", "stty raw; printf '\\377\\376ok'"]);
+        let session = pty.spawn(cmd, "SYSTEM", SessionOptions { raw: true, ..Default::default() }).unwrap();
+        assert!(session.raw);
+        let mut bytes = Vec::new();
+        loop {
+            match rx.recv_timeout(Duration::from_secs(10)).unwrap() {
+                PtyEvent::Raw { data, .. } => bytes.extend(data),
+                PtyEvent::Exit(_) => break,
+                PtyEvent::Data { .. } => panic!("raw session sent text"),
+                PtyEvent::Recorded(_) => panic!("session was not recorded"),
+            }
+        }
+        assert_eq!(bytes, b"\xff\xfeok");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_signals_reach_the_foreground_job() {
+        let (pty, rx) = manager();
+        let mut cmd = CommandBuilder::new("sh");
+        cmd.args(["-c", "trap 'echo caught; exit 3' INT; echo ready; while :; do sleep 0.1; done"]);
+        let session = pty.spawn(cmd, "SYSTEM", SessionOptions::default()).unwrap();
+        let mut output = String::new();
+        while !output.contains("ready") {
+            if let PtyEvent::Data { data, .. } = rx.recv_timeout(Duration::from_secs(10)).unwrap() {
+                output.push_str(&data);
+            }
+        }
+        assert!(pty.signal(&session.id, PtySignal::Interrupt).unwrap() > 0);
+        let (output, exit) = run_to_exit(&rx, &session.id);
+        assert!(output.contains("caught"), "{:?}", output);
+        assert_eq!(exit.code, 3);
+        assert!(pty.signal(&session.id, PtySignal::Kill).is_err());
+    }
+
+    #[test]
+    fn test_close_and_shutdown_kill_shells() {
+        let (pty, rx) = manager();
+        let sleeper = || {
+            let mut cmd = CommandBuilder::new("sh");
+            cmd.args(["-c", "sleep 30"]);
+            cmd
+        };
+        let first = pty.spawn(sleeper(), "SYSTEM", SessionOptions::default()).unwrap();
+        let second = pty.spawn(sleeper(), "SYSTEM", SessionOptions::default()).unwrap();
+        assert_ne!(first.id, second.id);
+
+        pty.close(&first.id).unwrap();
+        assert!(!run_to_exit(&rx, &first.id).1.success);
+        assert_eq!(pty.list().len(), 1);
+
+        pty.shutdown();
+        assert!(pty.list().is_empty());
+        assert!(!run_to_exit(&rx, &second.id).1.success);
+    }
+}
@@ -0,0 +1,243 @@
+use std::collections::VecDeque;
+use std::io::{self, Read};
+use std::time::{Duration, Instant};
+
+/// Smallest and largest read buffer of a session.
+pub const MIN_READ_BYTES: usize = 4 * 1024;
+pub const MAX_READ_BYTES: usize = 64 * 1024;
+
+/// Output is held back at most this long so bursts leave as one event.
+pub const FRAME_INTERVAL: Duration = Duration::from_millis(16);
+/// A frame this large is sent without waiting for the interval.
+pub const MAX_FRAME_BYTES: usize = 64 * 1024;
+
+/// Output kept per session for reattaching views.
+pub const SCROLLBACK_BYTES: usize = 256 * 1024;
+
+/// Decodes a byte stream as UTF-8 across reads: a multibyte character split
+/// between two reads is carried over instead of becoming `U+FFFD`. Bytes that
+/// can never be valid UTF-8 still decode to `U+FFFD`.
+#[derive(Debug, Default)]
+pub struct Utf8Decoder {
+    partial: Vec<u8>,
+}
+
+impl Utf8Decoder {
+    pub fn decode(&mut self, bytes: &[u8]) -> String {
+        self.partial.extend_from_slice(bytes);
+        let mut text = String::with_capacity(self.partial.len());
+        let mut rest = 0;
+        loop {
+            match std::str::from_utf8(&self.partial[rest..]) {
+                Ok(valid) => {
+                    text.push_str(valid);
+                    rest = self.partial.len();
+                    break;
+                }
+                Err(e) => {
+                    let valid_up_to = rest + e.valid_up_to();
+                    text.push_str(std::str::from_utf8(&self.partial[rest..valid_up_to]).unwrap_or_default());
+                    match e.error_len() {
+                        Some(len) => {
+                            text.push(char::REPLACEMENT_CHARACTER);
+                            rest = valid_up_to + len;
+                        }
+                        // Incomplete sequence at the end: wait for the next read
+                        None => {
+                            rest = valid_up_to;
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+        self.partial.drain(..rest);
+        text
+    }
+
+    /// Ends the stream; a sequence left incomplete decodes to `U+FFFD`.
+    pub fn finish(&mut self) -> String {
+        if self.partial.is_empty() {
+            return String::new();
+        }
+        self.partial.clear();
+        char::REPLACEMENT_CHARACTER.to_string()
+    }
+}
+
+/// Read buffer that doubles while reads fill it and halves while they use
+/// less than a quarter, so bursts take few syscalls and idle sessions stay small.
+#[derive(Debug)]
+pub struct ReadBuffer {
+    buf: Vec<u8>,
+}
+
+impl Default for ReadBuffer {
+    fn default() -> Self {
+        Self { buf: vec![0; MIN_READ_BYTES] }
+    }
+}
+
+impl ReadBuffer {
+    /// Reads once; an empty chunk means end of stream.
+    pub fn read_from<R: Read + ?Sized>(&mut self, reader: &mut R) -> io::Result<Vec<u8>> {
+        let n = reader.read(&mut self.buf)?;
+        let chunk = self.buf[..n].to_vec();
+        let capacity = self.buf.len();
+        if n == capacity && capacity < MAX_READ_BYTES {
+            self.buf.resize(capacity * 2, 0);
+        } else if n < capacity / 4 && capacity > MIN_READ_BYTES {
+            self.buf.truncate(capacity / 2);
+        }
+        Ok(chunk)
+    }
+}
+
+/// Coalesces output chunks into frames, flushed `FRAME_INTERVAL` after the
+/// first chunk or as soon as `MAX_FRAME_BYTES` have accumulated.
+#[derive(Debug)]
+pub struct FrameBatcher {
+    interval: Duration,
+    max_bytes: usize,
+    frame: Vec<u8>,
+    opened: Option<Instant>,
+}
+
+impl Default for FrameBatcher {
+    fn default() -> Self {
+        Self { interval: FRAME_INTERVAL, max_bytes: MAX_FRAME_BYTES, frame: Vec::new(), opened: None }
+    }
+}
+
+impl FrameBatcher {
+    /// Adds a chunk; returns whether the frame is full and must be sent now.
+    pub fn push(&mut self, bytes: &[u8]) -> bool {
+        if self.opened.is_none() {
+            self.opened = Some(Instant::now());
+        }
+        self.frame.extend_from_slice(bytes);
+        self.frame.len() >= self.max_bytes
+    }
+
+    /// When the open frame is due, if one is open.
+    pub fn deadline(&self) -> Option<Instant> {
+        self.opened.map(|opened| opened + self.interval)
+    }
+
+    pub fn take(&mut self) -> Vec<u8> {
+        self.opened = None;
+        std::mem::take(&mut self.frame)
+    }
+}
+
+/// The last `capacity` bytes of a session's output, replayed to a view that
+/// reattaches after a reload.
+#[derive(Debug)]
+pub struct Scrollback {
+    buf: VecDeque<u8>,
+    capacity: usize,
+    dropped: u64,
+}
+
+impl Default for Scrollback {
+    fn default() -> Self {
+        Self::new(SCROLLBACK_BYTES)
+    }
+}
+
+impl Scrollback {
+    pub fn new(capacity: usize) -> Self {
+        Self { buf: VecDeque::new(), capacity, dropped: 0 }
+    }
+
+    pub fn push(&mut self, bytes: &[u8]) {
+        self.buf.extend(bytes);
+        let excess = self.buf.len().saturating_sub(self.capacity);
+        self.buf.drain(..excess);
+        self.dropped += excess as u64;
+    }
+
+    /// Bytes evicted so far.
+    pub fn dropped(&self) -> u64 {
+        self.dropped
+    }
+
+    /// The buffered output. Once bytes have been evicted it starts after the
+    /// first line break, so replay does not begin inside a character or an
+    /// escape sequence.
+    pub fn snapshot(&self) -> Vec<u8> {
+        let (front, back) = self.buf.as_slices();
+        let mut bytes = [front, back].concat();
+        if self.dropped > 0 {
+            if let Some(newline) = bytes.iter().position(|&b| b == b'\n') {
+                bytes.drain(..=newline);
+            }
+        }
+        bytes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decoder_carries_split_characters() {
+        let text = "ñ€😀";
+        let bytes = text.as_bytes();
+        // Every split point, including inside each multibyte character
+        for split in 0..=bytes.len() {
+            let mut decoder = Utf8Decoder::default();
+            let decoded = decoder.decode(&bytes[..split]) + &decoder.decode(&bytes[split..]) + &decoder.finish();
+            assert_eq!(decoded, text, "split at {}", split);
+        }
+
+        let mut decoder = Utf8Decoder::default();
+        assert_eq!(decoder.decode(b"a\xffb\xe2\x82"), "a\u{FFFD}b");
+        assert_eq!(decoder.finish(), "\u{FFFD}");
+        assert_eq!(decoder.decode(b"ok"), "ok");
+    }
+
+    #[test]
+    fn test_read_buffer_adapts_to_load() {
+        let mut buffer = ReadBuffer::default();
+        let mut burst: &[u8] = &[b'x'; 256 * 1024];
+        while buffer.buf.len() < MAX_READ_BYTES {
+            assert_eq!(buffer.read_from(&mut burst).unwrap().len(), buffer.buf.len() / 2);
+        }
+        assert_eq!(buffer.read_from(&mut burst).unwrap().len(), MAX_READ_BYTES);
+        assert_eq!(buffer.buf.len(), MAX_READ_BYTES);
+
+        let mut trickle: &[u8] = b"$ ";
+        assert_eq!(buffer.read_from(&mut trickle).unwrap(), b"$ ");
+        assert_eq!(buffer.buf.len(), MAX_READ_BYTES / 2);
+        assert!(buffer.read_from(&mut trickle).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_batcher_coalesces_until_due_or_full() {
+        let mut frames = FrameBatcher { max_bytes: 8, ..Default::default() };
+        assert!(frames.deadline().is_none());
+        assert!(!frames.push(b"ab"));
+        let due = frames.deadline().unwrap();
+        assert!(!frames.push(b"cd"));
+        assert_eq!(frames.deadline(), Some(due));
+        assert!(frames.push(b"efgh"));
+        assert_eq!(frames.take(), b"abcdefgh");
+        assert!(frames.deadline().is_none());
+    }
+
+    #[test]
+    fn test_scrollback_keeps_the_tail_from_a_line_start() {
+        let mut scrollback = Scrollback::new(16);
+        scrollback.push(b"$ ls\r\n");
+        assert_eq!(scrollback.snapshot(), b"$ ls\r\n");
+        // Evicting "$ l" leaves a partial line, which is skipped
+        scrollback.push("ñotes.md\r\n$ ".as_bytes());
+        assert_eq!(scrollback.dropped(), 3);
+        assert_eq!(scrollback.snapshot(), "ñotes.md\r\n$ ".as_bytes());
+        scrollback.push(b"pwd\r\n");
+        assert_eq!(scrollback.dropped(), 8);
+        assert_eq!(scrollback.snapshot(), b"$ pwd\r\n");
+    }
+}
@@ -0,0 +1,280 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use sqlx::{Pool, Sqlite};
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+use super::output::Utf8Decoder;
+use super::SessionInfo;
+use crate::audit;
+use crate::AppState;
+
+/// A finished recording as stored in `pty_recordings`.
+#[derive(Clone, Debug, PartialEq, Serialize, sqlx::FromRow)]
+pub struct RecordingInfo {
+    /// Id of the recorded session.
+    pub id: String,
+    pub agency_id: String,
+    pub path: String,
+    pub cols: i64,
+    pub rows: i64,
+    pub started_at: String,
+    pub duration_secs: f64,
+    pub bytes: i64,
+    /// SHA-256 of the `.cast` file, also written to the audit chain.
+    pub hash: String,
+    /// Chain hash of the `PTY_RECORDING` audit entry holding `hash`.
+    pub audit_hash: Option<String>,
+}
+
+/// Header line of an asciicast v2 file.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CastHeader {
+    pub version: u32,
+    pub width: u16,
+    pub height: u16,
+    /// Unix time the recording started.
+    pub timestamp: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+}
+
+/// One event line: seconds since start, `o` (output) or `r` (resize, `COLSxROWS`), data.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CastEvent(pub f64, pub String, pub String);
+
+/// A parsed recording, ready to be played back.
+#[derive(Clone, Debug, Serialize)]
+pub struct Replay {
+    pub recording: RecordingInfo,
+    pub header: CastHeader,
+    pub events: Vec<CastEvent>,
+}
+
+/// Writes a session to an asciicast v2 (asciinema) file as it runs. Only
+/// output and resizes are recorded; keystrokes may carry secrets.
+pub struct Recorder {
+    file: BufWriter<File>,
+    hasher: Sha256,
+    bytes: u64,
+    path: PathBuf,
+    started: Instant,
+    decoder: Utf8Decoder,
+    info: SessionInfo,
+}
+
+impl Recorder {
+    pub fn create(path: &Path, info: &SessionInfo) -> io::Result<Self> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let mut recorder = Self {
+            file: BufWriter::new(File::create(path)?),
+            hasher: Sha256::new(),
+            bytes: 0,
+            path: path.to_path_buf(),
+            started: Instant::now(),
+            decoder: Utf8Decoder::default(),
+            info: info.clone(),
+        };
+        let header = CastHeader {
+            version: 2,
+            width: info.cols,
+            height: info.rows,
+            timestamp: chrono::Utc::now().timestamp(),
+            title: Some(format!("{} ({})", info.shell, info.agency_id)),
+        };
+        recorder.line(&serde_json::to_string(&header)?)?;
+        Ok(recorder)
+    }
+
+    fn line(&mut self, json: &str) -> io::Result<()> {
+        for part in [json.as_bytes(), b"\n"] {
+            self.file.write_all(part)?;
+            self.hasher.update(part);
+            self.bytes += part.len() as u64;
+        }
+        Ok(())
+    }
+
+    fn event(&mut self, kind: &str, data: String) -> io::Result<()> {
+        let event = CastEvent(self.started.elapsed().as_secs_f64(), kind.to_string(), data);
+        self.line(&serde_json::to_string(&event)?)
+    }
+
+    /// Records a frame of output; raw frames are decoded like text sessions'.
+    pub fn output(&mut self, bytes: &[u8]) -> io::Result<()> {
+        let text = self.decoder.decode(bytes);
+        if text.is_empty() {
+            return Ok(());
+        }
+        self.event("o", text)
+    }
+
+    pub fn resize(&mut self, cols: u16, rows: u16) -> io::Result<()> {
+        self.event("r", format!("{}x{}", cols, rows))
+    }
+
+    /// Closes the file and returns what is stored about it.
+    pub fn finish(mut self) -> io::Result<RecordingInfo> {
+        let tail = self.decoder.finish();
+        if !tail.is_empty() {
+            self.event("o", tail)?;
+        }
+        self.file.flush()?;
+        Ok(RecordingInfo {
+            id: self.info.id.clone(),
+            agency_id: self.info.agency_id.clone(),
+            path: self.path.to_string_lossy().to_string(),
+            cols: self.info.cols as i64,
+            rows: self.info.rows as i64,
+            started_at: self.info.created_at.clone(),
+            duration_secs: self.started.elapsed().as_secs_f64(),
+            bytes: self.bytes as i64,
+            hash: hex(self.hasher.finalize().as_slice()),
+            audit_hash: None,
+        })
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Writes a finished recording's hash to the audit chain, then stores the
+/// recording with a reference to that entry.
+pub async fn register(pool: &Pool<Sqlite>, recording: &RecordingInfo) -> Result<RecordingInfo, String> {
+    let metadata = format!("session={} bytes={} hash={}", recording.id, recording.bytes, recording.hash);
+    let audit_hash = audit::log_event(pool, "PTY_RECORDING", "RING_1", &metadata, &recording.agency_id).await?;
+    let recording = RecordingInfo { audit_hash: Some(audit_hash), ..recording.clone() };
+    sqlx::query(
+        "INSERT OR REPLACE INTO pty_recordings (id, agency_id, path, cols, rows, started_at, duration_secs, bytes, hash, audit_hash)
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"
+    )
+    .bind(&recording.id)
+    .bind(&recording.agency_id)
+    .bind(&recording.path)
+    .bind(recording.cols)
+    .bind(recording.rows)
+    .bind(&recording.started_at)
+    .bind(recording.duration_secs)
+    .bind(recording.bytes)
+    .bind(&recording.hash)
+    .bind(&recording.audit_hash)
+    .execute(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+    Ok(recording)
+}
+
+pub async fn list(pool: &Pool<Sqlite>, agency_id: &str) -> Result<Vec<RecordingInfo>, String> {
+    sqlx::query_as("SELECT * FROM pty_recordings WHERE agency_id = ? ORDER BY started_at DESC")
+        .bind(agency_id)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Reads a recording of `agency_id`, refusing it if the file no longer
+/// matches the hash taken when it was closed.
+pub async fn load(pool: &Pool<Sqlite>, agency_id: &str, id: &str) -> Result<(RecordingInfo, String), String> {
+    let recording: RecordingInfo = sqlx::query_as("SELECT * FROM pty_recordings WHERE agency_id = ? AND id = ?")
+        .bind(agency_id)
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Unknown recording: {}", id))?;
+    let content = std::fs::read_to_string(&recording.path).map_err(|e| format!("Cannot read {}: {}", recording.path, e))?;
+    if hex(Sha256::digest(content.as_bytes()).as_slice()) != recording.hash {
+        return Err(format!("Recording {} does not match its audited hash", id));
+    }
+    Ok((recording, content))
+}
+
+/// Parses asciicast v2 content.
+pub fn parse(content: &str) -> Result<(CastHeader, Vec<CastEvent>), String> {
+    let mut lines = content.lines().filter(|l| !l.trim().is_empty());
+    let header: CastHeader = serde_json::from_str(lines.next().ok_or("Empty recording")?).map_err(|e| format!("Bad header: {}", e))?;
+    if header.version != 2 {
+        return Err(format!("Unsupported asciicast version {}", header.version));
+    }
+    let events = lines.map(|l| serde_json::from_str(l).map_err(|e| format!("Bad event: {}", e))).collect::<Result<_, _>>()?;
+    Ok((header, events))
+}
+
+/// Lists the active agency's terminal recordings.
+#[tauri::command]
+pub async fn pty_recording_list(state: tauri::State<'_, AppState>) -> Result<Vec<RecordingInfo>, String> {
+    list(&state.db, &state.governance.get_active_agency_id()).await
+}
+
+/// Returns a recording as an asciicast v2 document, for asciinema and other players.
+#[tauri::command]
+pub async fn pty_recording_export(state: tauri::State<'_, AppState>, id: String) -> Result<String, String> {
+    let agency_id = state.governance.get_active_agency_id();
+    let (recording, content) = load(&state.db, &agency_id, &id).await?;
+    let _ = audit::log_event(&state.db, "PTY_RECORDING_EXPORT", "RING_1", &format!("session={} hash={}", id, recording.hash), &agency_id).await;
+    Ok(content)
+}
+
+/// Returns a recording parsed for playback in a read-only terminal view.
+#[tauri::command]
+pub async fn pty_recording_replay(state: tauri::State<'_, AppState>, id: String) -> Result<Replay, String> {
+    let agency_id = state.governance.get_active_agency_id();
+    let (recording, content) = load(&state.db, &agency_id, &id).await?;
+    let (header, events) = parse(&content)?;
+    let _ = audit::log_event(&state.db, "PTY_RECORDING_REPLAY", "RING_3", &format!("session={}", id), &agency_id).await;
+    Ok(Replay { recording, header, events })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_pool;
+
+    #[tokio::test]
+    async fn test_recordings_round_trip_and_detect_tampering() {
+        let pool = test_pool().await;
+        let path = std::env::temp_dir().join(format!("kora-cast-{}", uuid::Uuid::new_v4())).join("SYSTEM").join("s1.cast");
+        let info = SessionInfo {
+            id: "s1".into(),
+            agency_id: "SYSTEM".into(),
+            shell: "bash".into(),
+            cols: 80,
+            rows: 24,
+            raw: false,
+            record: true,
+            created_at: "2024-04-13T00:00:00Z".into(),
+        };
+        let mut recorder = Recorder::create(&path, &info).unwrap();
+        recorder.output(b"$ echo \xc3").unwrap();
+        recorder.output(b"\xb1\r\n").unwrap();
+        recorder.resize(120, 40).unwrap();
+        let recording = recorder.finish().unwrap();
+        assert_eq!(recording.bytes as u64, std::fs::metadata(&path).unwrap().len());
+
+        let recording = register(&pool, &recording).await.unwrap();
+        // Metadata the PII scrubber redacted is kept in shadow_metadata
+        let (audited,): (String,) = sqlx::query_as(
+            "SELECT COALESCE(s.encrypted_data, a.metadata) FROM audit_logs a LEFT JOIN shadow_metadata s ON s.log_id = a.id WHERE a.curr_hash = ?"
+        ).bind(&recording.audit_hash).fetch_one(&pool).await.unwrap();
+        assert!(audited.contains(&recording.hash));
+        assert_eq!(list(&pool, "SYSTEM").await.unwrap(), vec![recording.clone()]);
+        assert!(list(&pool, "OTHER").await.unwrap().is_empty());
+
+        let (_, content) = load(&pool, "SYSTEM", "s1").await.unwrap();
+        let (header, events) = parse(&content).unwrap();
+        assert_eq!((header.version, header.width, header.height), (2, 80, 24));
+        let kinds: Vec<(&str, &str)> = events.iter().map(|e| (e.1.as_str(), e.2.as_str())).collect();
+        assert_eq!(kinds, vec![("o", "$ echo "), ("o", "ñ\r\n"), ("r", "120x40")]);
+        assert!(events.windows(2).all(|w| w[0].0 <= w[1].0));
+
+        assert!(load(&pool, "OTHER", "s1").await.is_err());
+        std::fs::write(&path, content.replace("echo", "rm -rf")).unwrap();
+        assert!(load(&pool, "SYSTEM", "s1").await.unwrap_err().contains("audited hash"));
+
+        std::fs::remove_dir_all(path.parent().unwrap().parent().unwrap()).unwrap();
+    }
+}
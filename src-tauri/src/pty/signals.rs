@@ -0,0 +1,245 @@
+use serde::{Deserialize, Serialize};
+use sqlx::{Pool, Sqlite};
+use crate::audit;
+use crate::AppState;
+
+/// Signals the bridge can send to a session's foreground process group.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PtySignal {
+    #[serde(rename = "SIGINT")]
+    Interrupt,
+    #[serde(rename = "SIGTERM")]
+    Terminate,
+    #[serde(rename = "SIGTSTP")]
+    Suspend,
+    /// Cannot be caught; audited as a forced kill.
+    #[serde(rename = "SIGKILL")]
+    Kill,
+}
+
+impl PtySignal {
+    pub fn name(&self) -> &'static str {
+        match self {
+            PtySignal::Interrupt => "SIGINT",
+            PtySignal::Terminate => "SIGTERM",
+            PtySignal::Suspend => "SIGTSTP",
+            PtySignal::Kill => "SIGKILL",
+        }
+    }
+}
+
+/// Sends `signal` to every process of the group `pgid`.
+#[cfg(unix)]
+pub fn deliver(pgid: i32, signal: PtySignal) -> Result<(), String> {
+    let sig = match signal {
+        PtySignal::Interrupt => libc::SIGINT,
+        PtySignal::Terminate => libc::SIGTERM,
+        PtySignal::Suspend => libc::SIGTSTP,
+        PtySignal::Kill => libc::SIGKILL,
+    };
+    if unsafe { libc::killpg(pgid as libc::pid_t, sig) } != 0 {
+        return Err(format!("Failed to send {} to process group {}: {}", signal.name(), pgid, std::io::Error::last_os_error()));
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+pub fn deliver(_pgid: i32, signal: PtySignal) -> Result<(), String> {
+    Err(format!("{} is not supported on this platform", signal.name()))
+}
+
+/// Control keys the bridge intercepts before they reach the terminal.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ControlKey {
+    CtrlC,
+    CtrlZ,
+}
+
+impl ControlKey {
+    /// The key typed when `data` is exactly one intercepted keystroke.
+    pub fn parse(data: &str) -> Option<Self> {
+        match data {
+            "\x03" => Some(ControlKey::CtrlC),
+            "\x1a" => Some(ControlKey::CtrlZ),
+            _ => None,
+        }
+    }
+
+    /// The signal the terminal's line discipline would raise for the key.
+    pub fn signal(&self) -> PtySignal {
+        match self {
+            ControlKey::CtrlC => PtySignal::Interrupt,
+            ControlKey::CtrlZ => PtySignal::Suspend,
+        }
+    }
+
+    pub fn byte(&self) -> &'static str {
+        match self {
+            ControlKey::CtrlC => "\x03",
+            ControlKey::CtrlZ => "\x1a",
+        }
+    }
+}
+
+/// What an intercepted control key does.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum KeyAction {
+    /// Write the key to the terminal; its line discipline signals the
+    /// foreground job, or a program in raw mode reads the key itself.
+    Forward,
+    /// Signal the foreground process group directly, even in raw mode.
+    Signal,
+    /// Cancel the agency's running engine request; the shell is left alone.
+    Cancel,
+    Ignore,
+}
+
+/// Key mapping of an agency's terminals.
+///
+/// Stored per agency in `pty_configs` as serialized JSON, like `WatchConfig`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SignalConfig {
+    /// Ctrl+C while one of the agency's engine requests is running.
+    pub ctrl_c_during_request: KeyAction,
+    /// Ctrl+C otherwise.
+    pub ctrl_c: KeyAction,
+    pub ctrl_z: KeyAction,
+}
+
+impl Default for SignalConfig {
+    fn default() -> Self {
+        Self { ctrl_c_during_request: KeyAction::Cancel, ctrl_c: KeyAction::Forward, ctrl_z: KeyAction::Forward }
+    }
+}
+
+impl SignalConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.ctrl_c == KeyAction::Cancel || self.ctrl_z == KeyAction::Cancel {
+            return Err("Only Ctrl+C during an engine request can cancel".to_string());
+        }
+        Ok(())
+    }
+
+    pub fn action(&self, key: ControlKey, request_active: bool) -> KeyAction {
+        match key {
+            ControlKey::CtrlC if request_active => self.ctrl_c_during_request,
+            ControlKey::CtrlC => self.ctrl_c,
+            ControlKey::CtrlZ => self.ctrl_z,
+        }
+    }
+}
+
+/// Loads the key mapping of an agency, or the defaults.
+pub async fn load(pool: &Pool<Sqlite>, agency_id: &str) -> Result<SignalConfig, String> {
+    let row: Option<(String,)> = sqlx::query_as("SELECT config FROM pty_configs WHERE agency_id = ?")
+        .bind(agency_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    match row {
+        Some((json,)) => serde_json::from_str(&json).map_err(|e| format!("Corrupt terminal config for {}: {}", agency_id, e)),
+        None => Ok(SignalConfig::default()),
+    }
+}
+
+/// Persists the key mapping of an agency, replacing any previous one.
+pub async fn save(pool: &Pool<Sqlite>, agency_id: &str, config: &SignalConfig) -> Result<(), String> {
+    let json = serde_json::to_string(config).map_err(|e| e.to_string())?;
+    sqlx::query("INSERT OR REPLACE INTO pty_configs (agency_id, config, updated_at) VALUES (?, ?, ?)")
+        .bind(agency_id)
+        .bind(json)
+        .bind(chrono::Utc::now().to_rfc3339())
+        .execute(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Applies the session agency's mapping to a control key typed in a session.
+/// Only a request typed in this session counts as running.
+pub async fn handle_key(state: &AppState, session_id: &str, key: ControlKey) -> Result<(), String> {
+    let agency_id = state.pty.info(session_id)?.agency_id;
+    let request = state.ai_engine.requests().latest_in_session(session_id);
+    match load(&state.db, &agency_id).await?.action(key, request.is_some()) {
+        KeyAction::Forward => state.pty.write(session_id, key.byte()),
+        KeyAction::Signal => state.pty.signal(session_id, key.signal()).map(|_| ()),
+        KeyAction::Cancel => {
+            let Some(request) = request else { return Ok(()) };
+            // The request may have finished since it was looked up
+            if let Ok(request) = state.ai_engine.cancel_request(&request.id) {
+                let metadata = format!("request_id={} kind={} via=ctrl-c session={}", request.id, request.kind, session_id);
+                let _ = audit::log_event(&state.db, "REQUEST_CANCELLED", "RING_3", &metadata, &request.agency_id).await;
+            }
+            Ok(())
+        }
+        KeyAction::Ignore => Ok(()),
+    }
+}
+
+/// Writes a forced kill of a session to the audit chain.
+pub async fn audit_kill(pool: &Pool<Sqlite>, agency_id: &str, session_id: &str, method: &str) {
+    let metadata = format!("session={} method={}", session_id, method);
+    let _ = audit::log_event(pool, "PTY_KILL", "RING_1", &metadata, agency_id).await;
+}
+
+/// Sends a signal to the foreground job of a session. SIGKILL is audited.
+#[tauri::command]
+pub async fn pty_signal(state: tauri::State<'_, AppState>, session_id: String, signal: PtySignal) -> Result<(), String> {
+    let agency_id = state.pty.info(&session_id)?.agency_id;
+    let pgid = state.pty.signal(&session_id, signal)?;
+    if signal == PtySignal::Kill {
+        audit_kill(&state.db, &agency_id, &session_id, &format!("SIGKILL pgid={}", pgid)).await;
+    }
+    Ok(())
+}
+
+/// Returns the terminal key mapping of the active agency.
+#[tauri::command]
+pub async fn pty_signal_config_get(state: tauri::State<'_, AppState>) -> Result<SignalConfig, String> {
+    load(&state.db, &state.governance.get_active_agency_id()).await
+}
+
+/// Validates and stores the active agency's terminal key mapping.
+#[tauri::command]
+pub async fn pty_signal_config_set(state: tauri::State<'_, AppState>, config: SignalConfig) -> Result<String, String> {
+    let agency_id = state.governance.get_active_agency_id();
+    config.validate()?;
+    save(&state.db, &agency_id, &config).await?;
+    let _ = audit::log_event(
+        &state.db,
+        "PTY_CONFIG_UPDATE",
+        "RING_1",
+        &format!("ctrl_c_during_request={:?} ctrl_c={:?} ctrl_z={:?}", config.ctrl_c_during_request, config.ctrl_c, config.ctrl_z),
+        &agency_id,
+    ).await;
+    Ok(format!("Terminal config updated for {}", agency_id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_pool;
+
+    #[tokio::test]
+    async fn test_ctrl_c_cancels_only_during_requests() {
+        let config = SignalConfig::default();
+        config.validate().unwrap();
+        assert_eq!(config.action(ControlKey::CtrlC, true), KeyAction::Cancel);
+        assert_eq!(config.action(ControlKey::CtrlC, false), KeyAction::Forward);
+        assert_eq!(config.action(ControlKey::CtrlZ, true), KeyAction::Forward);
+        assert_eq!(ControlKey::parse("\x03"), Some(ControlKey::CtrlC));
+        assert_eq!(ControlKey::parse("\x03\x03"), None);
+        assert!(SignalConfig { ctrl_z: KeyAction::Cancel, ..Default::default() }.validate().is_err());
+
+        let pool = test_pool().await;
+        assert_eq!(load(&pool, "SYSTEM").await.unwrap(), config);
+        let custom = SignalConfig { ctrl_c_during_request: KeyAction::Signal, ..Default::default() };
+        save(&pool, "SYSTEM", &custom).await.unwrap();
+        assert_eq!(load(&pool, "SYSTEM").await.unwrap(), custom);
+        let json = serde_json::to_string(&custom).unwrap();
+        assert!(json.contains(r#""ctrl_c_during_request":"signal""#), "{}", json);
+    }
+}
@@ -0,0 +1,406 @@
+use std::ops::Range;
+use std::path::Path;
+use super::{CHUNK_SIZE, OVERLAP_SIZE};
+
+/// Default chunk size for content-aware strategies, in estimated tokens.
+pub const DEFAULT_MAX_TOKENS: usize = 512;
+
+/// Rough token estimate (~4 bytes per token), good enough for sizing and budgeting.
+pub fn estimate_tokens(text: &str) -> usize {
+    text.len().div_ceil(4)
+}
+
+/// Splits a document into byte ranges that are indexed as separate chunks.
+///
+/// Ranges are byte offsets into the original text so chunks can be read back
+/// from disk; every boundary falls on a UTF-8 character boundary.
+pub trait ChunkingStrategy: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    fn chunk(&self, text: &str) -> Vec<Range<usize>>;
+}
+
+/// The original fixed-size byte windows with overlap, snapped to character boundaries.
+pub struct FixedSizeChunker {
+    pub size: usize,
+    pub overlap: usize,
+}
+
+impl Default for FixedSizeChunker {
+    fn default() -> Self {
+        Self { size: CHUNK_SIZE, overlap: OVERLAP_SIZE }
+    }
+}
+
+impl FixedSizeChunker {
+    /// Raw windows over `len` bytes, for content that is not valid UTF-8.
+    pub fn byte_ranges(&self, len: usize) -> Vec<Range<usize>> {
+        let mut ranges = Vec::new();
+        let mut start = 0;
+        while start < len {
+            let end = std::cmp::min(start + self.size, len);
+            ranges.push(start..end);
+            start += self.size - self.overlap;
+            if start >= len {
+                break;
+            }
+        }
+        ranges
+    }
+}
+
+impl ChunkingStrategy for FixedSizeChunker {
+    fn name(&self) -> &'static str {
+        "fixed"
+    }
+
+    fn chunk(&self, text: &str) -> Vec<Range<usize>> {
+        let mut ranges: Vec<Range<usize>> = self.byte_ranges(text.len())
+            .into_iter()
+            .map(|r| floor_char_boundary(text, r.start)..floor_char_boundary(text, r.end))
+            .filter(|r| !r.is_empty())
+            .collect();
+        ranges.dedup();
+        ranges
+    }
+}
+
+/// Content-defined chunking: boundaries are picked by a rolling (gear) hash of
+/// the bytes themselves, so an edit only moves the boundaries next to it and
+/// the chunks before and after keep their exact content.
+pub struct ContentDefinedChunker {
+    pub min_bytes: usize,
+    /// Expected chunk size; rounded up to a power of two.
+    pub avg_bytes: usize,
+    pub max_bytes: usize,
+}
+
+impl Default for ContentDefinedChunker {
+    fn default() -> Self {
+        Self { min_bytes: 512, avg_bytes: 2048, max_bytes: 8192 }
+    }
+}
+
+/// Pseudo-random value per byte (splitmix64), fixed so boundaries are stable across runs.
+const GEAR: [u64; 256] = gear_table();
+
+const fn gear_table() -> [u64; 256] {
+    let mut table = [0u64; 256];
+    let mut state: u64 = 0;
+    let mut i = 0;
+    while i < 256 {
+        state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        table[i] = z ^ (z >> 31);
+        i += 1;
+    }
+    table
+}
+
+impl ContentDefinedChunker {
+    /// Cuts only where `can_cut(end)` holds; a cut that is due waits for the next such position.
+    fn ranges(&self, bytes: &[u8], can_cut: impl Fn(usize) -> bool) -> Vec<Range<usize>> {
+        // The top bits of the gear hash cover the last 64 bytes, independent of the chunk start
+        let bits = self.avg_bytes.max(2).next_power_of_two().trailing_zeros();
+        let min = self.min_bytes.max(1);
+        let mut ranges = Vec::new();
+        let mut start = 0;
+        let mut hash: u64 = 0;
+        let mut due = false;
+        for (i, byte) in bytes.iter().enumerate() {
+            hash = (hash << 1).wrapping_add(GEAR[*byte as usize]);
+            let len = i + 1 - start;
+            due |= len >= self.max_bytes || (len >= min && hash >> (64 - bits) == 0);
+            if due && can_cut(i + 1) {
+                ranges.push(start..i + 1);
+                start = i + 1;
+                hash = 0;
+                due = false;
+            }
+        }
+        if start < bytes.len() {
+            ranges.push(start..bytes.len());
+        }
+        ranges
+    }
+}
+
+impl ChunkingStrategy for ContentDefinedChunker {
+    fn name(&self) -> &'static str {
+        "cdc"
+    }
+
+    fn chunk(&self, text: &str) -> Vec<Range<usize>> {
+        self.ranges(text.as_bytes(), |end| text.is_char_boundary(end))
+    }
+}
+
+/// Paragraph and heading aware chunking for markdown and prose.
+///
+/// A heading always starts a new chunk so sections are not mixed; paragraphs
+/// are packed together up to the token limit.
+pub struct MarkdownChunker {
+    pub max_tokens: usize,
+}
+
+impl ChunkingStrategy for MarkdownChunker {
+    fn name(&self) -> &'static str {
+        "markdown"
+    }
+
+    fn chunk(&self, text: &str) -> Vec<Range<usize>> {
+        let mut blocks = Vec::new();
+        let mut in_fence = false;
+        let mut previous_blank = true;
+        for (start, line) in lines(text) {
+            let trimmed = line.trim();
+            if trimmed.starts_with("```") {
+                in_fence = !in_fence;
+            }
+            if in_fence {
+                previous_blank = false;
+                continue;
+            }
+            if trimmed.starts_with('#') {
+                blocks.push((start, true));
+            } else if previous_blank && !trimmed.is_empty() {
+                blocks.push((start, false));
+            }
+            previous_blank = trimmed.is_empty();
+        }
+        pack(text, &blocks, self.max_tokens)
+    }
+}
+
+/// Line and definition aware chunking for source code.
+///
+/// Top-level definitions (unindented lines opening a function, type or block)
+/// start new segments, so functions are kept whole when they fit.
+pub struct CodeChunker {
+    pub max_tokens: usize,
+}
+
+const DEFINITION_KEYWORDS: &[&str] = &[
+    "fn ", "pub ", "impl", "struct ", "enum ", "trait ", "mod ", "def ", "class ", "function ",
+    "export ", "async ", "const ", "interface ", "type ", "func ", "public ", "private ", "#[", "@",
+];
+
+impl ChunkingStrategy for CodeChunker {
+    fn name(&self) -> &'static str {
+        "code"
+    }
+
+    fn chunk(&self, text: &str) -> Vec<Range<usize>> {
+        let mut blocks = vec![(0, false)];
+        let mut previous_blank = false;
+        let mut previous_attribute = false;
+        for (start, line) in lines(text) {
+            let top_level = !line.starts_with(char::is_whitespace);
+            let is_definition = top_level && DEFINITION_KEYWORDS.iter().any(|k| line.starts_with(k));
+            // Attributes and decorators stay with the definition they annotate
+            let after_gap = top_level && previous_blank && !line.trim().is_empty();
+            if (is_definition || after_gap) && !previous_attribute {
+                blocks.push((start, false));
+            }
+            previous_attribute = top_level && (line.starts_with("#[") || line.starts_with('@'));
+            previous_blank = line.trim().is_empty();
+        }
+        blocks.dedup_by_key(|b| b.0);
+        pack(text, &blocks, self.max_tokens)
+    }
+}
+
+/// Picks the strategy for a file by extension; unknown types are split by content.
+pub fn strategy_for(path: &Path) -> Box<dyn ChunkingStrategy> {
+    let ext = path.extension().and_then(|e| e.to_str()).unwrap_or("").to_lowercase();
+    match ext.as_str() {
+        "md" | "markdown" | "txt" | "rst" | "adoc" | "org" => Box::new(MarkdownChunker { max_tokens: DEFAULT_MAX_TOKENS }),
+        "rs" | "py" | "js" | "mjs" | "ts" | "tsx" | "jsx" | "svelte" | "go" | "java" | "kt" | "c" | "h" | "cpp" | "hpp"
+        | "cs" | "rb" | "php" | "swift" | "sh" | "sql" | "toml" | "yaml" | "yml" => Box::new(CodeChunker { max_tokens: DEFAULT_MAX_TOKENS }),
+        _ => Box::new(ContentDefinedChunker::default()),
+    }
+}
+
+/// Lines with their starting byte offsets, newlines excluded.
+fn lines(text: &str) -> impl Iterator<Item = (usize, &str)> {
+    let mut offset = 0;
+    text.split_inclusive('\n').map(move |line| {
+        let start = offset;
+        offset += line.len();
+        (start, line.trim_end_matches(['\n', '\r']))
+    })
+}
+
+fn floor_char_boundary(text: &str, mut index: usize) -> usize {
+    while !text.is_char_boundary(index) {
+        index -= 1;
+    }
+    index
+}
+
+/// Whether a chunk may close after `segment`: a gear hash of its last bytes
+/// hits 1 in 4, so the choice depends on the content and not the position.
+fn is_anchor(segment: &[u8]) -> bool {
+    let hash = segment.iter().fold(0u64, |hash, byte| (hash << 1).wrapping_add(GEAR[*byte as usize]));
+    hash >> 62 == 0
+}
+
+/// Packs consecutive blocks into chunks of at most `max_tokens`.
+///
+/// `blocks` are `(start, hard)` segment starts; a hard start (a heading) always
+/// opens a new chunk. Oversized segments are split at lines, then at characters.
+/// Past a quarter of the limit a chunk also closes after an anchor segment, like the
+/// cuts of [`ContentDefinedChunker`]: after an edit the chunks fall back into
+/// step within a few anchors instead of every later boundary shifting.
+fn pack(text: &str, blocks: &[(usize, bool)], max_tokens: usize) -> Vec<Range<usize>> {
+    let max_bytes = max_tokens.max(1) * 4;
+    let mut starts: Vec<(usize, bool)> = blocks.iter().copied().filter(|(s, _)| *s < text.len()).collect();
+    if starts.first().is_none_or(|(s, _)| *s != 0) {
+        starts.insert(0, (0, false));
+    }
+
+    let mut segments = Vec::new();
+    for (i, (start, hard)) in starts.iter().enumerate() {
+        let end = starts.get(i + 1).map_or(text.len(), |(s, _)| *s);
+        for (j, piece) in split_oversized(text, *start..end, max_bytes).into_iter().enumerate() {
+            segments.push((piece, *hard && j == 0));
+        }
+    }
+
+    let mut chunks: Vec<Range<usize>> = Vec::new();
+    let mut closed = true;
+    for (segment, hard) in segments {
+        match chunks.last_mut() {
+            Some(current) if !closed && !hard && segment.end - current.start <= max_bytes => current.end = segment.end,
+            _ => chunks.push(segment.clone()),
+        }
+        let len = chunks.last().map_or(0, |c| c.len());
+        closed = len >= max_bytes / 4 && is_anchor(&text.as_bytes()[segment]);
+    }
+    chunks.retain(|r| !text[r.clone()].trim().is_empty());
+    chunks
+}
+
+fn split_oversized(text: &str, range: Range<usize>, max_bytes: usize) -> Vec<Range<usize>> {
+    if range.len() <= max_bytes {
+        return vec![range];
+    }
+
+    let mut pieces = Vec::new();
+    let mut start = range.start;
+    while range.end - start > max_bytes {
+        let window = &text[start..floor_char_boundary(text, start + max_bytes)];
+        let cut = match window.rfind('\n') {
+            Some(newline) if newline > 0 => start + newline + 1,
+            _ => start + window.len().max(text[start..].chars().next().map_or(1, char::len_utf8)),
+        };
+        pieces.push(start..cut);
+        start = cut;
+    }
+    pieces.push(start..range.end);
+    pieces
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn texts<'a>(text: &'a str, ranges: &[Range<usize>]) -> Vec<&'a str> {
+        ranges.iter().map(|r| &text[r.clone()]).collect()
+    }
+
+    #[test]
+    fn test_fixed_chunker_never_splits_code_points() {
+        let text = "é".repeat(10);
+        let chunker = FixedSizeChunker { size: 5, overlap: 1 };
+        for range in chunker.chunk(&text) {
+            assert!(text.is_char_boundary(range.start) && text.is_char_boundary(range.end));
+        }
+    }
+
+    #[test]
+    fn test_markdown_chunker_splits_at_headings() {
+        let text = "# Bridge\nThe lock blocks IPC.\n\nIt is set on violations.\n# Engine\nOpenClaw suspends.\n";
+        let chunks = MarkdownChunker { max_tokens: 512 }.chunk(text);
+        assert_eq!(texts(text, &chunks), vec![
+            "# Bridge\nThe lock blocks IPC.\n\nIt is set on violations.\n",
+            "# Engine\nOpenClaw suspends.\n",
+        ]);
+    }
+
+    #[test]
+    fn test_markdown_chunker_packs_paragraphs_by_tokens() {
+        let paragraph = "word ".repeat(30);
+        let text = format!("{0}\n\n{0}\n\n{0}\n", paragraph.trim());
+        let chunks = MarkdownChunker { max_tokens: 80 }.chunk(&text);
+        assert_eq!(chunks.len(), 2);
+        assert!(chunks.iter().all(|r| estimate_tokens(&text[r.clone()]) <= 80));
+        assert_eq!(chunks.last().unwrap().end, text.len());
+    }
+
+    #[test]
+    fn test_code_chunker_keeps_functions_whole() {
+        let text = "use std::io;\n\n#[inline]\nfn a() {\n    1\n}\n\nfn b() {\n    2\n}\n";
+        let chunks = CodeChunker { max_tokens: 8 }.chunk(text);
+        assert_eq!(texts(text, &chunks), vec!["use std::io;\n\n", "#[inline]\nfn a() {\n    1\n}\n\n", "fn b() {\n    2\n}\n"]);
+    }
+
+    #[test]
+    fn test_oversized_blocks_split_at_lines() {
+        let text = "ab\n".repeat(40);
+        let chunks = CodeChunker { max_tokens: 4 }.chunk(&text);
+        assert!(chunks.iter().all(|r| r.len() <= 16 && text[..r.end].ends_with('\n')));
+        assert_eq!(chunks.iter().map(|r| r.len()).sum::<usize>(), text.len());
+    }
+
+    #[test]
+    fn test_content_defined_boundaries_survive_edits() {
+        let text: String = (0..2000).map(|i| format!("line {} of the kora operator log: é\n", i * 7919 % 10007)).collect();
+        let chunker = ContentDefinedChunker::default();
+        let before = chunker.chunk(&text);
+        assert!(before.len() > 10);
+        assert!(before.iter().all(|r| r.len() <= chunker.max_bytes + 3 && text.is_char_boundary(r.end)));
+        assert_eq!(before.last().unwrap().end, text.len());
+
+        // An insertion near the middle only disturbs the chunks around it
+        let mid = text[text.len() / 2..].find('\n').unwrap() + text.len() / 2 + 1;
+        let edited = format!("{}an inserted paragraph\n{}", &text[..mid], &text[mid..]);
+        let after = chunker.chunk(&edited);
+        let old: std::collections::HashSet<&str> = texts(&text, &before).into_iter().collect();
+        let changed = texts(&edited, &after).into_iter().filter(|t| !old.contains(t)).count();
+        assert!(changed <= 2, "{} chunks changed", changed);
+    }
+
+    /// Chunks of `edited` that did not occur in `text`.
+    fn changed(chunker: &dyn ChunkingStrategy, text: &str, edited: &str) -> usize {
+        let old: std::collections::HashSet<&str> = texts(text, &chunker.chunk(text)).into_iter().collect();
+        texts(edited, &chunker.chunk(edited)).into_iter().filter(|t| !old.contains(t)).count()
+    }
+
+    #[test]
+    fn test_markdown_and_code_boundaries_survive_edits() {
+        let paragraphs: Vec<String> = (0..300).map(|i| format!("Paragraph {} notes {} on the bridge.\n\n", i, i * 7919 % 10007)).collect();
+        let text = paragraphs.concat();
+        let markdown = MarkdownChunker { max_tokens: 64 };
+        assert!(markdown.chunk(&text).len() > 50);
+        let mut edited = paragraphs.clone();
+        edited[3] = "Paragraph 3 now says something else entirely, and at length.\n\n".to_string();
+        assert!(changed(&markdown, &text, &edited.concat()) <= 5);
+
+        let functions: Vec<String> = (0..300).map(|i| format!("fn f{}() -> u32 {{\n    {}\n}}\n\n", i, i * 7919 % 10007)).collect();
+        let code = CodeChunker { max_tokens: 64 };
+        let text = functions.concat();
+        let mut edited = functions.clone();
+        edited.insert(2, "fn inserted() {}\n\n".to_string());
+        assert!(changed(&code, &text, &edited.concat()) <= 5);
+    }
+
+    #[test]
+    fn test_strategy_for_file_types() {
+        assert_eq!(strategy_for(Path::new("/knowledge/a/notes.md")).name(), "markdown");
+        assert_eq!(strategy_for(Path::new("/knowledge/a/lib.RS")).name(), "code");
+        assert_eq!(strategy_for(Path::new("/knowledge/a/blob.bin")).name(), "cdc");
+    }
+}
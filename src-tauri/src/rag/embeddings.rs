@@ -0,0 +1,284 @@
+use sqlx::{Pool, Sqlite, SqliteConnection};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::OnceLock;
+
+/// Path of a word-vector file (GloVe or word2vec text format) that replaces the
+/// hashed embedder with a local model. Unset keeps the hashed embedder.
+pub const MODEL_ENV: &str = "KORA_EMBEDDING_MODEL";
+
+/// Turns text into dense vectors. Implementations must run offline on CPU.
+pub trait EmbeddingProvider: Send + Sync {
+    /// Identifier stored with each vector; vectors of different models never mix.
+    fn model(&self) -> &str;
+
+    fn dimensions(&self) -> usize;
+
+    fn embed(&self, text: &str) -> Result<Vec<f32>, String>;
+}
+
+/// Feature-hashing embedder over word unigrams and character trigrams.
+///
+/// Deterministic and dependency-free: it captures lexical overlap and spelling
+/// variants (`suspend` / `suspends`) rather than meaning, which makes it the
+/// baseline provider and the one used by tests.
+pub struct HashedNgramEmbedder {
+    dim: usize,
+}
+
+impl HashedNgramEmbedder {
+    pub fn new(dim: usize) -> Self {
+        Self { dim: dim.max(1) }
+    }
+}
+
+impl Default for HashedNgramEmbedder {
+    fn default() -> Self {
+        Self::new(256)
+    }
+}
+
+/// 64-bit FNV-1a, stable across platforms and releases (unlike `DefaultHasher`).
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |h, b| (h ^ *b as u64).wrapping_mul(0x100000001b3))
+}
+
+impl EmbeddingProvider for HashedNgramEmbedder {
+    fn model(&self) -> &str {
+        "hashed-ngram"
+    }
+
+    fn dimensions(&self) -> usize {
+        self.dim
+    }
+
+    fn embed(&self, text: &str) -> Result<Vec<f32>, String> {
+        let mut vector = vec![0f32; self.dim];
+        let mut add = |feature: &str, weight: f32| {
+            let h = fnv1a(feature.as_bytes());
+            // The top bit picks the sign so collisions tend to cancel out
+            let sign = if h >> 63 == 0 { 1.0 } else { -1.0 };
+            vector[(h % self.dim as u64) as usize] += sign * weight;
+        };
+
+        for word in text.split(|c: char| !c.is_alphanumeric()).filter(|w| !w.is_empty()) {
+            let word = word.to_lowercase();
+            add(&word, 1.0);
+            let padded: Vec<char> = format!("#{}#", word).chars().collect();
+            for gram in padded.windows(3) {
+                add(&gram.iter().collect::<String>(), 0.5);
+            }
+        }
+
+        normalize(&mut vector);
+        Ok(vector)
+    }
+}
+
+/// Local model backend: averages pretrained word vectors loaded from a text
+/// file with one `word v1 v2 ... vn` line per word (GloVe, or word2vec with its
+/// `count dim` header). Words missing from the vocabulary are ignored.
+pub struct StaticEmbedder {
+    model: String,
+    dim: usize,
+    vectors: HashMap<String, Vec<f32>>,
+}
+
+impl StaticEmbedder {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let bytes = std::fs::read(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        let stem = path.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+        // The content hash keeps vectors of a replaced file from mixing with the new ones
+        Self::parse(&format!("static-{}-{:08x}", stem, fnv1a(&bytes) as u32), &String::from_utf8_lossy(&bytes))
+    }
+
+    fn parse(model: &str, text: &str) -> Result<Self, String> {
+        let mut vectors = HashMap::new();
+        let mut dim = 0;
+        for (n, line) in text.lines().enumerate() {
+            let mut fields = line.split_whitespace();
+            let Some(word) = fields.next() else { continue };
+            let values: Vec<f32> = fields.map(str::parse).collect::<Result<_, _>>()
+                .map_err(|e| format!("{} line {}: {}", model, n + 1, e))?;
+            if n == 0 && values.len() == 1 && word.parse::<u64>().is_ok() {
+                continue;
+            }
+            if dim == 0 {
+                dim = values.len();
+            }
+            if values.is_empty() || values.len() != dim {
+                return Err(format!("{} line {}: expected {} values, got {}", model, n + 1, dim, values.len()));
+            }
+            vectors.entry(word.to_lowercase()).or_insert(values);
+        }
+        if vectors.is_empty() {
+            return Err(format!("{} holds no vectors", model));
+        }
+        Ok(Self { model: model.to_string(), dim, vectors })
+    }
+}
+
+impl EmbeddingProvider for StaticEmbedder {
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    fn dimensions(&self) -> usize {
+        self.dim
+    }
+
+    fn embed(&self, text: &str) -> Result<Vec<f32>, String> {
+        let mut vector = vec![0f32; self.dim];
+        for word in text.split(|c: char| !c.is_alphanumeric()).filter(|w| !w.is_empty()) {
+            if let Some(values) = self.vectors.get(&word.to_lowercase()) {
+                vector.iter_mut().zip(values).for_each(|(v, x)| *v += x);
+            }
+        }
+        normalize(&mut vector);
+        Ok(vector)
+    }
+}
+
+/// The provider for a [`MODEL_ENV`] value: the hashed embedder when unset,
+/// otherwise the word vectors at that path.
+pub fn provider_for(model: Option<&Path>) -> Result<Box<dyn EmbeddingProvider>, String> {
+    match model {
+        None => Ok(Box::new(HashedNgramEmbedder::default())),
+        Some(path) => Ok(Box::new(StaticEmbedder::load(path)?)),
+    }
+}
+
+/// Provider used at index and query time, chosen by [`MODEL_ENV`] on first use.
+/// A model that fails to load is reported and the hashed embedder used instead.
+pub fn default_provider() -> &'static dyn EmbeddingProvider {
+    static PROVIDER: OnceLock<Box<dyn EmbeddingProvider>> = OnceLock::new();
+    PROVIDER.get_or_init(|| {
+        let model = std::env::var_os(MODEL_ENV);
+        provider_for(model.as_deref().map(Path::new)).unwrap_or_else(|e| {
+            eprintln!("[KORA] Failed to load embedding model: {}. Using hashed embeddings.", e);
+            Box::new(HashedNgramEmbedder::default())
+        })
+    }).as_ref()
+}
+
+fn normalize(vector: &mut [f32]) {
+    let norm = vector.iter().map(|v| v * v).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        vector.iter_mut().for_each(|v| *v /= norm);
+    }
+}
+
+pub fn cosine(a: &[f32], b: &[f32]) -> f32 {
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norms = a.iter().map(|v| v * v).sum::<f32>().sqrt() * b.iter().map(|v| v * v).sum::<f32>().sqrt();
+    if norms == 0.0 { 0.0 } else { dot / norms }
+}
+
+fn to_blob(vector: &[f32]) -> Vec<u8> {
+    vector.iter().flat_map(|v| v.to_le_bytes()).collect()
+}
+
+fn from_blob(blob: &[u8]) -> Vec<f32> {
+    blob.chunks_exact(4).map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]])).collect()
+}
+
+/// Embeds a chunk's text and stores the vector next to its `documents` row.
+pub async fn store_embedding(conn: &mut SqliteConnection, provider: &dyn EmbeddingProvider, document_id: &str, text: &str) -> Result<(), String> {
+    let vector = provider.embed(text)?;
+    sqlx::query("INSERT OR REPLACE INTO chunk_embeddings (document_id, model, dim, vector) VALUES (?, ?, ?, ?)")
+        .bind(document_id)
+        .bind(provider.model())
+        .bind(vector.len() as i64)
+        .bind(to_blob(&vector))
+        .execute(conn)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Whether a chunk already has a vector of `model`.
+pub async fn has_embedding(conn: &mut SqliteConnection, model: &str, document_id: &str) -> Result<bool, String> {
+    let row: Option<(i64,)> = sqlx::query_as("SELECT 1 FROM chunk_embeddings WHERE document_id = ? AND model = ?")
+        .bind(document_id)
+        .bind(model)
+        .fetch_optional(conn)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(row.is_some())
+}
+
+/// Cosine top-k over the agency's chunks embedded with the provider's model.
+///
+/// Returns `(document_id, similarity)` pairs, most similar first.
+pub async fn vector_search(pool: &Pool<Sqlite>, provider: &dyn EmbeddingProvider, agency_id: &str, query: &str, top_k: usize) -> Result<Vec<(String, f32)>, String> {
+    let query_vector = provider.embed(query)?;
+    let rows: Vec<(String, Vec<u8>)> = sqlx::query_as(
+        "SELECT e.document_id, e.vector FROM chunk_embeddings e
+         JOIN documents d ON d.id = e.document_id
+         WHERE d.agency_id = ? AND e.model = ? AND e.dim = ?"
+    )
+    .bind(agency_id)
+    .bind(provider.model())
+    .bind(provider.dimensions() as i64)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let mut scored: Vec<(String, f32)> = rows
+        .into_iter()
+        .map(|(id, blob)| (id, cosine(&query_vector, &from_blob(&blob))))
+        .filter(|(_, score)| *score > 0.0)
+        .collect();
+    scored.sort_by(|a, b| b.1.total_cmp(&a.1));
+    scored.truncate(top_k);
+    Ok(scored)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hashed_embedder_is_deterministic_and_normalized() {
+        let embedder = HashedNgramEmbedder::new(64);
+        let a = embedder.embed("OpenClaw suspends when idle").unwrap();
+        assert_eq!(a, embedder.embed("OpenClaw suspends when idle").unwrap());
+        assert!((a.iter().map(|v| v * v).sum::<f32>() - 1.0).abs() < 1e-4);
+        assert_eq!(from_blob(&to_blob(&a)), a);
+    }
+
+    #[test]
+    fn test_similar_text_scores_higher() {
+        let embedder = HashedNgramEmbedder::default();
+        let query = embedder.embed("suspend the engine").unwrap();
+        let close = embedder.embed("the engine suspends itself when idle").unwrap();
+        let far = embedder.embed("quarterly invoice totals for accounting").unwrap();
+        assert!(cosine(&query, &close) > cosine(&query, &far));
+    }
+
+    #[test]
+    fn test_static_embedder_averages_word_vectors() {
+        let text = "3 4\nengine 1 0 0 0\nsuspends 0.8 0.2 0 0\ninvoice 0 0 1 0\n";
+        let embedder = StaticEmbedder::parse("static-test", text).unwrap();
+        assert_eq!((embedder.model(), embedder.dimensions()), ("static-test", 4));
+        let query = embedder.embed("Engine").unwrap();
+        assert!(cosine(&query, &embedder.embed("it suspends").unwrap()) > cosine(&query, &embedder.embed("the invoice").unwrap()));
+        assert_eq!(embedder.embed("unknown words").unwrap(), vec![0.0; 4]);
+
+        assert!(StaticEmbedder::parse("bad", "engine 1 0\nidle 1\n").is_err());
+        assert!(StaticEmbedder::parse("bad", "engine one two\n").is_err());
+        assert!(StaticEmbedder::parse("empty", "").is_err());
+    }
+
+    #[test]
+    fn test_provider_for_selects_the_backend() {
+        assert_eq!(provider_for(None).unwrap().model(), "hashed-ngram");
+        let path = std::env::temp_dir().join(format!("kora-vectors-{}.txt", uuid::Uuid::new_v4()));
+        std::fs::write(&path, "engine 1 0\nidle 0 1\n").unwrap();
+        let provider = provider_for(Some(&path)).unwrap();
+        assert!(provider.model().starts_with("static-kora-vectors-"));
+        assert_eq!(provider.dimensions(), 2);
+        std::fs::remove_file(&path).unwrap();
+        assert!(provider_for(Some(&path)).is_err());
+    }
+}
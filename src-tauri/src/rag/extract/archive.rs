@@ -0,0 +1,153 @@
+use flate2::read::DeflateDecoder;
+use std::io::Read;
+use super::MAX_EXTRACTED_BYTES;
+
+/// Minimal read-only ZIP reader for the container formats (DOCX, ODT, EPUB).
+///
+/// Supports stored and deflated entries, which is all those formats use.
+/// Entries are located through the central directory.
+pub struct ZipArchive<'a> {
+    bytes: &'a [u8],
+    entries: Vec<Entry>,
+}
+
+struct Entry {
+    name: String,
+    method: u16,
+    compressed_size: usize,
+    local_header: usize,
+}
+
+fn u16_at(bytes: &[u8], at: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(bytes.get(at..at + 2)?.try_into().ok()?))
+}
+
+fn u32_at(bytes: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(bytes.get(at..at + 4)?.try_into().ok()?))
+}
+
+impl<'a> ZipArchive<'a> {
+    pub fn parse(bytes: &'a [u8]) -> Result<Self, String> {
+        let corrupt = || "Corrupt ZIP archive".to_string();
+
+        // The end-of-central-directory record sits in the last 64KB + 22 bytes
+        let floor = bytes.len().saturating_sub(65_557);
+        let eocd = (floor..bytes.len().saturating_sub(21))
+            .rev()
+            .find(|&i| u32_at(bytes, i) == Some(0x0605_4b50))
+            .ok_or_else(corrupt)?;
+        let count = u16_at(bytes, eocd + 10).ok_or_else(corrupt)? as usize;
+        let mut at = u32_at(bytes, eocd + 16).ok_or_else(corrupt)? as usize;
+
+        let mut entries = Vec::with_capacity(count);
+        for _ in 0..count {
+            if u32_at(bytes, at) != Some(0x0201_4b50) {
+                return Err(corrupt());
+            }
+            let name_len = u16_at(bytes, at + 28).ok_or_else(corrupt)? as usize;
+            let extra_len = u16_at(bytes, at + 30).ok_or_else(corrupt)? as usize;
+            let comment_len = u16_at(bytes, at + 32).ok_or_else(corrupt)? as usize;
+            let name = bytes.get(at + 46..at + 46 + name_len).ok_or_else(corrupt)?;
+            entries.push(Entry {
+                name: String::from_utf8_lossy(name).to_string(),
+                method: u16_at(bytes, at + 10).ok_or_else(corrupt)?,
+                compressed_size: u32_at(bytes, at + 20).ok_or_else(corrupt)? as usize,
+                local_header: u32_at(bytes, at + 42).ok_or_else(corrupt)? as usize,
+            });
+            at += 46 + name_len + extra_len + comment_len;
+        }
+        Ok(Self { bytes, entries })
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.entries.iter().any(|e| e.name == name)
+    }
+
+    /// Reads an entry as UTF-8 text, capped at `MAX_EXTRACTED_BYTES`.
+    pub fn read_text(&self, name: &str) -> Result<String, String> {
+        let entry = self.entries.iter().find(|e| e.name == name)
+            .ok_or_else(|| format!("Missing archive entry: {}", name))?;
+        let corrupt = || format!("Corrupt archive entry: {}", name);
+
+        let at = entry.local_header;
+        if u32_at(self.bytes, at) != Some(0x0403_4b50) {
+            return Err(corrupt());
+        }
+        let name_len = u16_at(self.bytes, at + 26).ok_or_else(corrupt)? as usize;
+        let extra_len = u16_at(self.bytes, at + 28).ok_or_else(corrupt)? as usize;
+        let start = at + 30 + name_len + extra_len;
+        let data = self.bytes.get(start..start + entry.compressed_size).ok_or_else(corrupt)?;
+
+        let mut out = Vec::new();
+        match entry.method {
+            0 => out.extend_from_slice(&data[..data.len().min(MAX_EXTRACTED_BYTES as usize)]),
+            8 => {
+                DeflateDecoder::new(data).take(MAX_EXTRACTED_BYTES).read_to_end(&mut out).map_err(|_| corrupt())?;
+            }
+            m => return Err(format!("Unsupported compression method {} in {}", m, name)),
+        }
+        Ok(String::from_utf8_lossy(&out).to_string())
+    }
+}
+
+/// Builds a ZIP archive in memory, for tests of the container formats.
+#[cfg(test)]
+pub fn build_zip(files: &[(&str, &str)]) -> Vec<u8> {
+    use flate2::write::DeflateEncoder;
+    use flate2::Compression;
+    use std::io::Write;
+
+    let mut out = Vec::new();
+    let mut central = Vec::new();
+    for (name, content) in files {
+        let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(content.as_bytes()).unwrap();
+        let data = encoder.finish().unwrap();
+        let offset = out.len() as u32;
+
+        out.extend_from_slice(&0x0403_4b50u32.to_le_bytes());
+        out.extend_from_slice(&[20, 0, 0, 0, 8, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+        out.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        out.extend_from_slice(&(content.len() as u32).to_le_bytes());
+        out.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        out.extend_from_slice(&[0, 0]);
+        out.extend_from_slice(name.as_bytes());
+        out.extend_from_slice(&data);
+
+        central.extend_from_slice(&0x0201_4b50u32.to_le_bytes());
+        central.extend_from_slice(&[20, 0, 20, 0, 0, 0, 8, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+        central.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        central.extend_from_slice(&(content.len() as u32).to_le_bytes());
+        central.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        central.extend_from_slice(&[0; 12]);
+        central.extend_from_slice(&offset.to_le_bytes());
+        central.extend_from_slice(name.as_bytes());
+    }
+
+    let central_offset = out.len() as u32;
+    out.extend_from_slice(&central);
+    out.extend_from_slice(&0x0605_4b50u32.to_le_bytes());
+    out.extend_from_slice(&[0, 0, 0, 0]);
+    out.extend_from_slice(&(files.len() as u16).to_le_bytes());
+    out.extend_from_slice(&(files.len() as u16).to_le_bytes());
+    out.extend_from_slice(&(central.len() as u32).to_le_bytes());
+    out.extend_from_slice(&central_offset.to_le_bytes());
+    out.extend_from_slice(&[0, 0]);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reads_deflated_entries() {
+        let bytes = build_zip(&[("a.txt", "hello"), ("dir/b.xml", "<b>world</b>")]);
+        let archive = ZipArchive::parse(&bytes).unwrap();
+        assert!(archive.contains("dir/b.xml"));
+        assert_eq!(archive.read_text("a.txt").unwrap(), "hello");
+        assert_eq!(archive.read_text("dir/b.xml").unwrap(), "<b>world</b>");
+        assert!(archive.read_text("missing").is_err());
+        assert!(ZipArchive::parse(b"not a zip").is_err());
+    }
+}
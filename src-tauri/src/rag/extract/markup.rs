@@ -0,0 +1,383 @@
+use super::archive::ZipArchive;
+use super::{DocumentMeta, Extracted, Extractor};
+
+/// How a tag affects the text being built.
+#[derive(Clone, Copy, PartialEq)]
+enum Mark {
+    None,
+    /// Inline break (`<br>`, `<w:tab/>`).
+    Space,
+    /// Block boundary; paragraphs are separated by a blank line.
+    Paragraph,
+    /// Page boundary.
+    Page,
+    /// Drop the element and everything inside it (`<script>`, `<style>`).
+    Skip,
+}
+
+/// Accumulates normalized text and page starts.
+#[derive(Default)]
+struct TextBuilder {
+    text: String,
+    pages: Vec<usize>,
+    /// Whitespace was seen and is emitted before the next visible character.
+    pending_space: bool,
+}
+
+impl TextBuilder {
+    fn push_text(&mut self, raw: &str) {
+        for c in decode_entities(raw).chars() {
+            if c.is_whitespace() {
+                self.pending_space = true;
+                continue;
+            }
+            if self.pending_space && !self.text.is_empty() && !self.text.ends_with(['\n', ' ']) {
+                self.text.push(' ');
+            }
+            self.pending_space = false;
+            self.text.push(c);
+        }
+    }
+
+    fn space(&mut self) {
+        self.pending_space = true;
+    }
+
+    fn paragraph(&mut self) {
+        self.pending_space = false;
+        if !self.text.is_empty() && !self.text.ends_with("\n\n") {
+            self.text.push_str(if self.text.ends_with('\n') { "\n" } else { "\n\n" });
+        }
+    }
+
+    fn page(&mut self) {
+        self.paragraph();
+        if self.pages.is_empty() {
+            self.pages.push(0);
+        }
+        if self.pages.last() != Some(&self.text.len()) {
+            self.pages.push(self.text.len());
+        }
+    }
+
+    fn finish(mut self) -> (String, Vec<usize>) {
+        self.paragraph();
+        // A trailing page break must not open an empty last page
+        if self.pages.last() == Some(&self.text.len()) && self.pages.len() > 1 {
+            self.pages.pop();
+        }
+        (self.text, self.pages)
+    }
+}
+
+/// Walks HTML or XML, classifying each tag by its lowercase name.
+fn walk(src: &str, builder: &mut TextBuilder, classify: impl Fn(&str) -> Mark) {
+    // ASCII lowercasing keeps byte offsets, so `lower` indexes like `src`
+    let lower = src.to_ascii_lowercase();
+    let mut rest = src;
+    while let Some(open) = rest.find('<') {
+        builder.push_text(&rest[..open]);
+        rest = &rest[open..];
+
+        if let Some(body) = rest.strip_prefix("<!--") {
+            rest = body.find("-->").map_or("", |end| &body[end + 3..]);
+            continue;
+        }
+        if let Some(body) = rest.strip_prefix("<![CDATA[") {
+            let end = body.find("]]>").unwrap_or(body.len());
+            builder.push_text(&body[..end]);
+            rest = body.get(end + 3..).unwrap_or("");
+            continue;
+        }
+
+        let Some(close) = rest.find('>') else { break };
+        let tag = &rest[1..close];
+        rest = &rest[close + 1..];
+        if tag.starts_with(['!', '?']) {
+            continue;
+        }
+
+        let closing = tag.starts_with('/');
+        let name: String = tag.trim_start_matches('/')
+            .chars()
+            .take_while(|c| !c.is_whitespace() && *c != '/')
+            .collect::<String>()
+            .to_lowercase();
+        match classify(&name) {
+            Mark::Skip if !closing && !tag.ends_with('/') => {
+                let end_tag = format!("</{}", name);
+                let at = src.len() - rest.len();
+                rest = lower[at..].find(&end_tag).map_or("", |i| &rest[i..]);
+            }
+            Mark::Space => builder.space(),
+            Mark::Paragraph => builder.paragraph(),
+            Mark::Page => builder.page(),
+            _ => {}
+        }
+    }
+    builder.push_text(rest);
+}
+
+/// Decodes the named entities common in documents plus numeric references.
+fn decode_entities(text: &str) -> String {
+    if !text.contains('&') {
+        return text.to_string();
+    }
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(amp) = rest.find('&') {
+        out.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+        // Searched in bytes: a cut at 12 may fall inside a multibyte character
+        let Some(semi) = rest.as_bytes()[..rest.len().min(12)].iter().position(|&b| b == b';') else {
+            out.push('&');
+            rest = &rest[1..];
+            continue;
+        };
+        let entity = &rest[1..semi];
+        let decoded = match entity {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            "nbsp" => Some(' '),
+            _ => entity.strip_prefix("#x").or_else(|| entity.strip_prefix("#X"))
+                .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                .or_else(|| entity.strip_prefix('#').and_then(|dec| dec.parse().ok()))
+                .and_then(char::from_u32),
+        };
+        match decoded {
+            Some(c) => {
+                out.push(c);
+                rest = &rest[semi + 1..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Text content of the first `<tag>` element, if any.
+fn element_text(src: &str, tag: &str) -> Option<String> {
+    let lower = src.to_ascii_lowercase();
+    let open = lower.find(&format!("<{}", tag.to_lowercase()))?;
+    let start = open + lower[open..].find('>')? + 1;
+    let end = start + lower[start..].find(&format!("</{}", tag.to_lowercase()))?;
+    let text = decode_entities(&src[start..end]).split_whitespace().collect::<Vec<_>>().join(" ");
+    (!text.is_empty()).then_some(text)
+}
+
+/// Value of `attr` on the first tag containing `marker`, e.g. `name="author"`.
+fn attribute(src: &str, marker: &str, attr: &str) -> Option<String> {
+    let lower = src.to_ascii_lowercase();
+    let at = lower.find(&marker.to_lowercase())?;
+    let tag_start = lower[..at].rfind('<')?;
+    let tag_end = at + lower[at..].find('>')?;
+    let tag = &src[tag_start..tag_end];
+    let key = format!("{}=", attr);
+    let value_at = tag.to_ascii_lowercase().find(&key)? + key.len();
+    let quote = tag[value_at..].chars().next()?;
+    let value = tag[value_at + quote.len_utf8()..].split(quote).next()?;
+    Some(decode_entities(value))
+}
+
+fn html_mark(name: &str) -> Mark {
+    match name {
+        "script" | "style" | "head" | "noscript" | "template" | "svg" => Mark::Skip,
+        "br" => Mark::Space,
+        "p" | "div" | "section" | "article" | "header" | "footer" | "main" | "aside" | "nav" | "li" | "ul" | "ol"
+        | "table" | "tr" | "blockquote" | "pre" | "h1" | "h2" | "h3" | "h4" | "h5" | "h6" | "hr" | "dt" | "dd"
+        | "figure" | "figcaption" | "body" => Mark::Paragraph,
+        "td" | "th" => Mark::Space,
+        _ => Mark::None,
+    }
+}
+
+fn html_to_text(src: &str, builder: &mut TextBuilder) {
+    walk(src, builder, html_mark);
+}
+
+pub struct HtmlExtractor;
+
+impl Extractor for HtmlExtractor {
+    fn name(&self) -> &'static str {
+        "html"
+    }
+
+    fn extract(&self, bytes: &[u8]) -> Result<Extracted, String> {
+        let src = String::from_utf8_lossy(bytes);
+        let mut builder = TextBuilder::default();
+        html_to_text(&src, &mut builder);
+        let (text, _) = builder.finish();
+        let meta = DocumentMeta {
+            title: element_text(&src, "title"),
+            author: attribute(&src, "name=\"author\"", "content"),
+        };
+        Ok(Extracted { text, pages: Vec::new(), meta })
+    }
+}
+
+/// Title and author from Dublin Core properties (`docProps/core.xml`, `meta.xml`, OPF).
+fn dublin_core(src: &str) -> DocumentMeta {
+    DocumentMeta {
+        title: element_text(src, "dc:title"),
+        author: element_text(src, "dc:creator").or_else(|| element_text(src, "meta:initial-creator")),
+    }
+}
+
+/// Word documents. Explicit and last-rendered page breaks become pages.
+pub struct DocxExtractor;
+
+impl Extractor for DocxExtractor {
+    fn name(&self) -> &'static str {
+        "docx"
+    }
+
+    fn extract(&self, bytes: &[u8]) -> Result<Extracted, String> {
+        let archive = ZipArchive::parse(bytes)?;
+        let body = archive.read_text("word/document.xml")?;
+
+        let mut builder = TextBuilder::default();
+        // `w:br` is a page break only with `w:type="page"`; tag names alone can't tell,
+        // so page breaks are rewritten to a marker element first.
+        let body = body.replace("<w:br w:type=\"page\"/>", "<kora:page/>");
+        walk(&body, &mut builder, |name| match name {
+            "w:p" => Mark::Paragraph,
+            "w:tab" | "w:br" => Mark::Space,
+            "kora:page" | "w:lastrenderedpagebreak" => Mark::Page,
+            "w:instrtext" | "w:deltext" => Mark::Skip,
+            _ => Mark::None,
+        });
+        let (text, pages) = builder.finish();
+
+        let meta = archive.read_text("docProps/core.xml").map(|core| dublin_core(&core)).unwrap_or_default();
+        Ok(Extracted { text, pages, meta })
+    }
+}
+
+/// OpenDocument text.
+pub struct OdtExtractor;
+
+impl Extractor for OdtExtractor {
+    fn name(&self) -> &'static str {
+        "odt"
+    }
+
+    fn extract(&self, bytes: &[u8]) -> Result<Extracted, String> {
+        let archive = ZipArchive::parse(bytes)?;
+        let content = archive.read_text("content.xml")?;
+
+        let mut builder = TextBuilder::default();
+        walk(&content, &mut builder, |name| match name {
+            "text:p" | "text:h" | "text:list-item" | "table:table-row" => Mark::Paragraph,
+            "text:s" | "text:tab" | "text:line-break" | "table:table-cell" => Mark::Space,
+            "office:automatic-styles" | "office:font-face-decls" => Mark::Skip,
+            _ => Mark::None,
+        });
+        let (text, pages) = builder.finish();
+
+        let meta = archive.read_text("meta.xml").map(|m| dublin_core(&m)).unwrap_or_default();
+        Ok(Extracted { text, pages, meta })
+    }
+}
+
+/// EPUB books. Each spine document is one page, so citations point at chapters.
+pub struct EpubExtractor;
+
+impl Extractor for EpubExtractor {
+    fn name(&self) -> &'static str {
+        "epub"
+    }
+
+    fn extract(&self, bytes: &[u8]) -> Result<Extracted, String> {
+        let archive = ZipArchive::parse(bytes)?;
+        let container = archive.read_text("META-INF/container.xml")?;
+        let opf_path = attribute(&container, "full-path=", "full-path").ok_or("EPUB has no package document")?;
+        let opf = archive.read_text(&opf_path)?;
+        let base = opf_path.rsplit_once('/').map_or(String::new(), |(dir, _)| format!("{}/", dir));
+
+        let mut builder = TextBuilder::default();
+        for idref in opf.split("<itemref").skip(1).filter_map(|t| attribute(&format!("<itemref{}", t), "idref=", "idref")) {
+            let Some(href) = attribute(&opf, &format!("id=\"{}\"", idref), "href") else { continue };
+            let Ok(chapter) = archive.read_text(&format!("{}{}", base, href)) else { continue };
+            builder.page();
+            html_to_text(&chapter, &mut builder);
+        }
+        let (text, pages) = builder.finish();
+
+        Ok(Extracted { text, pages, meta: dublin_core(&opf) })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::archive::build_zip;
+
+    #[test]
+    fn test_html_to_text() {
+        let html = r#"<html><head><title>Kora &amp; You</title><meta name="author" content="Ada"><style>p{}</style></head>
+            <body><h1>Bridge</h1><p>The lock&nbsp;blocks <b>IPC</b>.</p><script>alert(1)</script><ul><li>one</li><li>two</li></ul></body></html>"#;
+        let doc = HtmlExtractor.extract(html.as_bytes()).unwrap();
+        assert_eq!(doc.text, "Bridge\n\nThe lock blocks IPC.\n\none\n\ntwo\n\n");
+        assert_eq!(doc.meta, DocumentMeta { title: Some("Kora & You".into()), author: Some("Ada".into()) });
+    }
+
+    #[test]
+    fn test_entities_before_multibyte_text() {
+        assert_eq!(decode_entities("&amp;日本語テキスト"), "&日本語テキスト");
+        assert_eq!(decode_entities("&日本語;"), "&日本語;");
+        assert_eq!(decode_entities("a &#x65E5; b &bogus"), "a 日 b &bogus");
+
+        let html = "<meta name=\"author\" content=\"日本\"><p>&lt;日本語&gt;</p><SCRIPT>x</script><p>終</p>";
+        let doc = HtmlExtractor.extract(html.as_bytes()).unwrap();
+        assert_eq!(doc.text, "<日本語>\n\n終\n\n");
+        assert_eq!(doc.meta.author.as_deref(), Some("日本"));
+        // Unquoted values aren't supported, but must not split a character
+        assert!(attribute("<meta content=日本>", "content", "content").is_some());
+    }
+
+    #[test]
+    fn test_docx_paragraphs_pages_and_metadata() {
+        let body = r#"<w:document><w:body><w:p><w:r><w:t>First page</w:t></w:r></w:p><w:p><w:r><w:br w:type="page"/><w:t>Second</w:t><w:tab/><w:t>page</w:t></w:r></w:p></w:body></w:document>"#;
+        let core = "<cp:coreProperties><dc:title>Manual</dc:title><dc:creator>Grace</dc:creator></cp:coreProperties>";
+        let bytes = build_zip(&[("word/document.xml", body), ("docProps/core.xml", core)]);
+
+        let doc = DocxExtractor.extract(&bytes).unwrap();
+        assert_eq!(doc.text, "First page\n\nSecond page\n\n");
+        assert_eq!(doc.pages, vec![0, 12]);
+        assert_eq!(doc.meta.title.as_deref(), Some("Manual"));
+        assert_eq!(doc.meta.author.as_deref(), Some("Grace"));
+    }
+
+    #[test]
+    fn test_epub_chapters_become_pages() {
+        let container = r#"<container><rootfiles><rootfile full-path="OEBPS/book.opf" media-type="application/oebps-package+xml"/></rootfiles></container>"#;
+        let opf = r#"<package><metadata><dc:title>Book</dc:title></metadata><manifest><item id="c1" href="one.xhtml"/><item id="c2" href="two.xhtml"/></manifest><spine><itemref idref="c1"/><itemref idref="c2"/></spine></package>"#;
+        let bytes = build_zip(&[
+            ("META-INF/container.xml", container),
+            ("OEBPS/book.opf", opf),
+            ("OEBPS/one.xhtml", "<html><body><p>Chapter one</p></body></html>"),
+            ("OEBPS/two.xhtml", "<html><body><p>Chapter two</p></body></html>"),
+        ]);
+
+        let doc = EpubExtractor.extract(&bytes).unwrap();
+        assert_eq!(doc.text, "Chapter one\n\nChapter two\n\n");
+        assert_eq!(doc.pages, vec![0, 13]);
+        assert_eq!(doc.meta.title.as_deref(), Some("Book"));
+    }
+
+    #[test]
+    fn test_odt_paragraphs() {
+        let content = "<office:document-content><office:body><office:text><text:h>Title</text:h><text:p>Body<text:tab/>text</text:p></office:text></office:body></office:document-content>";
+        let bytes = build_zip(&[("content.xml", content), ("meta.xml", "<meta:initial-creator>Lin</meta:initial-creator>")]);
+        let doc = OdtExtractor.extract(&bytes).unwrap();
+        assert_eq!(doc.text, "Title\n\nBody text\n\n");
+        assert_eq!(doc.meta.author.as_deref(), Some("Lin"));
+    }
+}
@@ -0,0 +1,128 @@
+mod archive;
+mod markup;
+mod pdf;
+mod tabular;
+
+use std::ops::Range;
+use std::path::Path;
+
+/// Upper bound on text produced from a single document or archive entry.
+pub(super) const MAX_EXTRACTED_BYTES: u64 = 64 * 1024 * 1024;
+
+/// Document properties found by an extractor.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DocumentMeta {
+    pub title: Option<String>,
+    pub author: Option<String>,
+}
+
+/// Normalized text produced from a binary or markup document.
+#[derive(Debug, Default)]
+pub struct Extracted {
+    /// Plain text; paragraphs are separated by blank lines.
+    pub text: String,
+    /// Byte offsets in `text` where each page (or chapter) starts. Empty when
+    /// the format has no pagination.
+    pub pages: Vec<usize>,
+    pub meta: DocumentMeta,
+}
+
+impl Extracted {
+    /// Text ranges to chunk separately, with their 1-based page number.
+    pub fn page_ranges(&self) -> Vec<(Range<usize>, Option<i64>)> {
+        if self.pages.is_empty() {
+            return vec![(0..self.text.len(), None)];
+        }
+        self.pages.iter().enumerate().map(|(i, start)| {
+            let end = self.pages.get(i + 1).copied().unwrap_or(self.text.len());
+            (*start..end, Some(i as i64 + 1))
+        }).collect()
+    }
+}
+
+/// Turns a document format into text that can be chunked and cited.
+pub trait Extractor: Send + Sync {
+    /// Recorded with the indexed document (e.g. `pdf`, `docx`).
+    fn name(&self) -> &'static str;
+
+    fn extract(&self, bytes: &[u8]) -> Result<Extracted, String>;
+}
+
+/// Picks the extractor for a file by extension, falling back to content sniffing.
+///
+/// Returns `None` for plain text, which is indexed zero-copy from the file itself.
+pub fn extractor_for(path: &Path, bytes: &[u8]) -> Option<Box<dyn Extractor>> {
+    let ext = path.extension().and_then(|e| e.to_str()).unwrap_or("").to_lowercase();
+    match ext.as_str() {
+        "pdf" => return Some(Box::new(pdf::PdfExtractor)),
+        "docx" => return Some(Box::new(markup::DocxExtractor)),
+        "odt" => return Some(Box::new(markup::OdtExtractor)),
+        "epub" => return Some(Box::new(markup::EpubExtractor)),
+        "html" | "htm" | "xhtml" => return Some(Box::new(markup::HtmlExtractor)),
+        "csv" => return Some(Box::new(tabular::CsvExtractor)),
+        "json" => return Some(Box::new(tabular::JsonExtractor)),
+        _ => {}
+    }
+
+    // Sniff the magic bytes of files without a telling extension
+    if bytes.starts_with(b"%PDF-") {
+        return Some(Box::new(pdf::PdfExtractor));
+    }
+    if bytes.starts_with(b"PK\x03\x04") {
+        let archive = archive::ZipArchive::parse(bytes).ok()?;
+        if archive.contains("word/document.xml") {
+            return Some(Box::new(markup::DocxExtractor));
+        }
+        if archive.contains("META-INF/container.xml") {
+            return Some(Box::new(markup::EpubExtractor));
+        }
+        if archive.contains("content.xml") {
+            return Some(Box::new(markup::OdtExtractor));
+        }
+    }
+    let head = String::from_utf8_lossy(&bytes[..bytes.len().min(512)]).to_lowercase();
+    if head.trim_start().starts_with("<!doctype html") || head.contains("<html") {
+        return Some(Box::new(markup::HtmlExtractor));
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extractor_for_extension_and_magic() {
+        assert_eq!(extractor_for(Path::new("a.PDF"), b"").unwrap().name(), "pdf");
+        assert_eq!(extractor_for(Path::new("report"), b"%PDF-1.4\n").unwrap().name(), "pdf");
+        assert_eq!(extractor_for(Path::new("page"), b"<!DOCTYPE html><html>").unwrap().name(), "html");
+        assert!(extractor_for(Path::new("notes.md"), b"# Notes").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_indexed_docx_is_cited_by_page() {
+        let pool = crate::db::test_pool().await;
+        let body = r#"<w:document><w:body><w:p><w:r><w:t>Preface</w:t></w:r></w:p><w:p><w:r><w:br w:type="page"/><w:t>The bridge lock blocks IPC.</w:t></w:r></w:p></w:body></w:document>"#;
+        let path = std::env::temp_dir().join(format!("kora-extract-{}.docx", uuid::Uuid::new_v4()));
+        std::fs::write(&path, archive::build_zip(&[("word/document.xml", body)])).unwrap();
+
+        let result = crate::rag::index_file(&pool, &path.to_string_lossy(), "SYSTEM").await.unwrap();
+        let chunks = crate::rag::retrieval::retrieve(&pool, "SYSTEM", "bridge lock", 5).await.unwrap();
+        let (extractor,): (String,) = sqlx::query_as("SELECT extractor FROM document_metadata").fetch_one(&pool).await.unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(result.to_string().contains("(docx)"));
+        assert_eq!(extractor, "docx");
+        assert_eq!(chunks[0].text, "The bridge lock blocks IPC.\n\n");
+        assert_eq!(chunks[0].citation.page, Some(2));
+    }
+
+    #[test]
+    fn test_page_ranges() {
+        let doc = Extracted { text: "one\n\ntwo\n\n".into(), pages: vec![0, 5], ..Default::default() };
+        assert_eq!(doc.page_ranges(), vec![(0..5, Some(1)), (5..10, Some(2))]);
+
+        let doc = Extracted { text: "flat".into(), ..Default::default() };
+        assert_eq!(doc.page_ranges(), vec![(0..4, None)]);
+    }
+}
@@ -0,0 +1,321 @@
+use flate2::read::ZlibDecoder;
+use lazy_static::lazy_static;
+use regex::bytes::Regex;
+use std::io::Read;
+use super::{DocumentMeta, Extracted, Extractor, MAX_EXTRACTED_BYTES};
+
+lazy_static! {
+    static ref TITLE: Regex = Regex::new(r"/Title\s*\(((?:\\.|[^\\)])*)\)").unwrap();
+    static ref AUTHOR: Regex = Regex::new(r"/Author\s*\(((?:\\.|[^\\)])*)\)").unwrap();
+}
+
+/// Text from PDFs with standard (non-CID) fonts.
+///
+/// Content streams are scanned in file order and every stream with text
+/// operators counts as one page. This holds for the usual one-stream-per-page
+/// layout without resolving the page tree; text drawn with composite fonts or
+/// as images is not recovered.
+pub struct PdfExtractor;
+
+impl Extractor for PdfExtractor {
+    fn name(&self) -> &'static str {
+        "pdf"
+    }
+
+    fn extract(&self, bytes: &[u8]) -> Result<Extracted, String> {
+        if !bytes.starts_with(b"%PDF-") {
+            return Err("Not a PDF document".to_string());
+        }
+
+        let mut text = String::new();
+        let mut pages = Vec::new();
+        for stream in streams(bytes, MAX_EXTRACTED_BYTES) {
+            let page = content_text(&stream);
+            if page.trim().is_empty() {
+                continue;
+            }
+            pages.push(text.len());
+            text.push_str(page.trim());
+            text.push_str("\n\n");
+            if text.len() as u64 > MAX_EXTRACTED_BYTES {
+                break;
+            }
+        }
+
+        let meta = DocumentMeta {
+            title: TITLE.captures(bytes).map(|c| decode_literal(&c[1])),
+            author: AUTHOR.captures(bytes).map(|c| decode_literal(&c[1])),
+        };
+        Ok(Extracted { text, pages, meta })
+    }
+}
+
+/// Decoded payloads of the `stream ... endstream` objects, up to `budget`
+/// decoded bytes in total so many small deflate bombs cannot add up.
+fn streams(bytes: &[u8], budget: u64) -> Vec<Vec<u8>> {
+    let mut out = Vec::new();
+    let mut remaining = budget;
+    let mut at = 0;
+    // Start of the last `<<` before the stream, tracked forward rather than searched back
+    let mut dict_start = 0;
+    let mut scanned: usize = 0;
+    while let Some(found) = find(&bytes[at..], b"stream") {
+        let keyword = at + found;
+        at = keyword + 6;
+        if remaining == 0 {
+            break;
+        }
+        let window = scanned.saturating_sub(1);
+        if let Some(open) = bytes[window..keyword].windows(2).rposition(|w| w == b"<<") {
+            dict_start = window + open;
+        }
+        scanned = keyword;
+        // Skip the `endstream` keyword itself
+        if keyword >= 3 && &bytes[keyword - 3..keyword] == b"end" {
+            continue;
+        }
+        let mut start = at;
+        if bytes.get(start) == Some(&b'\r') {
+            start += 1;
+        }
+        if bytes.get(start) != Some(&b'\n') {
+            continue;
+        }
+        start += 1;
+        let Some(len) = find(&bytes[start..], b"endstream") else { break };
+        let data = &bytes[start..start + len];
+        at = start + len + 9;
+
+        let dict = &bytes[dict_start..keyword];
+        if find(dict, b"/FlateDecode").is_some() {
+            let mut decoded = Vec::new();
+            if ZlibDecoder::new(data).take(remaining).read_to_end(&mut decoded).is_ok() || !decoded.is_empty() {
+                remaining -= decoded.len() as u64;
+                out.push(decoded);
+            }
+        } else if find(dict, b"/Filter").is_none() {
+            let data = &data[..data.len().min(remaining as usize)];
+            remaining -= data.len() as u64;
+            out.push(data.to_vec());
+        }
+    }
+    out
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|w| w == needle)
+}
+
+/// Text shown by the `Tj`, `TJ`, `'` and `"` operators of a content stream.
+fn content_text(stream: &[u8]) -> String {
+    let mut text = String::new();
+    let mut operands: Vec<String> = Vec::new();
+    let mut i = 0;
+    let newline = |text: &mut String| {
+        if !text.is_empty() && !text.ends_with('\n') {
+            text.push('\n');
+        }
+    };
+
+    while i < stream.len() {
+        match stream[i] {
+            b'(' => {
+                let (literal, next) = read_literal(stream, i);
+                operands.push(decode_literal(&literal));
+                i = next;
+            }
+            b'<' if stream.get(i + 1) != Some(&b'<') => {
+                let end = stream[i..].iter().position(|b| *b == b'>').map_or(stream.len(), |p| i + p);
+                operands.push(decode_hex(&stream[i + 1..end]));
+                i = end + 1;
+            }
+            b'[' => {
+                // TJ arrays mix strings with kerning; large negative kerning is a word gap
+                let mut joined = String::new();
+                i += 1;
+                while i < stream.len() && stream[i] != b']' {
+                    match stream[i] {
+                        b'(' => {
+                            let (literal, next) = read_literal(stream, i);
+                            joined.push_str(&decode_literal(&literal));
+                            i = next;
+                        }
+                        b'<' => {
+                            let end = stream[i..].iter().position(|b| *b == b'>').map_or(stream.len(), |p| i + p);
+                            joined.push_str(&decode_hex(&stream[i + 1..end]));
+                            i = end + 1;
+                        }
+                        b'-' | b'0'..=b'9' | b'.' => {
+                            let end = stream[i..].iter().position(|b| !matches!(b, b'-' | b'0'..=b'9' | b'.')).map_or(stream.len(), |p| i + p);
+                            let kerning: f32 = std::str::from_utf8(&stream[i..end]).ok().and_then(|s| s.parse().ok()).unwrap_or(0.0);
+                            if kerning < -200.0 && !joined.ends_with(' ') {
+                                joined.push(' ');
+                            }
+                            i = end;
+                        }
+                        _ => i += 1,
+                    }
+                }
+                operands.push(joined);
+                i += 1;
+            }
+            b'%' => {
+                while i < stream.len() && stream[i] != b'\n' {
+                    i += 1;
+                }
+            }
+            c if c.is_ascii_alphabetic() || c == b'\'' || c == b'"' || c == b'*' => {
+                let end = stream[i..].iter().position(|b| !(b.is_ascii_alphabetic() || matches!(b, b'\'' | b'"' | b'*'))).map_or(stream.len(), |p| i + p);
+                match &stream[i..end] {
+                    b"Tj" | b"TJ" => {
+                        if let Some(s) = operands.last() {
+                            text.push_str(s);
+                        }
+                    }
+                    b"'" | b"\"" => {
+                        newline(&mut text);
+                        if let Some(s) = operands.last() {
+                            text.push_str(s);
+                        }
+                    }
+                    b"Td" | b"TD" | b"T*" | b"ET" => newline(&mut text),
+                    _ => {}
+                }
+                operands.clear();
+                i = end;
+            }
+            _ => i += 1,
+        }
+    }
+    text
+}
+
+/// Raw bytes of the literal string starting at `start`, and the index after it.
+fn read_literal(stream: &[u8], start: usize) -> (Vec<u8>, usize) {
+    let mut depth = 0;
+    let mut i = start;
+    let mut out = Vec::new();
+    while i < stream.len() {
+        let b = stream[i];
+        match b {
+            b'\\' => {
+                out.push(b);
+                if let Some(next) = stream.get(i + 1) {
+                    out.push(*next);
+                }
+                i += 2;
+                continue;
+            }
+            b'(' => {
+                depth += 1;
+                if depth > 1 {
+                    out.push(b);
+                }
+            }
+            b')' => {
+                depth -= 1;
+                if depth == 0 {
+                    return (out, i + 1);
+                }
+                out.push(b);
+            }
+            _ => out.push(b),
+        }
+        i += 1;
+    }
+    (out, i)
+}
+
+/// Resolves the escapes of a literal string; bytes are read as Latin-1.
+fn decode_literal(raw: &[u8]) -> String {
+    let mut out = String::new();
+    let mut i = 0;
+    while i < raw.len() {
+        if raw[i] != b'\\' {
+            out.push(raw[i] as char);
+            i += 1;
+            continue;
+        }
+        i += 1;
+        match raw.get(i) {
+            Some(b'n') => out.push('\n'),
+            Some(b'r') => out.push('\r'),
+            Some(b't') => out.push('\t'),
+            Some(d @ b'0'..=b'7') => {
+                let mut value = (d - b'0') as u32;
+                let mut digits = 1;
+                while digits < 3 && matches!(raw.get(i + 1), Some(b'0'..=b'7')) {
+                    i += 1;
+                    digits += 1;
+                    value = value * 8 + (raw[i] - b'0') as u32;
+                }
+                out.extend(char::from_u32(value));
+            }
+            Some(b'\n') | Some(b'\r') => {}
+            Some(c) => out.push(*c as char),
+            None => {}
+        }
+        i += 1;
+    }
+    out
+}
+
+fn decode_hex(hex: &[u8]) -> String {
+    let digits: Vec<u8> = hex.iter().filter(|b| b.is_ascii_hexdigit()).copied().collect();
+    digits.chunks(2)
+        .filter_map(|pair| {
+            let s = std::str::from_utf8(pair).ok()?;
+            u8::from_str_radix(&format!("{:0<2}", s), 16).ok()
+        })
+        .map(|b| b as char)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::write::ZlibEncoder;
+    use flate2::Compression;
+    use std::io::Write;
+
+    fn pdf_with_pages(pages: &[&str]) -> Vec<u8> {
+        let mut pdf = b"%PDF-1.4\n1 0 obj << /Title (Kora \\(Manual\\)) /Author (Ada) >> endobj\n".to_vec();
+        for (n, content) in pages.iter().enumerate() {
+            let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(content.as_bytes()).unwrap();
+            let data = encoder.finish().unwrap();
+            pdf.extend_from_slice(format!("{} 0 obj << /Length {} /Filter /FlateDecode >>\nstream\n", n + 2, data.len()).as_bytes());
+            pdf.extend_from_slice(&data);
+            pdf.extend_from_slice(b"\nendstream\nendobj\n");
+        }
+        pdf.extend_from_slice(b"%%EOF\n");
+        pdf
+    }
+
+    #[test]
+    fn test_extracts_text_per_page() {
+        let bytes = pdf_with_pages(&[
+            "BT /F1 12 Tf 72 720 Td (Bridge lock) Tj 0 -14 Td [(bl) 20 (ocks) -300 (IPC)] TJ ET",
+            "BT (Page \\050two\\051) Tj ET",
+        ]);
+        let doc = PdfExtractor.extract(&bytes).unwrap();
+        assert_eq!(doc.text, "Bridge lock\nblocks IPC\n\nPage (two)\n\n");
+        assert_eq!(doc.pages, vec![0, 24]);
+        assert_eq!(doc.meta.title.as_deref(), Some("Kora (Manual)"));
+        assert_eq!(doc.meta.author.as_deref(), Some("Ada"));
+    }
+
+    #[test]
+    fn test_decoded_streams_share_one_budget() {
+        let bomb = format!("BT ({}) Tj ET", "A".repeat(1000));
+        let bytes = pdf_with_pages(&[bomb.as_str(); 10]);
+        let decoded = streams(&bytes, 2500);
+        assert_eq!(decoded.iter().map(Vec::len).collect::<Vec<_>>(), vec![bomb.len(), bomb.len(), 2500 - 2 * bomb.len()]);
+        assert_eq!(streams(&bytes, MAX_EXTRACTED_BYTES).len(), 10);
+    }
+
+    #[test]
+    fn test_hex_strings() {
+        assert_eq!(content_text(b"BT <48656C6C6F> Tj ET"), "Hello\n");
+    }
+}
@@ -0,0 +1,122 @@
+use serde_json::Value;
+use super::{Extracted, Extractor};
+
+/// CSV tables. Each row becomes a line of `header: value` pairs so a chunk
+/// stays meaningful without the header row.
+pub struct CsvExtractor;
+
+/// Splits CSV text into records, honouring quoted fields and escaped quotes.
+fn parse_csv(src: &str) -> Vec<Vec<String>> {
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = src.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => row.push(std::mem::take(&mut field)),
+            '\r' if !quoted => {}
+            '\n' if !quoted => {
+                row.push(std::mem::take(&mut field));
+                rows.push(std::mem::take(&mut row));
+            }
+            _ => field.push(c),
+        }
+    }
+    if !field.is_empty() || !row.is_empty() {
+        row.push(field);
+        rows.push(row);
+    }
+    rows.retain(|r| r.iter().any(|f| !f.trim().is_empty()));
+    rows
+}
+
+impl Extractor for CsvExtractor {
+    fn name(&self) -> &'static str {
+        "csv"
+    }
+
+    fn extract(&self, bytes: &[u8]) -> Result<Extracted, String> {
+        let src = String::from_utf8_lossy(bytes);
+        let mut rows = parse_csv(src.trim_start_matches('\u{feff}')).into_iter();
+        let header = rows.next().unwrap_or_default();
+
+        let mut text = String::new();
+        for row in rows {
+            let line: Vec<String> = row.iter().enumerate()
+                .filter(|(_, value)| !value.trim().is_empty())
+                .map(|(i, value)| match header.get(i) {
+                    Some(name) if !name.trim().is_empty() => format!("{}: {}", name.trim(), value.trim()),
+                    _ => value.trim().to_string(),
+                })
+                .collect();
+            text.push_str(&line.join("; ").replace('\n', " "));
+            text.push('\n');
+        }
+        Ok(Extracted { text, ..Default::default() })
+    }
+}
+
+/// JSON documents, flattened to one `path: value` line per scalar.
+pub struct JsonExtractor;
+
+fn flatten(value: &Value, path: &str, out: &mut String) {
+    match value {
+        Value::Object(map) => {
+            for (key, child) in map {
+                let child_path = if path.is_empty() { key.clone() } else { format!("{}.{}", path, key) };
+                flatten(child, &child_path, out);
+            }
+        }
+        Value::Array(items) => {
+            for (i, child) in items.iter().enumerate() {
+                flatten(child, &format!("{}[{}]", path, i), out);
+            }
+        }
+        Value::Null => {}
+        Value::String(s) => out.push_str(&format!("{}: {}\n", path, s.replace('\n', " "))),
+        scalar => out.push_str(&format!("{}: {}\n", path, scalar)),
+    }
+}
+
+impl Extractor for JsonExtractor {
+    fn name(&self) -> &'static str {
+        "json"
+    }
+
+    fn extract(&self, bytes: &[u8]) -> Result<Extracted, String> {
+        let value: Value = serde_json::from_slice(bytes).map_err(|e| format!("Invalid JSON: {}", e))?;
+        let mut text = String::new();
+        flatten(&value, "", &mut text);
+        let title = value.get("title").or_else(|| value.get("name")).and_then(Value::as_str).map(str::to_string);
+        let author = value.get("author").and_then(Value::as_str).map(str::to_string);
+        Ok(Extracted { text, meta: super::DocumentMeta { title, author }, ..Default::default() })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_csv_rows_carry_headers() {
+        let csv = "name,role,notes\nAda,admin,\"likes \"\"Kora\"\", a lot\"\n\nLin,,\"multi\nline\"\n";
+        let doc = CsvExtractor.extract(csv.as_bytes()).unwrap();
+        assert_eq!(doc.text, "name: Ada; role: admin; notes: likes \"Kora\", a lot\nname: Lin; notes: multi line\n");
+    }
+
+    #[test]
+    fn test_json_is_flattened() {
+        let json = r#"{"title": "Runbook", "steps": [{"cmd": "kora system status"}, {"cmd": "kora agency list", "sudo": false}], "skip": null}"#;
+        let doc = JsonExtractor.extract(json.as_bytes()).unwrap();
+        assert_eq!(doc.text, "steps[0].cmd: kora system status\nsteps[1].cmd: kora agency list\nsteps[1].sudo: false\ntitle: Runbook\n");
+        assert_eq!(doc.meta.title.as_deref(), Some("Runbook"));
+        assert!(JsonExtractor.extract(b"{nope").is_err());
+    }
+}
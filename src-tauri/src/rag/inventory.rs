@@ -0,0 +1,325 @@
+use serde::Serialize;
+use sqlx::{Pool, Sqlite};
+use std::path::Path;
+use super::retrieval::{ChunkReader, Citation};
+use crate::audit;
+use crate::jail::{self, Namespace};
+use crate::AppState;
+
+/// One indexed file as shown in the knowledge inventory.
+#[derive(Clone, Debug, Serialize)]
+pub struct DocumentInfo {
+    pub path: String,
+    /// Bytes at index time; `None` for files indexed before sizes were recorded.
+    pub size: Option<i64>,
+    pub hash: String,
+    pub chunks: i64,
+    pub indexed_at: String,
+    pub extractor: String,
+    pub title: Option<String>,
+    pub author: Option<String>,
+    pub page_count: Option<i64>,
+    pub tags: Vec<String>,
+    /// URL or path the document was learned from, if fetched by `kora knowledge learn`.
+    pub source: Option<String>,
+    pub fetched_at: Option<String>,
+}
+
+#[derive(sqlx::FromRow)]
+struct DocumentRow {
+    path: String,
+    size: Option<i64>,
+    hash: String,
+    chunks: i64,
+    indexed_at: String,
+    extractor: String,
+    title: Option<String>,
+    author: Option<String>,
+    page_count: Option<i64>,
+    tags: String,
+    source: Option<String>,
+    fetched_at: Option<String>,
+}
+
+impl From<DocumentRow> for DocumentInfo {
+    fn from(row: DocumentRow) -> Self {
+        Self {
+            path: row.path,
+            size: row.size,
+            hash: row.hash,
+            chunks: row.chunks,
+            indexed_at: row.indexed_at,
+            extractor: row.extractor,
+            title: row.title,
+            author: row.author,
+            page_count: row.page_count,
+            tags: serde_json::from_str(&row.tags).unwrap_or_default(),
+            source: row.source,
+            fetched_at: row.fetched_at,
+        }
+    }
+}
+
+/// A chunk of a document with its text.
+#[derive(Clone, Debug, Serialize)]
+pub struct DocumentChunk {
+    pub document_id: String,
+    pub citation: Citation,
+    /// `None` when the file is gone or shorter than the indexed range.
+    pub text: Option<String>,
+}
+
+/// A document with all of its chunks.
+#[derive(Clone, Debug, Serialize)]
+pub struct DocumentDetail {
+    pub document: DocumentInfo,
+    pub chunks: Vec<DocumentChunk>,
+}
+
+const DOCUMENT_SELECT: &str =
+    "SELECT m.path, m.size, MIN(d.hash) AS hash, COUNT(d.id) AS chunks, m.indexed_at, m.extractor, m.title, m.author, m.page_count, m.tags, p.source, p.fetched_at
+     FROM document_metadata m
+     JOIN documents d ON d.path = m.path AND d.agency_id = m.agency_id
+     LEFT JOIN document_provenance p ON p.path = m.path AND p.agency_id = m.agency_id
+     WHERE m.agency_id = ?";
+
+/// Every indexed file of `agency_id`, by path.
+pub async fn list_documents(pool: &Pool<Sqlite>, agency_id: &str) -> Result<Vec<DocumentInfo>, String> {
+    let rows: Vec<DocumentRow> = sqlx::query_as(&format!("{} GROUP BY m.path ORDER BY m.path", DOCUMENT_SELECT))
+        .bind(agency_id)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(rows.into_iter().map(DocumentInfo::from).collect())
+}
+
+/// A single indexed file of `agency_id`, if indexed.
+pub async fn get_document(pool: &Pool<Sqlite>, agency_id: &str, path: &str) -> Result<Option<DocumentInfo>, String> {
+    let row: Option<DocumentRow> = sqlx::query_as(&format!("{} AND m.path = ? GROUP BY m.path", DOCUMENT_SELECT))
+        .bind(agency_id)
+        .bind(path)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(row.map(DocumentInfo::from))
+}
+
+/// The chunks of one file in document order, with their text.
+pub async fn document_chunks(pool: &Pool<Sqlite>, agency_id: &str, path: &str) -> Result<Vec<DocumentChunk>, String> {
+    let rows: Vec<(String, String, i64, i64, Option<i64>)> = sqlx::query_as(
+        "SELECT id, content, offset_start, offset_end, page FROM documents
+         WHERE agency_id = ? AND path = ? ORDER BY page, offset_start"
+    )
+    .bind(agency_id)
+    .bind(path)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let mut reader = ChunkReader::default();
+    Ok(rows.into_iter().map(|(document_id, stored, offset_start, offset_end, page)| {
+        let citation = Citation { path: path.to_string(), offset_start, offset_end, page };
+        let text = reader.read(stored, &citation);
+        DocumentChunk { document_id, citation, text }
+    }).collect())
+}
+
+/// Drops a file of `agency_id` from the index; chunks owned by other agencies
+/// at the same path are left alone. Returns the number of chunks removed.
+pub async fn remove_document(pool: &Pool<Sqlite>, agency_id: &str, path: &str) -> Result<u64, String> {
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    let removed = sqlx::query("DELETE FROM documents WHERE agency_id = ? AND path = ?")
+        .bind(agency_id)
+        .bind(path)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| e.to_string())?
+        .rows_affected();
+    for table in ["document_metadata", "document_provenance"] {
+        sqlx::query(&format!("DELETE FROM {} WHERE agency_id = ? AND path = ?", table))
+            .bind(agency_id)
+            .bind(path)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+    tx.commit().await.map_err(|e| e.to_string())?;
+    Ok(removed)
+}
+
+/// Replaces the tags of an indexed file. Tags are trimmed, deduplicated and sorted.
+pub async fn set_tags(pool: &Pool<Sqlite>, agency_id: &str, path: &str, tags: &[String]) -> Result<Vec<String>, String> {
+    let mut tags: Vec<String> = tags.iter().map(|t| t.trim().to_string()).filter(|t| !t.is_empty()).collect();
+    tags.sort();
+    tags.dedup();
+    let json = serde_json::to_string(&tags).map_err(|e| e.to_string())?;
+    let updated = sqlx::query("UPDATE document_metadata SET tags = ? WHERE agency_id = ? AND path = ?")
+        .bind(json)
+        .bind(agency_id)
+        .bind(path)
+        .execute(pool)
+        .await
+        .map_err(|e| e.to_string())?
+        .rows_affected();
+    if updated == 0 {
+        return Err(format!("Not indexed: {}", path));
+    }
+    Ok(tags)
+}
+
+/// Shows a disk path as its jailed path, if it is under one of the agency's roots.
+fn jailed(namespace: &Namespace, path: &str) -> String {
+    namespace.to_virtual(Path::new(path)).map(|p| p.to_string_lossy().to_string()).unwrap_or_else(|| path.to_string())
+}
+
+/// Rewrites retrieval citations to jailed paths, as handed to the UI and the shell.
+pub fn jail_citations(namespace: &Namespace, citations: &mut [Citation]) {
+    for citation in citations {
+        citation.path = jailed(namespace, &citation.path);
+    }
+}
+
+/// `list_documents` with jailed paths, as handed to the UI.
+pub async fn list_jailed(pool: &Pool<Sqlite>, namespace: &Namespace, agency_id: &str) -> Result<Vec<DocumentInfo>, String> {
+    let mut documents = list_documents(pool, agency_id).await?;
+    for document in &mut documents {
+        document.path = jailed(namespace, &document.path);
+    }
+    Ok(documents)
+}
+
+/// A document and its chunks by jailed path.
+pub async fn document_jailed(pool: &Pool<Sqlite>, namespace: &Namespace, agency_id: &str, path: &Path) -> Result<DocumentDetail, String> {
+    let disk = namespace.to_disk(path).to_string_lossy().to_string();
+    let mut document = get_document(pool, agency_id, &disk).await?.ok_or_else(|| format!("Not indexed: {}", path.display()))?;
+    let mut chunks = document_chunks(pool, agency_id, &disk).await?;
+    document.path = jailed(namespace, &document.path);
+    for chunk in &mut chunks {
+        chunk.citation.path = document.path.clone();
+    }
+    Ok(DocumentDetail { document, chunks })
+}
+
+/// Lists the active agency's indexed documents.
+#[tauri::command]
+pub async fn kora_knowledge_list(state: tauri::State<'_, AppState>) -> Result<Vec<DocumentInfo>, String> {
+    let agency_id = state.governance.get_active_agency_id();
+    // Only the agency's own knowledge root may be listed
+    jail::enforce(&state, &format!("/knowledge/{}", agency_id), "KNOWLEDGE_LIST").await?;
+    let documents = list_jailed(&state.db, &jail::namespace(&state, &agency_id), &agency_id).await?;
+    let _ = audit::log_event(&state.db, "KNOWLEDGE_LIST", "RING_3", &format!("count={}", documents.len()), &agency_id).await;
+    Ok(documents)
+}
+
+/// Returns one indexed document and its chunks.
+#[tauri::command]
+pub async fn kora_knowledge_document(state: tauri::State<'_, AppState>, path: String) -> Result<DocumentDetail, String> {
+    let valid_path = jail::enforce(&state, &path, "KNOWLEDGE_DOCUMENT").await?;
+    let agency_id = state.governance.get_active_agency_id();
+    let _ = audit::log_event(&state.db, "KNOWLEDGE_DOCUMENT", "RING_3", &valid_path.to_string_lossy(), &agency_id).await;
+    document_jailed(&state.db, &jail::namespace(&state, &agency_id), &agency_id, &valid_path).await
+}
+
+/// Removes a document from the active agency's index; the file itself is kept.
+#[tauri::command]
+pub async fn kora_knowledge_remove(state: tauri::State<'_, AppState>, path: String) -> Result<u64, String> {
+    let disk_path = jail::resolve(&state, &path, "KNOWLEDGE_REMOVE").await?;
+    let agency_id = state.governance.get_active_agency_id();
+    let removed = remove_document(&state.db, &agency_id, &disk_path.to_string_lossy()).await?;
+    let _ = audit::log_event(&state.db, "KNOWLEDGE_REMOVE", "RING_1", &format!("path={} chunks={}", path, removed), &agency_id).await;
+    Ok(removed)
+}
+
+/// Sets the tags of a document in the active agency's index.
+#[tauri::command]
+pub async fn kora_knowledge_tag(state: tauri::State<'_, AppState>, path: String, tags: Vec<String>) -> Result<Vec<String>, String> {
+    let disk_path = jail::resolve(&state, &path, "KNOWLEDGE_TAG").await?;
+    let agency_id = state.governance.get_active_agency_id();
+    let tags = set_tags(&state.db, &agency_id, &disk_path.to_string_lossy(), &tags).await?;
+    let _ = audit::log_event(&state.db, "KNOWLEDGE_TAG", "RING_3", &format!("path={} tags={}", path, tags.join(",")), &agency_id).await;
+    Ok(tags)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_pool;
+    use crate::rag;
+
+    #[tokio::test]
+    async fn test_inventory_lists_reads_tags_and_removes() {
+        let pool = test_pool().await;
+        let dir = std::env::temp_dir().join(format!("kora-inventory-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let notes = dir.join("notes.md");
+        let text = "# Bridge\nThe lock blocks IPC.\n# Engine\nOpenClaw suspends.\n";
+        std::fs::write(&notes, text).unwrap();
+        let path = notes.to_string_lossy().to_string();
+        let stats = rag::index_file(&pool, &path, "SYSTEM").await.unwrap();
+
+        let listed = list_documents(&pool, "SYSTEM").await.unwrap();
+        assert_eq!(listed.len(), 1);
+        assert_eq!((listed[0].size, listed[0].chunks, listed[0].extractor.as_str()), (Some(text.len() as i64), 2, "plain"));
+        assert_eq!(listed[0].hash, stats.hash);
+        assert!(list_documents(&pool, "OTHER").await.unwrap().is_empty());
+
+        let chunks = document_chunks(&pool, "SYSTEM", &path).await.unwrap();
+        let texts: Vec<_> = chunks.iter().map(|c| c.text.as_deref().unwrap()).collect();
+        assert_eq!(texts, vec!["# Bridge\nThe lock blocks IPC.\n", "# Engine\nOpenClaw suspends.\n"]);
+
+        // Tags are normalized and survive re-indexing
+        let tags = set_tags(&pool, "SYSTEM", &path, &[" ops ".into(), "bridge".into(), "ops".into()]).await.unwrap();
+        assert_eq!(tags, vec!["bridge", "ops"]);
+        std::fs::write(&notes, "# Bridge\nThe lock blocks all IPC.\n").unwrap();
+        rag::index_file(&pool, &path, "SYSTEM").await.unwrap();
+        assert_eq!(get_document(&pool, "SYSTEM", &path).await.unwrap().unwrap().tags, vec!["bridge", "ops"]);
+        assert!(set_tags(&pool, "OTHER", &path, &["x".into()]).await.is_err());
+
+        assert_eq!(remove_document(&pool, "OTHER", &path).await.unwrap(), 0);
+        assert_eq!(remove_document(&pool, "SYSTEM", &path).await.unwrap(), 1);
+        assert!(get_document(&pool, "SYSTEM", &path).await.unwrap().is_none());
+        let (fts,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM documents_fts").fetch_one(&pool).await.unwrap();
+        assert_eq!(fts, 0);
+        assert!(notes.exists());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_listed_paths_resolve_back_through_the_jail() {
+        let pool = test_pool().await;
+        let data = std::env::temp_dir().join(format!("kora-inventory-{}", uuid::Uuid::new_v4()));
+        let knowledge = data.join("knowledge/SYSTEM");
+        let namespace = Namespace::new("SYSTEM", knowledge.clone(), data.join("workspace/SYSTEM"));
+        std::fs::create_dir_all(knowledge.join("core")).unwrap();
+        let notes = knowledge.join("core/notes.md");
+        std::fs::write(&notes, "# Bridge\nThe lock blocks IPC.\n").unwrap();
+        rag::index_file(&pool, &notes.to_string_lossy(), "SYSTEM").await.unwrap();
+
+        let listed = list_jailed(&pool, &namespace, "SYSTEM").await.unwrap();
+        assert_eq!(listed[0].path, "/knowledge/SYSTEM/core/notes.md");
+        // What the UI got back passes the jail and finds the document
+        let valid = jail::KoraJail::new("SYSTEM").validate_path(&listed[0].path).unwrap();
+        let detail = document_jailed(&pool, &namespace, "SYSTEM", &valid).await.unwrap();
+        assert_eq!(detail.document.path, listed[0].path);
+        assert_eq!(detail.chunks[0].citation.path, listed[0].path);
+        assert_eq!(detail.chunks[0].text.as_deref(), Some("# Bridge\nThe lock blocks IPC.\n"));
+
+        // So does a cited path
+        let chunks = rag::retrieval::retrieve(&pool, "SYSTEM", "lock blocks", 3).await.unwrap();
+        let (_, mut citations) = rag::retrieval::assemble_context(&chunks, 1000);
+        jail_citations(&namespace, &mut citations);
+        assert_eq!(citations[0].path, listed[0].path);
+        let cited = jail::KoraJail::new("SYSTEM").validate_path(&citations[0].path).unwrap();
+        assert_eq!(document_jailed(&pool, &namespace, "SYSTEM", &cited).await.unwrap().document.path, listed[0].path);
+
+        assert_eq!(namespace.to_disk(&valid), notes);
+        assert_eq!(remove_document(&pool, "SYSTEM", &namespace.to_disk(&valid).to_string_lossy()).await.unwrap(), 1);
+        assert!(list_jailed(&pool, &namespace, "SYSTEM").await.unwrap().is_empty());
+        // Shared roots are not remapped
+        assert_eq!(namespace.to_disk(Path::new("/data/x")), Path::new("/data/x"));
+        assert_eq!(namespace.to_disk(Path::new("/knowledge/SYSTEM")), knowledge);
+
+        std::fs::remove_dir_all(&data).unwrap();
+    }
+}
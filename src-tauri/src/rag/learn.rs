@@ -0,0 +1,566 @@
+use serde::Serialize;
+use sqlx::{Pool, Sqlite};
+use std::future::Future;
+use std::net::{IpAddr, SocketAddr};
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
+use super::IndexStats;
+use crate::audit;
+use crate::jail;
+use crate::AppState;
+
+/// Largest document a fetcher will bring in.
+const MAX_FETCH_BYTES: u64 = 50 * 1024 * 1024;
+
+/// Most files taken from one directory source.
+const MAX_FETCH_FILES: usize = 1000;
+
+/// How long a download may take to connect, and to finish.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+const FETCH_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Most redirects followed for one download.
+const MAX_REDIRECTS: usize = 5;
+
+/// When set, an administrator lets `learn` download from loopback, private and
+/// link-local addresses.
+pub const PRIVATE_HOSTS_ENV: &str = "KORA_LEARN_PRIVATE_HOSTS";
+
+pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// A document obtained from a source, before it is written to the archive.
+#[derive(Clone, Debug)]
+pub struct Fetched {
+    /// Relative path under the archive folder.
+    pub name: PathBuf,
+    pub bytes: Vec<u8>,
+    /// URL or absolute path the bytes came from.
+    pub source: String,
+}
+
+/// Brings documents in from one kind of source (`kora knowledge learn`).
+pub trait Fetcher: Send + Sync {
+    /// Recorded as provenance (e.g. `file`, `http`).
+    fn name(&self) -> &'static str;
+
+    fn fetch<'a>(&'a self, source: &'a str) -> BoxFuture<'a, Result<Vec<Fetched>, String>>;
+}
+
+/// Whether `source` is a URL rather than a local path.
+pub fn is_url(source: &str) -> bool {
+    source.contains("://")
+}
+
+/// Picks the fetcher for a URL, a directory or a single file.
+pub fn fetcher_for(source: &str) -> Result<Box<dyn Fetcher>, String> {
+    if is_url(source) {
+        let scheme = source.split("://").next().unwrap_or("").to_ascii_lowercase();
+        return match scheme.as_str() {
+            "http" | "https" => Ok(Box::new(HttpFetcher::default())),
+            _ => Err(format!("Unsupported source scheme: {}", scheme)),
+        };
+    }
+    if Path::new(source).is_dir() {
+        Ok(Box::new(DirectoryFetcher))
+    } else {
+        Ok(Box::new(FileFetcher))
+    }
+}
+
+/// Refuses symlinks, so a source cannot lead out of the jailed root.
+fn not_symlink(path: &Path) -> Result<(), String> {
+    let metadata = std::fs::symlink_metadata(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    if metadata.file_type().is_symlink() {
+        return Err(format!("{} is a symlink", path.display()));
+    }
+    Ok(())
+}
+
+fn read_capped(path: &Path) -> Result<Vec<u8>, String> {
+    let size = std::fs::metadata(path).map_err(|e| format!("{}: {}", path.display(), e))?.len();
+    if size > MAX_FETCH_BYTES {
+        return Err(format!("{} exceeds {} bytes", path.display(), MAX_FETCH_BYTES));
+    }
+    std::fs::read(path).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+/// A single local file, archived under its own name. Symlinks are refused.
+pub struct FileFetcher;
+
+impl Fetcher for FileFetcher {
+    fn name(&self) -> &'static str {
+        "file"
+    }
+
+    fn fetch<'a>(&'a self, source: &'a str) -> BoxFuture<'a, Result<Vec<Fetched>, String>> {
+        Box::pin(async move {
+            let path = Path::new(source);
+            let name = path.file_name().ok_or_else(|| format!("Not a file: {}", source))?;
+            not_symlink(path)?;
+            Ok(vec![Fetched { name: PathBuf::from(name), bytes: read_capped(path)?, source: source.to_string() }])
+        })
+    }
+}
+
+/// Every file below a local directory, archived under `<dir name>/<relative path>`.
+/// Hidden entries and symlinks are skipped; a symlinked root is refused.
+pub struct DirectoryFetcher;
+
+impl Fetcher for DirectoryFetcher {
+    fn name(&self) -> &'static str {
+        "directory"
+    }
+
+    fn fetch<'a>(&'a self, source: &'a str) -> BoxFuture<'a, Result<Vec<Fetched>, String>> {
+        Box::pin(async move {
+            let root = Path::new(source);
+            not_symlink(root)?;
+            let base = PathBuf::from(root.file_name().unwrap_or_default());
+            let mut files = Vec::new();
+            let mut pending = vec![root.to_path_buf()];
+            while let Some(dir) = pending.pop() {
+                for entry in std::fs::read_dir(&dir).map_err(|e| e.to_string())? {
+                    let entry = entry.map_err(|e| e.to_string())?;
+                    if entry.file_name().to_string_lossy().starts_with('.') {
+                        continue;
+                    }
+                    let file_type = entry.file_type().map_err(|e| e.to_string())?;
+                    if file_type.is_dir() {
+                        pending.push(entry.path());
+                    } else if file_type.is_file() {
+                        files.push(entry.path());
+                    }
+                }
+            }
+            if files.len() > MAX_FETCH_FILES {
+                return Err(format!("{} holds more than {} files", source, MAX_FETCH_FILES));
+            }
+            files.sort();
+
+            files.into_iter().map(|path| {
+                let relative = path.strip_prefix(root).unwrap_or(&path);
+                Ok(Fetched { name: base.join(relative), bytes: read_capped(&path)?, source: path.to_string_lossy().to_string() })
+            }).collect()
+        })
+    }
+}
+
+/// Whether `ip` is reachable on the public internet: not loopback, private,
+/// link-local, shared, documentation, multicast or unspecified.
+fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, ..] = ip.octets();
+            !(ip.is_loopback() || ip.is_private() || ip.is_link_local() || ip.is_broadcast()
+                || ip.is_documentation() || ip.is_unspecified() || ip.is_multicast()
+                || a == 0 || (a == 100 && (64..128).contains(&b)))
+        }
+        IpAddr::V6(ip) => {
+            if let Some(v4) = ip.to_ipv4_mapped() {
+                return is_public(IpAddr::V4(v4));
+            }
+            let first = ip.segments()[0];
+            !(ip.is_loopback() || ip.is_unspecified() || ip.is_multicast()
+                || (first & 0xfe00) == 0xfc00 || (first & 0xffc0) == 0xfe80)
+        }
+    }
+}
+
+/// Checks the host of a URL that is about to be requested. Names are checked
+/// again once resolved (see [`PublicResolver`]).
+fn check_host(url: &reqwest::Url) -> Result<(), String> {
+    let host = url.host_str().unwrap_or("").trim_start_matches('[').trim_end_matches(']');
+    let public = match host.parse::<IpAddr>() {
+        Ok(ip) => is_public(ip),
+        Err(_) => {
+            let name = host.trim_end_matches('.').to_ascii_lowercase();
+            !name.is_empty() && name != "localhost" && !name.ends_with(".localhost")
+        }
+    };
+    if public {
+        Ok(())
+    } else {
+        Err(format!("{} is not a public host (set {} to allow it)", host, PRIVATE_HOSTS_ENV))
+    }
+}
+
+/// Resolves names to their public addresses only, so a name cannot point a
+/// download (or a redirect) at the local network.
+struct PublicResolver;
+
+impl reqwest::dns::Resolve for PublicResolver {
+    fn resolve(&self, name: reqwest::dns::Name) -> reqwest::dns::Resolving {
+        let host = name.as_str().to_string();
+        Box::pin(async move {
+            let addrs: Vec<SocketAddr> = tokio::net::lookup_host((host.as_str(), 0)).await?
+                .filter(|addr| is_public(addr.ip()))
+                .collect();
+            if addrs.is_empty() {
+                return Err(format!("{} does not resolve to a public address", host).into());
+            }
+            let addrs: reqwest::dns::Addrs = Box::new(addrs.into_iter());
+            Ok(addrs)
+        })
+    }
+}
+
+/// An HTTP(S) download, archived as `<host>/<last path segment>`.
+///
+/// Downloads time out, follow at most [`MAX_REDIRECTS`] redirects and only
+/// reach public hosts unless private ones are allowed.
+pub struct HttpFetcher {
+    client: reqwest::Client,
+    allow_private: bool,
+}
+
+impl Default for HttpFetcher {
+    /// Allows private hosts when [`PRIVATE_HOSTS_ENV`] is set.
+    fn default() -> Self {
+        Self::new(std::env::var_os(PRIVATE_HOSTS_ENV).is_some())
+    }
+}
+
+impl HttpFetcher {
+    pub fn new(allow_private: bool) -> Self {
+        Self::with_timeouts(allow_private, CONNECT_TIMEOUT, FETCH_TIMEOUT)
+    }
+
+    fn with_timeouts(allow_private: bool, connect: Duration, total: Duration) -> Self {
+        let redirects = reqwest::redirect::Policy::custom(move |attempt| {
+            if attempt.previous().len() > MAX_REDIRECTS {
+                return attempt.error(format!("more than {} redirects", MAX_REDIRECTS));
+            }
+            match check_host(attempt.url()) {
+                Err(e) if !allow_private => attempt.error(e),
+                _ => attempt.follow(),
+            }
+        });
+        let mut builder = reqwest::Client::builder()
+            .connect_timeout(connect)
+            .timeout(total)
+            .redirect(redirects);
+        if !allow_private {
+            builder = builder.dns_resolver(Arc::new(PublicResolver));
+        }
+        let client = builder.build().unwrap_or_default();
+        Self { client, allow_private }
+    }
+}
+
+/// Keeps a URL segment usable as a file name.
+fn sanitize(segment: &str) -> String {
+    segment.chars().map(|c| if c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_') { c } else { '_' }).collect()
+}
+
+fn extension_for(content_type: &str) -> Option<&'static str> {
+    let mime = content_type.split(';').next().unwrap_or("").trim().to_ascii_lowercase();
+    Some(match mime.as_str() {
+        "text/html" | "application/xhtml+xml" => "html",
+        "application/pdf" => "pdf",
+        "application/json" => "json",
+        "text/csv" => "csv",
+        "text/markdown" => "md",
+        "text/plain" => "txt",
+        "application/epub+zip" => "epub",
+        "application/vnd.openxmlformats-officedocument.wordprocessingml.document" => "docx",
+        "application/vnd.oasis.opendocument.text" => "odt",
+        _ => return None,
+    })
+}
+
+impl Fetcher for HttpFetcher {
+    fn name(&self) -> &'static str {
+        "http"
+    }
+
+    fn fetch<'a>(&'a self, source: &'a str) -> BoxFuture<'a, Result<Vec<Fetched>, String>> {
+        Box::pin(async move {
+            let url = reqwest::Url::parse(source).map_err(|e| format!("Invalid URL {}: {}", source, e))?;
+            if !self.allow_private {
+                check_host(&url)?;
+            }
+            let failed = |e: reqwest::Error| match std::error::Error::source(&e) {
+                _ if e.is_timeout() => format!("{} timed out", source),
+                Some(cause) => format!("{}: {}", e, cause),
+                None => e.to_string(),
+            };
+            let mut response = self.client.get(url.clone()).send().await.map_err(failed)?;
+            if !response.status().is_success() {
+                return Err(format!("{} answered {}", source, response.status()));
+            }
+            if response.content_length().is_some_and(|n| n > MAX_FETCH_BYTES) {
+                return Err(format!("{} exceeds {} bytes", source, MAX_FETCH_BYTES));
+            }
+            let content_type = response.headers().get(reqwest::header::CONTENT_TYPE)
+                .and_then(|v| v.to_str().ok())
+                .unwrap_or("")
+                .to_string();
+
+            let mut bytes = Vec::new();
+            while let Some(chunk) = response.chunk().await.map_err(failed)? {
+                bytes.extend_from_slice(&chunk);
+                if bytes.len() as u64 > MAX_FETCH_BYTES {
+                    return Err(format!("{} exceeds {} bytes", source, MAX_FETCH_BYTES));
+                }
+            }
+
+            let host = sanitize(url.host_str().unwrap_or("unknown-host"));
+            let segment = url.path_segments().and_then(|mut s| s.rfind(|s| !s.is_empty())).map(sanitize);
+            let mut file = segment.unwrap_or_else(|| "index".to_string());
+            if Path::new(&file).extension().is_none() {
+                if let Some(ext) = extension_for(&content_type) {
+                    file = format!("{}.{}", file, ext);
+                }
+            }
+            Ok(vec![Fetched { name: Path::new(&host).join(file), bytes, source: source.to_string() }])
+        })
+    }
+}
+
+/// One document brought in by `learn`.
+#[derive(Clone, Debug, Serialize)]
+pub struct LearnedDocument {
+    pub source: String,
+    /// Where it was written under the archive.
+    pub path: String,
+    pub fetcher: String,
+    pub content_hash: String,
+    pub fetched_at: String,
+    /// False when an identical copy was already in the archive.
+    pub written: bool,
+    pub index: Option<IndexStats>,
+    /// Indexing failure; the file stays in the archive.
+    pub error: Option<String>,
+}
+
+/// Fetches `source` into `archive`, records provenance and indexes each document
+/// for `agency_id`.
+///
+/// A document identical to the file already at its archive path is not written
+/// again; a different one is stored under a `-n` suffixed name.
+pub async fn learn(pool: &Pool<Sqlite>, agency_id: &str, archive: &Path, source: &str) -> Result<Vec<LearnedDocument>, String> {
+    learn_with(pool, agency_id, archive, source, fetcher_for(source)?.as_ref()).await
+}
+
+/// [`learn`] with a given fetcher.
+pub async fn learn_with(pool: &Pool<Sqlite>, agency_id: &str, archive: &Path, source: &str, fetcher: &dyn Fetcher) -> Result<Vec<LearnedDocument>, String> {
+    let fetched = fetcher.fetch(source).await?;
+
+    let mut learned = Vec::new();
+    for doc in fetched {
+        let content_hash = super::content_hash(&doc.bytes);
+        let target = archive.join(&doc.name);
+        let same = |p: &Path| std::fs::read(p).is_ok_and(|existing| super::content_hash(&existing) == content_hash);
+        let written = !same(&target);
+        let path = if written {
+            let path = super::unique_path(&target, Path::exists);
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+            }
+            std::fs::write(&path, &doc.bytes).map_err(|e| format!("{}: {}", path.display(), e))?;
+            path
+        } else {
+            target
+        };
+        let path_str = path.to_string_lossy().to_string();
+        let fetched_at = chrono::Utc::now().to_rfc3339();
+
+        sqlx::query(
+            "INSERT INTO document_provenance (agency_id, path, source, fetcher, content_hash, fetched_at) VALUES (?, ?, ?, ?, ?, ?)
+             ON CONFLICT (agency_id, path) DO UPDATE SET source = excluded.source, fetcher = excluded.fetcher,
+                 content_hash = excluded.content_hash, fetched_at = excluded.fetched_at"
+        )
+        .bind(agency_id)
+        .bind(&path_str)
+        .bind(&doc.source)
+        .bind(fetcher.name())
+        .bind(&content_hash)
+        .bind(&fetched_at)
+        .execute(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+
+        let (index, error) = match super::index_file(pool, &path_str, agency_id).await {
+            Ok(stats) => (Some(stats), None),
+            Err(e) => (None, Some(e)),
+        };
+        learned.push(LearnedDocument {
+            source: doc.source,
+            path: path_str,
+            fetcher: fetcher.name().to_string(),
+            content_hash,
+            fetched_at,
+            written,
+            index,
+            error,
+        });
+    }
+    Ok(learned)
+}
+
+/// Learns a URL or local path into the active agency's `knowledge/archive`.
+///
+/// Local sources are jailed paths such as `/knowledge/{agency}/inbox`.
+#[tauri::command]
+pub async fn kora_knowledge_learn(state: tauri::State<'_, AppState>, source: String) -> Result<Vec<LearnedDocument>, String> {
+    let agency_id = state.governance.get_active_agency_id();
+    let root = jail::resolve(&state, &format!("/knowledge/{}", agency_id), "KNOWLEDGE_LEARN").await?;
+    let source = if is_url(&source) {
+        source
+    } else {
+        jail::resolve(&state, &source, "KNOWLEDGE_LEARN").await?.to_string_lossy().to_string()
+    };
+
+    let learned = learn(&state.db, &agency_id, &root.join("archive"), &source).await?;
+    for doc in &learned {
+        let metadata = format!("source={} path={} hash={}", doc.source, doc.path, doc.content_hash);
+        let _ = audit::log_event(&state.db, "KNOWLEDGE_LEARN", "RING_1", &metadata, &agency_id).await;
+    }
+    Ok(learned)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_pool;
+    use crate::rag::{inventory, search};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// Stand-in web server: answers every request with `response`, or never
+    /// answers when it is empty.
+    async fn respond(response: String) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            let mut held = Vec::new();
+            while let Ok((mut socket, _)) = listener.accept().await {
+                let mut buf = [0u8; 4096];
+                let _ = socket.read(&mut buf).await;
+                if response.is_empty() {
+                    held.push(socket);
+                    continue;
+                }
+                let _ = socket.write_all(response.as_bytes()).await;
+                let _ = socket.shutdown().await;
+            }
+        });
+        base
+    }
+
+    /// Answers every request with `body` as `content_type`.
+    async fn serve(content_type: &'static str, body: &'static str) -> String {
+        respond(format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            content_type, body.len(), body
+        )).await
+    }
+
+    #[test]
+    fn test_fetcher_for_sources() {
+        assert_eq!(fetcher_for("https://example.com/a.pdf").unwrap().name(), "http");
+        assert_eq!(fetcher_for(&std::env::temp_dir().to_string_lossy()).unwrap().name(), "directory");
+        assert_eq!(fetcher_for("/knowledge/ACME/notes.md").unwrap().name(), "file");
+        assert!(fetcher_for("ftp://example.com/a").is_err());
+        assert_eq!(extension_for("text/html; charset=utf-8"), Some("html"));
+    }
+
+    #[tokio::test]
+    async fn test_learn_url_records_provenance_and_indexes() {
+        let pool = test_pool().await;
+        let archive = std::env::temp_dir().join(format!("kora-learn-{}", uuid::Uuid::new_v4())).join("archive");
+        let base = serve("text/html", "<html><body><p>The bridge lock blocks IPC.</p></body></html>").await;
+        let url = format!("{}/docs/bridge?v=2", base);
+
+        let fetcher = HttpFetcher::new(true);
+        let learned = learn_with(&pool, "SYSTEM", &archive, &url, &fetcher).await.unwrap();
+        assert_eq!(learned.len(), 1);
+        let doc = &learned[0];
+        assert!(doc.written && doc.error.is_none());
+        assert!(doc.path.ends_with("archive/127.0.0.1/bridge.html"), "{}", doc.path);
+        assert_eq!(doc.index.as_ref().unwrap().extractor, "html");
+
+        let hits = search::search(&pool, "SYSTEM", "bridge lock", 5).await.unwrap();
+        assert_eq!(hits[0].path, doc.path);
+        let listed = inventory::get_document(&pool, "SYSTEM", &doc.path).await.unwrap().unwrap();
+        assert_eq!(listed.source.as_deref(), Some(url.as_str()));
+
+        // Learning the same content again keeps the archived copy
+        let again = learn_with(&pool, "SYSTEM", &archive, &url, &fetcher).await.unwrap();
+        assert!(!again[0].written && again[0].path == doc.path);
+        assert!(again[0].index.as_ref().unwrap().unchanged);
+
+        std::fs::remove_dir_all(archive.parent().unwrap()).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_learn_directory_recurses() {
+        let pool = test_pool().await;
+        let base = std::env::temp_dir().join(format!("kora-learn-{}", uuid::Uuid::new_v4()));
+        let source = base.join("drop");
+        std::fs::create_dir_all(source.join("sub")).unwrap();
+        std::fs::write(source.join("a.md"), "Alpha notes.").unwrap();
+        std::fs::write(source.join("sub/b.md"), "Beta notes.").unwrap();
+        std::fs::write(source.join(".secret"), "skip").unwrap();
+        let archive = base.join("archive");
+
+        let learned = learn(&pool, "SYSTEM", &archive, &source.to_string_lossy()).await.unwrap();
+        let paths: Vec<String> = learned.iter().map(|d| Path::new(&d.path).strip_prefix(&archive).unwrap().to_string_lossy().to_string()).collect();
+        assert_eq!(paths, vec!["drop/a.md", "drop/sub/b.md"]);
+        assert!(learned.iter().all(|d| d.fetcher == "directory" && d.index.is_some()));
+        assert_eq!(inventory::list_documents(&pool, "SYSTEM").await.unwrap().len(), 2);
+
+        std::fs::remove_dir_all(&base).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_private_hosts_are_refused() {
+        assert!(is_public("93.184.216.34".parse().unwrap()) && is_public("2606:2800:220:1::1".parse().unwrap()));
+        for ip in ["127.0.0.1", "10.1.2.3", "172.16.0.1", "192.168.1.1", "169.254.169.254", "100.64.0.1", "0.0.0.0", "::1", "fe80::1", "fd00::1", "::ffff:127.0.0.1"] {
+            assert!(!is_public(ip.parse().unwrap()), "{}", ip);
+        }
+        for url in ["http://localhost/a", "http://app.localhost/a", "http://[::1]/a", "http://169.254.169.254/latest"] {
+            assert!(check_host(&reqwest::Url::parse(url).unwrap()).is_err(), "{}", url);
+        }
+        assert!(check_host(&reqwest::Url::parse("https://example.com/a").unwrap()).is_ok());
+
+        let base = serve("text/plain", "internal").await;
+        let err = HttpFetcher::new(false).fetch(&base).await.unwrap_err();
+        assert!(err.contains("not a public host"), "{}", err);
+        let resolved = reqwest::dns::Resolve::resolve(&PublicResolver, "localhost".parse().unwrap()).await;
+        assert!(resolved.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_redirect_loops_and_stalls_end() {
+        let looping = respond("HTTP/1.1 302 Found\r\nLocation: /again\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_string()).await;
+        let err = HttpFetcher::new(true).fetch(&looping).await.unwrap_err();
+        assert!(err.contains("redirects"), "{}", err);
+
+        let stalled = respond(String::new()).await;
+        let fetcher = HttpFetcher::with_timeouts(true, Duration::from_secs(1), Duration::from_millis(300));
+        let started = std::time::Instant::now();
+        let err = fetcher.fetch(&stalled).await.unwrap_err();
+        assert!(err.contains("timed out") && started.elapsed() < Duration::from_secs(5), "{}", err);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_symlinked_sources_are_refused() {
+        let base = std::env::temp_dir().join(format!("kora-learn-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(base.join("outside")).unwrap();
+        std::fs::write(base.join("outside/secret.md"), "Secret.").unwrap();
+        std::os::unix::fs::symlink(base.join("outside/secret.md"), base.join("link.md")).unwrap();
+        std::os::unix::fs::symlink(base.join("outside"), base.join("linked")).unwrap();
+
+        let file = base.join("link.md").to_string_lossy().to_string();
+        assert!(FileFetcher.fetch(&file).await.unwrap_err().contains("symlink"));
+        let dir = base.join("linked").to_string_lossy().to_string();
+        assert!(fetcher_for(&dir).unwrap().fetch(&dir).await.unwrap_err().contains("symlink"));
+        assert_eq!(FileFetcher.fetch(&base.join("outside/secret.md").to_string_lossy()).await.unwrap().len(), 1);
+
+        std::fs::remove_dir_all(&base).unwrap();
+    }
+}
@@ -0,0 +1,253 @@
+use memmap2::Mmap;
+use serde::Serialize;
+use sqlx::{Pool, Sqlite, SqliteConnection};
+use std::fs::File;
+use std::path::{Path, MAIN_SEPARATOR_STR};
+use super::embeddings;
+use crate::audit;
+use crate::AppState;
+
+/// Outcome of reconciling an agency's index with the files on disk.
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct ReindexReport {
+    /// Distinct indexed paths examined.
+    pub checked: usize,
+    pub unchanged: usize,
+    pub reindexed: usize,
+    /// Paths dropped because the file is gone.
+    pub removed: usize,
+    /// Paths that could not be re-indexed (unreadable, unknown agency, ...).
+    pub failed: usize,
+    /// FTS, vector, metadata and provenance rows left without a chunk.
+    pub orphans: u64,
+}
+
+/// Rows matching `path` itself or anything below it (for directory events).
+const UNDER_PATH: &str = "(path = ? OR substr(path, 1, length(?)) = ?)";
+
+fn dir_prefix(path: &str) -> String {
+    format!("{}{}", path.trim_end_matches(MAIN_SEPARATOR_STR), MAIN_SEPARATOR_STR)
+}
+
+/// Drops every chunk indexed from `path` (or from files under it), whatever
+/// agency owns them: the file is gone, so none of them can be read back.
+///
+/// Returns the number of chunks removed.
+pub async fn forget_path(pool: &Pool<Sqlite>, path: &str) -> Result<u64, String> {
+    let prefix = dir_prefix(path);
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    let removed = sqlx::query(&format!("DELETE FROM documents WHERE {}", UNDER_PATH))
+        .bind(path)
+        .bind(&prefix)
+        .bind(&prefix)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| e.to_string())?
+        .rows_affected();
+    for table in ["document_metadata", "document_provenance"] {
+        sqlx::query(&format!("DELETE FROM {} WHERE {}", table, UNDER_PATH))
+            .bind(path)
+            .bind(&prefix)
+            .bind(&prefix)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+    tx.commit().await.map_err(|e| e.to_string())?;
+    Ok(removed)
+}
+
+/// Moves the chunks of `from` (a file or directory) to `to` without re-reading
+/// them. Ownership follows the destination, so a move between
+/// `knowledge/{agency}` roots hands the chunks to `agency_id`.
+///
+/// Chunks previously indexed at `to` for that agency are replaced. Returns the
+/// number of chunks moved; zero means `from` was never indexed.
+pub async fn rename_path(pool: &Pool<Sqlite>, from: &str, to: &str, agency_id: &str) -> Result<u64, String> {
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    let moved = rename_in(&mut tx, from, to, agency_id).await?;
+    tx.commit().await.map_err(|e| e.to_string())?;
+    Ok(moved)
+}
+
+/// `rename_path` within a caller's transaction, so several moves commit together.
+pub async fn rename_in(conn: &mut SqliteConnection, from: &str, to: &str, agency_id: &str) -> Result<u64, String> {
+    let (from_prefix, to_prefix) = (dir_prefix(from), dir_prefix(to));
+
+    for table in ["documents", "document_metadata", "document_provenance"] {
+        sqlx::query(&format!("DELETE FROM {} WHERE agency_id = ? AND {}", table, UNDER_PATH))
+            .bind(agency_id)
+            .bind(to)
+            .bind(&to_prefix)
+            .bind(&to_prefix)
+            .execute(&mut *conn)
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+
+    // The FTS rows follow through the documents_fts_agency trigger
+    let mut moved = 0;
+    for table in ["documents", "document_metadata", "document_provenance"] {
+        let result = sqlx::query(&format!(
+            "UPDATE OR REPLACE {} SET agency_id = ?, path = CASE WHEN path = ? THEN ? ELSE ? || substr(path, length(?) + 1) END WHERE {}",
+            table, UNDER_PATH
+        ))
+        .bind(agency_id)
+        .bind(from)
+        .bind(to)
+        .bind(&to_prefix)
+        .bind(&from_prefix)
+        .bind(from)
+        .bind(&from_prefix)
+        .bind(&from_prefix)
+        .execute(&mut *conn)
+        .await
+        .map_err(|e| e.to_string())?;
+        if table == "documents" {
+            moved = result.rows_affected();
+        }
+    }
+    Ok(moved)
+}
+
+/// Deletes full-text, vector, metadata and provenance rows whose chunks no longer exist.
+pub async fn collect_orphans(pool: &Pool<Sqlite>) -> Result<u64, String> {
+    let mut removed = 0;
+    for statement in [
+        "DELETE FROM documents_fts WHERE document_id NOT IN (SELECT id FROM documents)",
+        "DELETE FROM chunk_embeddings WHERE document_id NOT IN (SELECT id FROM documents)",
+        "DELETE FROM document_metadata WHERE NOT EXISTS (SELECT 1 FROM documents d WHERE d.path = document_metadata.path AND d.agency_id = document_metadata.agency_id)",
+        "DELETE FROM document_provenance WHERE NOT EXISTS (SELECT 1 FROM documents d WHERE d.path = document_provenance.path AND d.agency_id = document_provenance.agency_id)",
+    ] {
+        removed += sqlx::query(statement)
+            .execute(pool)
+            .await
+            .map_err(|e| e.to_string())?
+            .rows_affected();
+    }
+    Ok(removed)
+}
+
+/// Content hash of a file as `index_file` computes it.
+pub fn disk_hash(path: &Path) -> Result<String, String> {
+    let file = File::open(path).map_err(|e| e.to_string())?;
+    let mmap = unsafe { Mmap::map(&file).map_err(|e| e.to_string())? };
+    Ok(super::content_hash(&mmap))
+}
+
+/// Brings the index of `agency_id` in line with disk: chunks of missing files
+/// are dropped, files whose hash changed or whose chunks predate the current
+/// index layout are re-indexed, then orphans are collected.
+pub async fn reconcile(pool: &Pool<Sqlite>, agency_id: &str) -> Result<ReindexReport, String> {
+    let indexed: Vec<(String, String)> = sqlx::query_as("SELECT path, MIN(hash) FROM documents WHERE agency_id = ? GROUP BY path")
+        .bind(agency_id)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let mut report = ReindexReport { checked: indexed.len(), ..Default::default() };
+    for (path, hash) in indexed {
+        if !Path::new(&path).is_file() {
+            forget_path(pool, &path).await?;
+            report.removed += 1;
+            continue;
+        }
+        let current = disk_hash(Path::new(&path));
+        let complete = match &current {
+            Ok(current) if *current == hash => super::is_fully_indexed(pool, &path, agency_id, embeddings::default_provider().model()).await?,
+            _ => false,
+        };
+        match current {
+            Ok(_) if complete => report.unchanged += 1,
+            Ok(_) => match super::index_file(pool, &path, agency_id).await {
+                Ok(_) => report.reindexed += 1,
+                Err(e) => {
+                    eprintln!("[RAG] Re-index failed for {}: {}", path, e);
+                    report.failed += 1;
+                }
+            },
+            Err(e) => {
+                eprintln!("[RAG] Cannot read {}: {}", path, e);
+                report.failed += 1;
+            }
+        }
+    }
+
+    report.orphans = collect_orphans(pool).await?;
+    Ok(report)
+}
+
+/// Reconciles the active agency's knowledge index with disk.
+#[tauri::command]
+pub async fn kora_knowledge_reindex(state: tauri::State<'_, AppState>) -> Result<ReindexReport, String> {
+    let agency_id = state.governance.get_active_agency_id();
+    let report = reconcile(&state.db, &agency_id).await?;
+    let metadata = format!(
+        "checked={} reindexed={} removed={} failed={} orphans={}",
+        report.checked, report.reindexed, report.removed, report.failed, report.orphans
+    );
+    let _ = audit::log_event(&state.db, "KNOWLEDGE_REINDEX", "RING_1", &metadata, &agency_id).await;
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_pool;
+    use crate::rag::{self, search};
+
+    async fn count(pool: &Pool<Sqlite>, table: &str) -> i64 {
+        let (n,): (i64,) = sqlx::query_as(&format!("SELECT COUNT(*) FROM {}", table)).fetch_one(pool).await.unwrap();
+        n
+    }
+
+    #[tokio::test]
+    async fn test_rename_moves_chunks_and_forget_drops_them() {
+        let pool = test_pool().await;
+        let dir = std::env::temp_dir().join(format!("kora-lifecycle-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(dir.join("notes")).unwrap();
+        let old = dir.join("notes").join("lock.md");
+        std::fs::write(&old, "The bridge lock blocks IPC.").unwrap();
+        rag::index_file(&pool, &old.to_string_lossy(), "SYSTEM").await.unwrap();
+
+        // Renaming the directory carries the files below it
+        let moved = dir.join("archive");
+        std::fs::rename(dir.join("notes"), &moved).unwrap();
+        let n = rename_path(&pool, &dir.join("notes").to_string_lossy(), &moved.to_string_lossy(), "SYSTEM").await.unwrap();
+        assert_eq!(n, 1);
+        let hits = search::search(&pool, "SYSTEM", "bridge", 10).await.unwrap();
+        assert_eq!(hits[0].path, moved.join("lock.md").to_string_lossy());
+        let (meta_path,): (String,) = sqlx::query_as("SELECT path FROM document_metadata").fetch_one(&pool).await.unwrap();
+        assert_eq!(meta_path, hits[0].path);
+
+        assert_eq!(forget_path(&pool, &moved.to_string_lossy()).await.unwrap(), 1);
+        for table in ["documents", "documents_fts", "chunk_embeddings", "document_metadata"] {
+            assert_eq!(count(&pool, table).await, 0, "{}", table);
+        }
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_reconcile_drops_missing_reindexes_changed_and_collects_orphans() {
+        let pool = test_pool().await;
+        let dir = std::env::temp_dir().join(format!("kora-reconcile-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let (kept, changed, deleted) = (dir.join("kept.md"), dir.join("changed.md"), dir.join("deleted.md"));
+        for path in [&kept, &changed, &deleted] {
+            std::fs::write(path, "The bridge lock blocks IPC.").unwrap();
+            rag::index_file(&pool, &path.to_string_lossy(), "SYSTEM").await.unwrap();
+        }
+        std::fs::write(&changed, "OpenClaw suspends when idle.").unwrap();
+        std::fs::remove_file(&deleted).unwrap();
+        sqlx::query("INSERT INTO documents_fts (content, document_id, path, agency_id) VALUES ('stale', 'gone', 'x', 'SYSTEM')").execute(&pool).await.unwrap();
+
+        let report = reconcile(&pool, "SYSTEM").await.unwrap();
+        assert_eq!(report, ReindexReport { checked: 3, unchanged: 1, reindexed: 1, removed: 1, failed: 0, orphans: 1 });
+        let hits = search::search(&pool, "SYSTEM", "suspends", 10).await.unwrap();
+        assert_eq!(hits[0].path, changed.to_string_lossy());
+        assert!(search::search(&pool, "SYSTEM", "bridge", 10).await.unwrap().iter().all(|h| h.path == kept.to_string_lossy()));
+        assert_eq!(count(&pool, "document_metadata").await, 2);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
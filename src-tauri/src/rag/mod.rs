@@ -0,0 +1,453 @@
+pub mod chunking;
+pub mod embeddings;
+pub mod extract;
+pub mod inventory;
+pub mod learn;
+pub mod lifecycle;
+pub mod organize;
+pub mod retrieval;
+pub mod search;
+
+use self::chunking::ChunkingStrategy;
+
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use sqlx::{Pool, Sqlite};
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::ops::Range;
+use std::path::{Component, Path, PathBuf};
+use uuid::Uuid;
+use memmap2::Mmap;
+
+const CHUNK_SIZE: usize = 50 * 1024; // 50KB
+const OVERLAP_SIZE: usize = 5 * 1024; // 10% overlap (5KB)
+
+/// SHA-256 of a file's contents, as stored in `documents.hash`.
+pub fn content_hash(bytes: &[u8]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(bytes);
+    format!("{:x}", hasher.finalize())
+}
+
+/// `target`, or the first `name-n.ext` variant of it for which `taken` is false.
+pub fn unique_path(target: &Path, taken: impl Fn(&Path) -> bool) -> PathBuf {
+    let stem = target.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+    let mut candidate = target.to_path_buf();
+    let mut n = 1;
+    while taken(&candidate) {
+        let name = match target.extension() {
+            Some(ext) => format!("{}-{}.{}", stem, n, ext.to_string_lossy()),
+            None => format!("{}-{}", stem, n),
+        };
+        candidate = target.with_file_name(name);
+        n += 1;
+    }
+    candidate
+}
+
+/// Agency owning a file: the one whose configured watch root holds it (the
+/// innermost, should roots nest), else the `{agency}` directory it sits in
+/// below `knowledge_base`. Only these known roots are stripped, so other
+/// `knowledge` directories in the path play no part.
+pub fn agency_for_path(path: &Path, knowledge_base: &Path, custom_roots: &[(String, PathBuf)]) -> Option<String> {
+    let custom = custom_roots.iter()
+        .filter(|(_, root)| path.starts_with(root))
+        .max_by_key(|(_, root)| root.components().count());
+    if let Some((agency_id, _)) = custom {
+        return Some(agency_id.clone());
+    }
+    let mut below = path.strip_prefix(knowledge_base).ok()?.components();
+    match (below.next(), below.next()) {
+        (Some(Component::Normal(agency_id)), Some(_)) => Some(agency_id.to_string_lossy().to_string()),
+        _ => None,
+    }
+}
+
+/// Resolves the agency a file is indexed for: the owner of the root it is
+/// under (see [`agency_for_path`]), otherwise `fallback` (the active context).
+///
+/// A root naming an agency that does not exist is rejected rather than
+/// silently indexed into the fallback.
+pub async fn resolve_agency(pool: &Pool<Sqlite>, path: &Path, knowledge_base: &Path, fallback: &str) -> Result<String, String> {
+    let custom_roots = crate::drivers::watch_config::custom_roots(pool).await?;
+    let Some(agency_id) = agency_for_path(path, knowledge_base, &custom_roots) else {
+        return Ok(fallback.to_string());
+    };
+    let exists: Option<(String,)> = sqlx::query_as("SELECT id FROM agencies WHERE id = ?")
+        .bind(&agency_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+    exists.map(|(id,)| id).ok_or_else(|| format!("Unknown agency for {}: {}", path.display(), agency_id))
+}
+
+/// Whether every chunk of a file has its chunk hash, full-text row and an
+/// embedding of `model`. Chunks indexed by earlier versions may lack some,
+/// and are only repaired by re-indexing even when the file is unchanged.
+pub async fn is_fully_indexed(pool: &Pool<Sqlite>, path: &str, agency_id: &str, model: &str) -> Result<bool, String> {
+    let (chunks, hashed, embedded): (i64, i64, i64) = sqlx::query_as(
+        "SELECT COUNT(*), COUNT(d.chunk_hash), COUNT(e.document_id) FROM documents d
+         LEFT JOIN chunk_embeddings e ON e.document_id = d.id AND e.model = ?
+         WHERE d.path = ? AND d.agency_id = ?"
+    )
+    .bind(model)
+    .bind(path)
+    .bind(agency_id)
+    .fetch_one(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+    let (searchable,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM documents_fts WHERE path = ? AND agency_id = ?")
+        .bind(path)
+        .bind(agency_id)
+        .fetch_one(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(hashed == chunks && embedded == chunks && searchable == chunks)
+}
+
+/// Outcome of indexing one file.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct IndexStats {
+    pub extractor: String,
+    pub strategy: String,
+    pub bytes: usize,
+    /// SHA-256 of the whole file.
+    pub hash: String,
+    /// True when the file hash matched and nothing was touched.
+    pub unchanged: bool,
+    pub chunks: usize,
+    /// Chunks whose content was already indexed and were kept as is.
+    pub reused: usize,
+    /// Chunks inserted, embedded and added to the full-text index.
+    pub added: usize,
+    /// Previously indexed chunks that no longer occur in the file.
+    pub removed: usize,
+}
+
+impl std::fmt::Display for IndexStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.unchanged {
+            return write!(f, "File already indexed and unchanged");
+        }
+        write!(
+            f,
+            "Indexed {} bytes via Mmap ({}) into {} {} chunks ({} reused, {} new). Hash: {}",
+            self.bytes, self.extractor, self.chunks, self.strategy, self.reused, self.added, self.hash
+        )
+    }
+}
+
+/// Indexes a file into the RAG system using Zero-Copy memory mapping.
+///
+/// Computes a streaming SHA-256 hash and stores metadata (offsets) in the database
+/// to allow high-performance retrieval without memory bloat. Chunks are owned by
+/// `agency_id` and only ever retrieved within it.
+///
+/// Re-indexing is incremental: chunks are matched to the previous ones by content
+/// hash, so only new chunks are embedded and added to the full-text index.
+pub async fn index_file(pool: &Pool<Sqlite>, file_path: &str, agency_id: &str) -> Result<IndexStats, String> {
+    let path = Path::new(file_path);
+    if !path.exists() {
+        return Err("File not found".to_string());
+    }
+
+    // 1. Open File and Mmap (Zero-Copy)
+    let file = File::open(path).map_err(|e| e.to_string())?;
+    let mmap = unsafe { Mmap::map(&file).map_err(|e| e.to_string())? };
+    let content_len = mmap.len();
+
+    // 2. Compute Hash (Streaming/Slice based)
+    let hash = content_hash(&mmap);
+
+    // 3. Check for Changes
+    let previous: Vec<(String, String, Option<String>, Option<i64>)> = sqlx::query_as(
+        "SELECT id, hash, chunk_hash, page FROM documents WHERE path = ? AND agency_id = ?"
+    )
+    .bind(file_path)
+    .bind(agency_id)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let embedder = embeddings::default_provider();
+    let same_file = previous.first().is_some_and(|(_, existing_hash, _, _)| *existing_hash == hash);
+    if same_file && is_fully_indexed(pool, file_path, agency_id, embedder.model()).await? {
+        return Ok(IndexStats { hash, bytes: content_len, unchanged: true, chunks: previous.len(), reused: previous.len(), ..Default::default() });
+    }
+
+    // 4. Extraction: binary and markup formats become normalized text that is
+    //    stored with each chunk; plain text stays zero-copy, read back via offsets.
+    let extractor = extract::extractor_for(path, &mmap);
+    let extracted = match &extractor {
+        Some(e) => Some(e.extract(&mmap)?),
+        None => None,
+    };
+    let extractor_name = extractor.as_ref().map_or("plain", |e| e.name());
+
+    // 5. Chunking, strategy picked by file type. Extracted pages are chunked
+    //    separately so every chunk cites a single page.
+    let mut ranges: Vec<(Range<usize>, Option<i64>)> = Vec::new();
+    let (strategy, source): (&str, &[u8]) = match &extracted {
+        Some(doc) => {
+            let chunker = chunking::MarkdownChunker { max_tokens: chunking::DEFAULT_MAX_TOKENS };
+            for (page_range, page) in doc.page_ranges() {
+                for r in chunker.chunk(&doc.text[page_range.clone()]) {
+                    ranges.push((page_range.start + r.start..page_range.start + r.end, page));
+                }
+            }
+            (chunker.name(), doc.text.as_bytes())
+        }
+        // Content that is not UTF-8 falls back to raw fixed-size windows
+        None => match std::str::from_utf8(&mmap) {
+            Ok(text) => {
+                let chunker = chunking::strategy_for(path);
+                ranges.extend(chunker.chunk(text).into_iter().map(|r| (r, None)));
+                (chunker.name(), &mmap[..])
+            }
+            Err(_) => {
+                ranges.extend(chunking::FixedSizeChunker::default().byte_ranges(content_len).into_iter().map(|r| (r, None)));
+                ("fixed", &mmap[..])
+            }
+        },
+    };
+
+    // Previous chunks available for reuse, by content hash and page
+    let mut stale: HashSet<String> = previous.iter().map(|(id, _, _, _)| id.clone()).collect();
+    let mut reusable: HashMap<(String, Option<i64>), Vec<String>> = HashMap::new();
+    for (id, _, chunk_hash, page) in previous {
+        if let Some(chunk_hash) = chunk_hash {
+            reusable.entry((chunk_hash, page)).or_default().push(id);
+        }
+    }
+    let mut stats = IndexStats {
+        extractor: extractor_name.to_string(),
+        strategy: strategy.to_string(),
+        bytes: content_len,
+        chunks: ranges.len(),
+        ..Default::default()
+    };
+
+    // 6. Atomic Update: reused chunks only get their new offsets; chunks left
+    //    over are deleted (their FTS rows and embeddings follow the DELETE)
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+
+    for (range, page) in &ranges {
+        let bytes = &source[range.clone()];
+        let chunk_hash = content_hash(bytes);
+        if let Some(id) = reusable.get_mut(&(chunk_hash.clone(), *page)).and_then(Vec::pop) {
+            sqlx::query("UPDATE documents SET hash = ?, offset_start = ?, offset_end = ? WHERE id = ?")
+                .bind(&hash)
+                .bind(range.start as i64)
+                .bind(range.end as i64)
+                .bind(&id)
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| e.to_string())?;
+            // Vectors of an earlier embedding model are not searched
+            if !embeddings::has_embedding(&mut tx, embedder.model(), &id).await? {
+                embeddings::store_embedding(&mut tx, embedder, &id, &String::from_utf8_lossy(bytes)).await?;
+            }
+            stale.remove(&id);
+            stats.reused += 1;
+            continue;
+        }
+
+        let id = Uuid::new_v4().to_string();
+        let text = String::from_utf8_lossy(bytes);
+        // Only extracted text is stored; plain files are read back from disk
+        let content = if extracted.is_some() { text.as_ref() } else { "" };
+        sqlx::query(
+            "INSERT INTO documents (id, path, hash, content, offset_start, offset_end, page, agency_id, chunk_hash) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)"
+        )
+        .bind(&id)
+        .bind(file_path)
+        .bind(&hash)
+        .bind(content)
+        .bind(range.start as i64)
+        .bind(range.end as i64)
+        .bind(page)
+        .bind(agency_id)
+        .bind(&chunk_hash)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| e.to_string())?;
+
+        search::index_chunk(&mut tx, &id, &text).await?;
+        embeddings::store_embedding(&mut tx, embedder, &id, &text).await?;
+        stats.added += 1;
+    }
+
+    // Anything not reused above is stale, including chunks indexed before chunk hashes existed
+    for id in &stale {
+        sqlx::query("DELETE FROM documents WHERE id = ?")
+            .bind(id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+    stats.removed = stale.len();
+
+    let meta = extracted.as_ref().map(|d| d.meta.clone()).unwrap_or_default();
+    let page_count = extracted.as_ref().map(|d| d.pages.len() as i64).filter(|n| *n > 0);
+    // Upsert so tags survive re-indexing
+    sqlx::query(
+        "INSERT INTO document_metadata (agency_id, path, extractor, title, author, page_count, indexed_at, size) VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+         ON CONFLICT (agency_id, path) DO UPDATE SET extractor = excluded.extractor, title = excluded.title, author = excluded.author,
+             page_count = excluded.page_count, indexed_at = excluded.indexed_at, size = excluded.size"
+    )
+    .bind(agency_id)
+    .bind(file_path)
+    .bind(extractor_name)
+    .bind(meta.title)
+    .bind(meta.author)
+    .bind(page_count)
+    .bind(chrono::Utc::now().to_rfc3339())
+    .bind(content_len as i64)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    tx.commit().await.map_err(|e| e.to_string())?;
+
+    stats.hash = hash;
+    Ok(stats)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chunk_boundaries() {
+        let content_len = 200 * 1024; // 200KB
+        let mut start = 0;
+        let mut count = 0;
+        
+        while start < content_len {
+            let end = std::cmp::min(start + CHUNK_SIZE, content_len);
+            assert!(end <= content_len);
+            assert!(end > start);
+            count += 1;
+            start += CHUNK_SIZE - OVERLAP_SIZE;
+            if start >= content_len { break; }
+        }
+        
+        // (200 / 45) ~ 4.4 -> 5 chunks approx (actually 50KB size, 5KB overlap -> 45KB step)
+        // 0-50, 45-95, 90-140, 135-185, 180-200
+        assert_eq!(count, 5);
+    }
+
+    #[test]
+    fn test_agency_for_path() {
+        let base = Path::new("/srv/kora/knowledge");
+        let owner = |path: &str| agency_for_path(Path::new(path), base, &[]);
+        assert_eq!(owner("/srv/kora/knowledge/ACME/notes/a.md").as_deref(), Some("ACME"));
+        assert_eq!(owner("/srv/kora/knowledge/b.md"), None);
+        assert_eq!(owner("/srv/kora/workspace/ACME/c.md"), None);
+        assert_eq!(owner("/home/u/knowledge/GLOBEX/d.md"), None);
+
+        // A data dir that itself lies below a `knowledge` directory
+        let nested = Path::new("/home/u/knowledge/app/knowledge");
+        let path = Path::new("/home/u/knowledge/app/knowledge/SYSTEM/e.md");
+        assert_eq!(agency_for_path(path, nested, &[]).as_deref(), Some("SYSTEM"));
+
+        let custom = [("ACME".to_string(), PathBuf::from("/mnt/share")), ("GLOBEX".to_string(), PathBuf::from("/mnt/share/globex"))];
+        assert_eq!(agency_for_path(Path::new("/mnt/share/knowledge/x.md"), base, &custom).as_deref(), Some("ACME"));
+        assert_eq!(agency_for_path(Path::new("/mnt/share/globex/y.md"), base, &custom).as_deref(), Some("GLOBEX"));
+        assert_eq!(agency_for_path(Path::new("/mnt/shared/z.md"), base, &custom), None);
+    }
+
+    #[tokio::test]
+    async fn test_reindex_reuses_unchanged_chunks() {
+        let pool = crate::db::test_pool().await;
+        let path = std::env::temp_dir().join(format!("kora-incremental-{}.log", Uuid::new_v4()));
+        let lines: Vec<String> = (0..3000).map(|i| format!("{} bridge heartbeat {} ok\n", i, i * 7919 % 10007)).collect();
+        std::fs::write(&path, lines.concat()).unwrap();
+        let path_str = path.to_string_lossy().to_string();
+
+        let first = index_file(&pool, &path_str, "SYSTEM").await.unwrap();
+        assert_eq!((first.strategy.as_str(), first.reused, first.added), ("cdc", 0, first.chunks));
+        assert!(index_file(&pool, &path_str, "SYSTEM").await.unwrap().unchanged);
+
+        let mut edited = lines.clone();
+        edited[1500] = "1500 OpenClaw suspended while idle\n".to_string();
+        std::fs::write(&path, edited.concat()).unwrap();
+        let second = index_file(&pool, &path_str, "SYSTEM").await.unwrap();
+        assert!(second.added <= 2 && second.added >= 1, "{:?}", second);
+        assert_eq!(second.reused + second.added, second.chunks);
+        assert_eq!(second.removed, first.chunks - second.reused);
+
+        let (rows,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM documents_fts").fetch_one(&pool).await.unwrap();
+        assert_eq!(rows as usize, second.chunks);
+        let chunks = retrieval::retrieve(&pool, "SYSTEM", "OpenClaw suspended", 1).await.unwrap();
+        assert!(chunks[0].text.contains("1500 OpenClaw suspended while idle"));
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_chunks_from_before_the_upgrade_are_repaired() {
+        let pool = crate::db::test_pool().await;
+        let path = std::env::temp_dir().join(format!("kora-legacy-{}.md", Uuid::new_v4()));
+        let text = "# Bridge\n\nThe lock blocks IPC while the kernel is sealed.\n";
+        std::fs::write(&path, text).unwrap();
+        let path_str = path.to_string_lossy().to_string();
+        // As indexed before full-text search, embeddings and chunk hashes
+        sqlx::query("INSERT INTO documents (id, path, hash, content, offset_start, offset_end, agency_id) VALUES ('legacy', ?, ?, '', 0, ?, 'SYSTEM')")
+            .bind(&path_str)
+            .bind(content_hash(text.as_bytes()))
+            .bind(text.len() as i64)
+            .execute(&pool)
+            .await
+            .unwrap();
+        let provider = embeddings::default_provider();
+        assert!(!is_fully_indexed(&pool, &path_str, "SYSTEM", provider.model()).await.unwrap());
+
+        let report = lifecycle::reconcile(&pool, "SYSTEM").await.unwrap();
+        assert_eq!((report.unchanged, report.reindexed), (0, 1));
+        assert!(is_fully_indexed(&pool, &path_str, "SYSTEM", provider.model()).await.unwrap());
+        let ids: Vec<(String,)> = sqlx::query_as("SELECT id FROM documents WHERE path = ?").bind(&path_str).fetch_all(&pool).await.unwrap();
+        assert!(!ids.is_empty() && ids.iter().all(|(id,)| id != "legacy"));
+        assert_eq!(search::search(&pool, "SYSTEM", "kernel sealed", 5).await.unwrap().len(), 1);
+
+        assert!(index_file(&pool, &path_str, "SYSTEM").await.unwrap().unchanged);
+        assert_eq!(lifecycle::reconcile(&pool, "SYSTEM").await.unwrap().unchanged, 1);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_agencies_never_see_each_others_chunks() {
+        let pool = crate::db::test_pool().await;
+        for agency in ["ACME", "GLOBEX"] {
+            sqlx::query("INSERT INTO agencies (id, name, created_at) VALUES (?, ?, datetime())").bind(agency).bind(agency).execute(&pool).await.unwrap();
+        }
+        let root = std::env::temp_dir().join(format!("kora-isolation-{}", Uuid::new_v4())).join("knowledge");
+        let mut files = Vec::new();
+        for (agency, text) in [("ACME", "The bridge lock blocks IPC for ACME."), ("GLOBEX", "The bridge lock blocks IPC for GLOBEX.")] {
+            let dir = root.join(agency);
+            std::fs::create_dir_all(&dir).unwrap();
+            let file = dir.join("lock.md");
+            std::fs::write(&file, text).unwrap();
+            let owner = resolve_agency(&pool, &file, &root, "SYSTEM").await.unwrap();
+            assert_eq!(owner, agency);
+            index_file(&pool, &file.to_string_lossy(), &owner).await.unwrap();
+            files.push((agency, file.to_string_lossy().to_string()));
+        }
+        assert!(resolve_agency(&pool, &root.join("INITECH/x.md"), &root, "SYSTEM").await.is_err());
+
+        let provider = embeddings::default_provider();
+        for (agency, own) in &files {
+            let hits = search::search(&pool, agency, "bridge lock", 10).await.unwrap();
+            assert!(!hits.is_empty() && hits.iter().all(|h| &h.path == own));
+            let vectors = embeddings::vector_search(&pool, provider, agency, "bridge lock", 10).await.unwrap();
+            assert_eq!(vectors.len(), 1);
+            let ranked = retrieval::hybrid_search(&pool, provider, agency, "bridge lock", 10).await.unwrap();
+            assert!(ranked.iter().all(|c| &c.citation.path == own));
+            let chunks = retrieval::retrieve(&pool, agency, "bridge lock GLOBEX ACME", 10).await.unwrap();
+            assert!(!chunks.is_empty() && chunks.iter().all(|c| &c.citation.path == own && c.text.contains(agency)));
+        }
+        assert!(retrieval::retrieve(&pool, "SYSTEM", "bridge lock", 10).await.unwrap().is_empty());
+
+        std::fs::remove_dir_all(root.parent().unwrap()).unwrap();
+    }
+}
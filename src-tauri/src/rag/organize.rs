@@ -0,0 +1,378 @@
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::Serialize;
+use sqlx::{Pool, Sqlite};
+use std::future::Future;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use super::lifecycle;
+use crate::audit;
+use crate::jail;
+use crate::AppState;
+
+/// Folder of the knowledge hierarchy a file belongs in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Category {
+    /// Master rules, agent identities and principles.
+    Core,
+    /// Raw processed data: transcripts, extracted documents, bulk exports.
+    Archive,
+    /// Structured analyses ready for the execution engine.
+    Dist,
+}
+
+impl Category {
+    pub const ALL: [Category; 3] = [Category::Core, Category::Archive, Category::Dist];
+
+    pub fn dir(self) -> &'static str {
+        match self {
+            Category::Core => "core",
+            Category::Archive => "archive",
+            Category::Dist => "dist",
+        }
+    }
+
+    /// First category named in a free-text engine answer.
+    pub fn from_answer(answer: &str) -> Option<Self> {
+        answer
+            .split(|c: char| !c.is_alphabetic())
+            .find_map(|word| Self::ALL.into_iter().find(|c| c.dir().eq_ignore_ascii_case(word)))
+    }
+}
+
+/// One file move of an organize plan.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct PlannedMove {
+    pub from: String,
+    pub to: String,
+    pub category: Category,
+    /// Rule (or engine answer) that picked the category.
+    pub reason: String,
+}
+
+/// Result of a librarian pass over an agency's knowledge root.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct OrganizePlan {
+    pub root: String,
+    pub moves: Vec<PlannedMove>,
+    /// Orphan files no rule could place; they are left where they are.
+    pub unclassified: Vec<String>,
+    pub applied: bool,
+}
+
+/// Bytes read from each file for content heuristics and engine prompts.
+const SAMPLE_BYTES: u64 = 4096;
+
+/// Files at or above this size are treated as raw bulk data.
+const LARGE_FILE_BYTES: u64 = 1024 * 1024;
+
+/// How long the engine gets to classify one file.
+const CLASSIFY_TIMEOUT: Duration = Duration::from_secs(30);
+
+const CORE_NAMES: &[&str] = &["rule", "regla", "identity", "identidad", "principle", "principio", "policy", "politica", "guideline", "persona", "soul", "constitution", "manifesto"];
+const DIST_NAMES: &[&str] = &["analysis", "analisis", "report", "informe", "summary", "resumen", "synthesis", "sintesis", "brief", "spec", "playbook"];
+const ARCHIVE_NAMES: &[&str] = &["transcript", "transcripcion", "raw", "dump", "export", "backup", "scrape", "crawl"];
+const ARCHIVE_EXTENSIONS: &[&str] = &[
+    "pdf", "docx", "odt", "epub", "html", "htm", "csv", "json", "xml", "log", "srt", "vtt", "mp3", "mp4", "wav", "m4a", "zip",
+];
+
+lazy_static! {
+    static ref TIMESTAMP_LINE: Regex = Regex::new(r"(?m)^\s*\[?\d{1,2}:\d{2}(:\d{2})?").unwrap();
+    static ref TABLE_RULE: Regex = Regex::new(r"(?m)^\s*\|\s*:?-{3,}").unwrap();
+    static ref DIST_HEADING: Regex = Regex::new(r"(?mi)^#{1,6}\s.*\b(findings|conclusions?|recommendations?|summary|analysis|hallazgos|conclusiones|recomendaciones)\b").unwrap();
+    static ref CORE_HEADING: Regex = Regex::new(r"(?mi)^#{1,6}\s.*\b(rules|principles|identity|reglas|principios|identidad)\b").unwrap();
+    static ref IMPERATIVE_LINE: Regex = Regex::new(r"(?mi)^\s*(?:[-*]|\d+\.)?\s*(always|never|must|you are|siempre|nunca)\b").unwrap();
+}
+
+/// Picks a category by rules: file name, then content, then extension, then size.
+///
+/// `sample` is the start of the file. Returns `None` when no rule applies.
+pub fn classify(path: &Path, size: u64, sample: &str) -> Option<(Category, String)> {
+    let stem = path.file_stem().map(|s| s.to_string_lossy().to_lowercase()).unwrap_or_default();
+    for (category, names) in [(Category::Core, CORE_NAMES), (Category::Dist, DIST_NAMES), (Category::Archive, ARCHIVE_NAMES)] {
+        if let Some(name) = names.iter().find(|n| stem.contains(*n)) {
+            return Some((category, format!("name contains '{}'", name)));
+        }
+    }
+
+    if TIMESTAMP_LINE.find_iter(sample).count() >= 3 {
+        return Some((Category::Archive, "timestamped transcript".to_string()));
+    }
+    if CORE_HEADING.is_match(sample) || IMPERATIVE_LINE.find_iter(sample).count() >= 3 {
+        return Some((Category::Core, "rules or principles".to_string()));
+    }
+    if DIST_HEADING.is_match(sample) || TABLE_RULE.is_match(sample) {
+        return Some((Category::Dist, "structured analysis".to_string()));
+    }
+
+    let ext = path.extension().and_then(|e| e.to_str()).unwrap_or("").to_ascii_lowercase();
+    if ARCHIVE_EXTENSIONS.contains(&ext.as_str()) {
+        return Some((Category::Archive, format!("raw .{} document", ext)));
+    }
+    if size >= LARGE_FILE_BYTES {
+        return Some((Category::Archive, "large file".to_string()));
+    }
+    None
+}
+
+/// Files under `root` outside the `core`, `archive` and `dist` folders, sorted.
+/// Hidden files and folders are skipped.
+fn orphans(root: &Path) -> Result<Vec<PathBuf>, String> {
+    let mut found = Vec::new();
+    let mut pending = vec![root.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        for entry in std::fs::read_dir(&dir).map_err(|e| e.to_string())? {
+            let entry = entry.map_err(|e| e.to_string())?;
+            let name = entry.file_name().to_string_lossy().to_string();
+            if name.starts_with('.') {
+                continue;
+            }
+            let file_type = entry.file_type().map_err(|e| e.to_string())?;
+            if file_type.is_dir() {
+                if dir != root || !Category::ALL.iter().any(|c| c.dir() == name) {
+                    pending.push(entry.path());
+                }
+            } else if file_type.is_file() {
+                found.push(entry.path());
+            }
+        }
+    }
+    found.sort();
+    Ok(found)
+}
+
+fn read_sample(path: &Path) -> String {
+    let mut bytes = Vec::new();
+    if let Ok(file) = std::fs::File::open(path) {
+        let _ = file.take(SAMPLE_BYTES).read_to_end(&mut bytes);
+    }
+    String::from_utf8_lossy(&bytes).to_string()
+}
+
+impl OrganizePlan {
+    /// Plans `from` into `category`, keeping its path relative to the root. A
+    /// name already taken on disk or by an earlier move gets a `-n` suffix.
+    fn push(&mut self, from: &Path, category: Category, reason: String) {
+        let root = Path::new(&self.root);
+        let relative = from.strip_prefix(root).unwrap_or(from);
+        let target = root.join(category.dir()).join(relative);
+        let to = super::unique_path(&target, |p| p.exists() || self.moves.iter().any(|m| Path::new(&m.to) == p));
+        self.moves.push(PlannedMove { from: from.to_string_lossy().to_string(), to: to.to_string_lossy().to_string(), category, reason });
+    }
+}
+
+/// Dry run: classifies every orphan file of `root` by rules.
+pub fn plan(root: &Path) -> Result<OrganizePlan, String> {
+    let mut plan = OrganizePlan { root: root.to_string_lossy().to_string(), ..Default::default() };
+    for path in orphans(root)? {
+        let size = std::fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+        match classify(&path, size, &read_sample(&path)) {
+            Some((category, reason)) => plan.push(&path, category, reason),
+            None => plan.unclassified.push(path.to_string_lossy().to_string()),
+        }
+    }
+    Ok(plan)
+}
+
+/// Asks the engine to place the files rules left unclassified.
+///
+/// `ask` receives a `CLASSIFY <name> SAMPLE <excerpt>` command and returns the
+/// answer; files whose answer names no category stay unclassified.
+pub async fn assist<F, Fut>(plan: &mut OrganizePlan, mut ask: F)
+where
+    F: FnMut(String) -> Fut,
+    Fut: Future<Output = Result<String, String>>,
+{
+    for path in std::mem::take(&mut plan.unclassified) {
+        let file = PathBuf::from(&path);
+        let name = file.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+        // The engine protocol is line based, so the excerpt is flattened to one line
+        let mut sample = read_sample(&file).split_whitespace().collect::<Vec<_>>().join(" ");
+        if sample.len() > 1000 {
+            let mut cut = 1000;
+            while !sample.is_char_boundary(cut) {
+                cut -= 1;
+            }
+            sample.truncate(cut);
+        }
+
+        match ask(format!("CLASSIFY {} SAMPLE {}", name, sample)).await.map(|a| Category::from_answer(&a)) {
+            Ok(Some(category)) => plan.push(&file, category, "engine".to_string()),
+            Ok(None) => plan.unclassified.push(path),
+            Err(e) => {
+                eprintln!("[LIBRARIAN] Engine could not classify {}: {}", path, e);
+                plan.unclassified.push(path);
+            }
+        }
+    }
+}
+
+/// Moves the planned files and updates their index paths, all or nothing.
+///
+/// Every source must still exist and no destination may have appeared since
+/// the plan was made. If a move or the index update fails, files already
+/// moved are put back.
+pub async fn apply(pool: &Pool<Sqlite>, agency_id: &str, plan: &OrganizePlan) -> Result<(), String> {
+    for m in &plan.moves {
+        if !Path::new(&m.from).is_file() {
+            return Err(format!("Source changed since planning: {}", m.from));
+        }
+        if Path::new(&m.to).exists() {
+            return Err(format!("Destination already exists: {}", m.to));
+        }
+    }
+
+    let mut done: Vec<&PlannedMove> = Vec::new();
+    let mut failure = None;
+    for m in &plan.moves {
+        let moved = Path::new(&m.to).parent().map_or(Ok(()), std::fs::create_dir_all).and_then(|_| std::fs::rename(&m.from, &m.to));
+        if let Err(e) = moved {
+            failure = Some(format!("Failed to move {}: {}", m.from, e));
+            break;
+        }
+        done.push(m);
+    }
+
+    if failure.is_none() {
+        let indexed = async {
+            let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+            for m in &done {
+                lifecycle::rename_in(&mut tx, &m.from, &m.to, agency_id).await?;
+            }
+            tx.commit().await.map_err(|e| e.to_string())
+        };
+        failure = indexed.await.err();
+    }
+
+    match failure {
+        None => Ok(()),
+        Some(e) => {
+            for m in done.iter().rev() {
+                let _ = std::fs::rename(&m.to, &m.from);
+            }
+            Err(e)
+        }
+    }
+}
+
+/// Runs the librarian over the active agency's knowledge root.
+///
+/// Without `apply` this is a dry run returning the plan. With `apply` the plan
+/// is made afresh and executed; every move is audited. `assisted` asks the
+/// engine about files the rules cannot place.
+#[tauri::command]
+pub async fn kora_knowledge_organize(state: tauri::State<'_, AppState>, apply: bool, assisted: Option<bool>) -> Result<OrganizePlan, String> {
+    let agency_id = state.governance.get_active_agency_id();
+    let root = jail::resolve(&state, &format!("/knowledge/{}", agency_id), "KNOWLEDGE_ORGANIZE").await?;
+
+    let mut organize = plan(&root)?;
+    if assisted.unwrap_or(false) {
+        let engine = &state.ai_engine;
+        assist(&mut organize, |command| {
+            let agency_id = agency_id.clone();
+            async move { engine.ask("CLASSIFY", &agency_id, &command, CLASSIFY_TIMEOUT).await }
+        }).await;
+    }
+
+    if !apply {
+        let metadata = format!("moves={} unclassified={}", organize.moves.len(), organize.unclassified.len());
+        let _ = audit::log_event(&state.db, "KNOWLEDGE_ORGANIZE_PLAN", "RING_3", &metadata, &agency_id).await;
+        return Ok(organize);
+    }
+
+    self::apply(&state.db, &agency_id, &organize).await?;
+    for m in &organize.moves {
+        let metadata = format!("from={} to={} category={} reason={}", m.from, m.to, m.category.dir(), m.reason);
+        let _ = audit::log_event(&state.db, "KNOWLEDGE_ORGANIZE_MOVE", "RING_1", &metadata, &agency_id).await;
+    }
+    organize.applied = true;
+    Ok(organize)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_pool;
+    use crate::rag::{self, search};
+
+    fn write(path: &Path, text: &str) {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, text).unwrap();
+    }
+
+    #[test]
+    fn test_classify_rules() {
+        let by = |name: &str, size: u64, sample: &str| classify(Path::new(name), size, sample).map(|(c, _)| c);
+        assert_eq!(by("agent-identity.md", 10, ""), Some(Category::Core));
+        assert_eq!(by("q3-report.md", 10, ""), Some(Category::Dist));
+        assert_eq!(by("call.txt", 10, "[00:01] Ada: hi\n[00:02] Lin: hello\n[00:05] Ada: bye\n"), Some(Category::Archive));
+        assert_eq!(by("voice.md", 10, "- Always answer in Spanish\n- Never share keys\n- Must cite sources\n"), Some(Category::Core));
+        assert_eq!(by("funnel.md", 10, "# Funnel\n## Findings\nConversion is low.\n"), Some(Category::Dist));
+        assert_eq!(by("leads.md", 10, "| a | b |\n|---|---|\n| 1 | 2 |\n"), Some(Category::Dist));
+        assert_eq!(by("manual.PDF", 10, ""), Some(Category::Archive));
+        assert_eq!(by("blob.bin", LARGE_FILE_BYTES, ""), Some(Category::Archive));
+        assert_eq!(by("notes.md", 10, "Some thoughts."), None);
+        assert_eq!(Category::from_answer("Dist."), Some(Category::Dist));
+        assert_eq!(Category::from_answer("I'd say: CORE"), Some(Category::Core));
+        assert_eq!(Category::from_answer("no idea"), None);
+    }
+
+    #[tokio::test]
+    async fn test_plan_assist_and_apply() {
+        let pool = test_pool().await;
+        let root = std::env::temp_dir().join(format!("kora-organize-{}", uuid::Uuid::new_v4()));
+        write(&root.join("rules.md"), "Be kind.");
+        write(&root.join("core/rules.md"), "Already placed.");
+        write(&root.join("clients/q3-analysis.md"), "Numbers.");
+        write(&root.join("notes.md"), "Some thoughts on the bridge lock.");
+        write(&root.join(".hidden"), "skip");
+        rag::index_file(&pool, &root.join("rules.md").to_string_lossy(), "SYSTEM").await.unwrap();
+
+        let mut organize = plan(&root).unwrap();
+        let moved: Vec<(String, Category)> = organize.moves.iter()
+            .map(|m| (Path::new(&m.to).strip_prefix(&root).unwrap().to_string_lossy().to_string(), m.category))
+            .collect();
+        assert_eq!(moved, vec![
+            ("dist/clients/q3-analysis.md".to_string(), Category::Dist),
+            ("core/rules-1.md".to_string(), Category::Core),
+        ]);
+        assert_eq!(organize.unclassified, vec![root.join("notes.md").to_string_lossy().to_string()]);
+        assert!(root.join("rules.md").exists(), "a dry run moves nothing");
+
+        assist(&mut organize, |command| async move {
+            assert!(command.starts_with("CLASSIFY notes.md SAMPLE Some thoughts"));
+            Ok("archive".to_string())
+        }).await;
+        assert!(organize.unclassified.is_empty());
+        assert_eq!(organize.moves[2].to, root.join("archive/notes.md").to_string_lossy());
+
+        apply(&pool, "SYSTEM", &organize).await.unwrap();
+        for m in &organize.moves {
+            assert!(!Path::new(&m.from).exists() && Path::new(&m.to).is_file());
+        }
+        let hits = search::search(&pool, "SYSTEM", "kind", 10).await.unwrap();
+        assert_eq!(hits[0].path, root.join("core/rules-1.md").to_string_lossy());
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_apply_is_all_or_nothing() {
+        let pool = test_pool().await;
+        let root = std::env::temp_dir().join(format!("kora-organize-{}", uuid::Uuid::new_v4()));
+        write(&root.join("rules.md"), "Be kind.");
+        write(&root.join("report.md"), "Numbers.");
+        let organize = plan(&root).unwrap();
+        assert_eq!(organize.moves.len(), 2);
+
+        // A destination appearing after planning aborts before anything moves
+        write(&root.join("dist/report.md"), "Someone else's.");
+        assert!(apply(&pool, "SYSTEM", &organize).await.is_err());
+        assert!(root.join("rules.md").exists() && root.join("report.md").exists());
+        assert!(!root.join("core/rules.md").exists());
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,267 @@
+use memmap2::Mmap;
+use serde::Serialize;
+use sqlx::{Pool, Sqlite};
+use std::collections::HashMap;
+use std::fs::File;
+use super::chunking::estimate_tokens;
+use super::embeddings::{self, EmbeddingProvider};
+use super::search;
+
+/// Number of chunks handed to the engine for one question.
+pub const DEFAULT_TOP_K: usize = 5;
+
+/// Context window budget, in estimated tokens.
+pub const CONTEXT_TOKEN_BUDGET: usize = 2048;
+
+/// Weight of the BM25 side in hybrid ranking; the rest goes to vector similarity.
+pub const HYBRID_ALPHA: f32 = 0.5;
+
+/// Where a piece of context came from: the file and the byte range read from it.
+///
+/// For extracted documents (PDF, DOCX, ...) the range is in the extracted text
+/// and `page` locates it in the original.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Citation {
+    pub path: String,
+    pub offset_start: i64,
+    pub offset_end: i64,
+    pub page: Option<i64>,
+}
+
+/// A chunk selected for a question, with its text read back from disk.
+#[derive(Clone, Debug)]
+pub struct RetrievedChunk {
+    pub citation: Citation,
+    pub text: String,
+}
+
+/// A chunk ranked by hybrid search.
+#[derive(Clone, Debug)]
+pub struct RankedChunk {
+    pub document_id: String,
+    pub citation: Citation,
+    /// Fused relevance in `[0, 1]`.
+    pub score: f32,
+}
+
+/// Ranks the agency's chunks by fusing BM25 and vector similarity.
+///
+/// BM25 scores are scaled by the best match so both sides fall in `[0, 1]`, then
+/// mixed with `HYBRID_ALPHA`. Chunks found by only one side still qualify.
+pub async fn hybrid_search(pool: &Pool<Sqlite>, provider: &dyn EmbeddingProvider, agency_id: &str, query: &str, top_k: usize) -> Result<Vec<RankedChunk>, String> {
+    let candidates = top_k * 4;
+    let lexical = search::search(pool, agency_id, query, candidates as u32).await?;
+    let semantic = embeddings::vector_search(pool, provider, agency_id, query, candidates).await?;
+
+    let best = lexical.iter().map(|h| h.score).fold(0.0, f64::max);
+    let mut fused: HashMap<String, RankedChunk> = HashMap::new();
+    for hit in lexical {
+        let bm25 = if best > 0.0 { (hit.score / best) as f32 } else { 0.0 };
+        fused.insert(hit.document_id.clone(), RankedChunk {
+            document_id: hit.document_id,
+            citation: Citation { path: hit.path, offset_start: hit.offset_start, offset_end: hit.offset_end, page: hit.page },
+            score: HYBRID_ALPHA * bm25,
+        });
+    }
+    for (document_id, similarity) in semantic {
+        if let Some(chunk) = fused.get_mut(&document_id) {
+            chunk.score += (1.0 - HYBRID_ALPHA) * similarity;
+            continue;
+        }
+        let row: Option<(String, i64, i64, Option<i64>)> = sqlx::query_as("SELECT path, offset_start, offset_end, page FROM documents WHERE id = ? AND agency_id = ?")
+            .bind(&document_id)
+            .bind(agency_id)
+            .fetch_optional(pool)
+            .await
+            .map_err(|e| e.to_string())?;
+        if let Some((path, offset_start, offset_end, page)) = row {
+            fused.insert(document_id.clone(), RankedChunk {
+                document_id,
+                citation: Citation { path, offset_start, offset_end, page },
+                score: (1.0 - HYBRID_ALPHA) * similarity,
+            });
+        }
+    }
+
+    let mut ranked: Vec<RankedChunk> = fused.into_values().collect();
+    ranked.sort_by(|a, b| b.score.total_cmp(&a.score));
+    ranked.truncate(top_k);
+    Ok(ranked)
+}
+
+/// Reads chunk text back: extracted documents keep it in the row (`stored`),
+/// plain files are read through the offsets, mapping each file once.
+#[derive(Default)]
+pub struct ChunkReader {
+    maps: HashMap<String, Option<Mmap>>,
+}
+
+impl ChunkReader {
+    /// `None` when the file is gone or shorter than the recorded range.
+    pub fn read(&mut self, stored: String, citation: &Citation) -> Option<String> {
+        if !stored.is_empty() {
+            return Some(stored);
+        }
+        let Citation { path, offset_start, offset_end, .. } = citation;
+        let map = self.maps.entry(path.clone()).or_insert_with(|| {
+            File::open(path).ok().and_then(|f| unsafe { Mmap::map(&f).ok() })
+        });
+        let bytes = map.as_ref()?.get(*offset_start as usize..*offset_end as usize)?;
+        Some(String::from_utf8_lossy(bytes).to_string())
+    }
+}
+
+/// Returns the `top_k` chunks of `agency_id` most relevant to `query`.
+///
+/// Candidates are ranked by hybrid search; their bytes are then read from the
+/// source files through the stored offsets. Chunks whose file is gone or
+/// shorter than the recorded range are skipped.
+pub async fn retrieve(pool: &Pool<Sqlite>, agency_id: &str, query: &str, top_k: usize) -> Result<Vec<RetrievedChunk>, String> {
+    let ranked = hybrid_search(pool, embeddings::default_provider(), agency_id, query, top_k).await?;
+
+    let mut reader = ChunkReader::default();
+    let mut chunks = Vec::new();
+    for chunk in ranked {
+        // Extracted documents keep their chunk text in the row
+        let stored: Option<(String,)> = sqlx::query_as("SELECT content FROM documents WHERE id = ? AND agency_id = ?")
+            .bind(&chunk.document_id)
+            .bind(agency_id)
+            .fetch_optional(pool)
+            .await
+            .map_err(|e| e.to_string())?;
+        let Some((stored,)) = stored else { continue };
+        if let Some(text) = reader.read(stored, &chunk.citation) {
+            chunks.push(RetrievedChunk { text, citation: chunk.citation });
+        }
+    }
+    Ok(chunks)
+}
+
+/// Packs chunks, best first, into a single-line context within `budget` tokens.
+///
+/// Each excerpt is labelled `[n]`, matching the n-th returned citation. The last
+/// excerpt that fits is truncated rather than dropped.
+pub fn assemble_context(chunks: &[RetrievedChunk], budget: usize) -> (String, Vec<Citation>) {
+    let mut context = String::new();
+    let mut citations = Vec::new();
+    let mut remaining = budget;
+
+    for chunk in chunks {
+        let label = format!("[{}] ", citations.len() + 1);
+        let overhead = estimate_tokens(&label) + 1;
+        if remaining <= overhead {
+            break;
+        }
+        // The engine protocol is line based, so excerpts are flattened to one line
+        let mut excerpt = chunk.text.split_whitespace().collect::<Vec<_>>().join(" ");
+        let max_bytes = (remaining - overhead) * 4;
+        if excerpt.len() > max_bytes {
+            let mut cut = max_bytes;
+            while !excerpt.is_char_boundary(cut) {
+                cut -= 1;
+            }
+            excerpt.truncate(cut);
+        }
+        if excerpt.is_empty() {
+            continue;
+        }
+
+        if !context.is_empty() {
+            context.push(' ');
+        }
+        context.push_str(&label);
+        context.push_str(&excerpt);
+        remaining = remaining.saturating_sub(overhead + estimate_tokens(&excerpt));
+        citations.push(chunk.citation.clone());
+    }
+
+    (context, citations)
+}
+
+/// Builds the engine command for a question grounded on `context`.
+pub fn build_prompt(query: &str, context: &str) -> String {
+    if context.is_empty() {
+        return format!("KNOWLEDGE {}", query);
+    }
+    format!("KNOWLEDGE {} CONTEXT {}", query, context)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_pool;
+
+    fn chunk(path: &str, text: &str) -> RetrievedChunk {
+        RetrievedChunk { citation: Citation { path: path.into(), offset_start: 0, offset_end: text.len() as i64, page: None }, text: text.into() }
+    }
+
+    #[tokio::test]
+    async fn test_retrieve_reads_ranked_chunks_through_offsets() {
+        let pool = test_pool().await;
+        let path = std::env::temp_dir().join(format!("kora-retrieval-{}.txt", uuid::Uuid::new_v4()));
+        let content = "The bridge lock blocks IPC.\nOpenClaw suspends when idle and restores its state.";
+        std::fs::write(&path, content).unwrap();
+        let path_str = path.to_string_lossy().to_string();
+
+        for (start, end) in [(0, 28), (28, content.len())] {
+            let id = uuid::Uuid::new_v4().to_string();
+            sqlx::query("INSERT INTO documents (id, path, hash, content, offset_start, offset_end, agency_id) VALUES (?, ?, 'h', '', ?, ?, 'SYSTEM')")
+                .bind(&id)
+                .bind(&path_str)
+                .bind(start as i64)
+                .bind(end as i64)
+                .execute(&pool)
+                .await
+                .unwrap();
+            let mut conn = pool.acquire().await.unwrap();
+            search::index_chunk(&mut conn, &id, &content[start..end]).await.unwrap();
+        }
+
+        let chunks = retrieve(&pool, "SYSTEM", "when does openclaw suspend idle", 5).await.unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(chunks.len(), 1);
+        assert_eq!(chunks[0].citation, Citation { path: path_str, offset_start: 28, offset_end: content.len() as i64, page: None });
+        assert!(chunks[0].text.starts_with("OpenClaw suspends"));
+    }
+
+    #[tokio::test]
+    async fn test_hybrid_search_fuses_lexical_and_semantic_matches() {
+        let pool = test_pool().await;
+        let dir = std::env::temp_dir().join(format!("kora-hybrid-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let exact = dir.join("exact.md");
+        let variant = dir.join("variant.md");
+        let unrelated = dir.join("unrelated.md");
+        std::fs::write(&exact, "Idle engines suspend after the timeout.").unwrap();
+        std::fs::write(&variant, "The engine suspends itself while idling.").unwrap();
+        std::fs::write(&unrelated, "Quarterly invoices are exported as CSV.").unwrap();
+        for path in [&exact, &variant, &unrelated] {
+            crate::rag::index_file(&pool, &path.to_string_lossy(), "SYSTEM").await.unwrap();
+        }
+
+        let ranked = hybrid_search(&pool, embeddings::default_provider(), "SYSTEM", "suspend idle engines", 3).await.unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        // The exact wording wins on both sides; the variant only matches through its vector
+        assert_eq!(ranked[0].citation.path, exact.to_string_lossy());
+        assert_eq!(ranked[1].citation.path, variant.to_string_lossy());
+        assert!(ranked[0].score > ranked[1].score);
+        assert!(ranked.iter().all(|c| c.score <= 1.0));
+    }
+
+    #[test]
+    fn test_assemble_context_respects_budget() {
+        let chunks = vec![chunk("/a", "alpha  beta\ngamma"), chunk("/b", &"delta ".repeat(200))];
+
+        let (context, citations) = assemble_context(&chunks, 40);
+        assert!(context.starts_with("[1] alpha beta gamma [2] delta"));
+        assert!(!context.contains('\n'));
+        assert!(estimate_tokens(&context) <= 40);
+        assert_eq!(citations.len(), 2);
+
+        let (context, citations) = assemble_context(&chunks, 1);
+        assert!(context.is_empty());
+        assert!(citations.is_empty());
+    }
+}
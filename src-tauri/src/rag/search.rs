@@ -0,0 +1,135 @@
+use serde::Serialize;
+use sqlx::{Pool, Sqlite, SqliteConnection};
+use crate::audit;
+use crate::AppState;
+
+/// Highlight markers wrapped around matched terms in snippets.
+pub const HIGHLIGHT_OPEN: &str = "<mark>";
+pub const HIGHLIGHT_CLOSE: &str = "</mark>";
+
+/// Upper bound on results returned by one search.
+const MAX_RESULTS: u32 = 100;
+
+/// One ranked full-text match.
+#[derive(Clone, Debug, Serialize, sqlx::FromRow)]
+pub struct SearchHit {
+    pub document_id: String,
+    pub path: String,
+    pub offset_start: i64,
+    pub offset_end: i64,
+    /// Page of extracted documents, when paginated.
+    pub page: Option<i64>,
+    /// Excerpt around the match with terms wrapped in highlight markers.
+    pub snippet: String,
+    /// BM25 relevance; higher is better.
+    pub score: f64,
+}
+
+/// Adds the text of a freshly inserted chunk to the full-text index.
+///
+/// Ownership (`path`, `agency_id`) is copied from the `documents` row.
+pub async fn index_chunk(conn: &mut SqliteConnection, document_id: &str, text: &str) -> Result<(), String> {
+    sqlx::query(
+        "INSERT INTO documents_fts (content, document_id, path, agency_id) SELECT ?, id, path, agency_id FROM documents WHERE id = ?"
+    )
+    .bind(text)
+    .bind(document_id)
+    .execute(conn)
+    .await
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Turns free text into an FTS5 query: every term quoted, any term may match.
+///
+/// Quoting keeps user input from being parsed as FTS5 syntax (`NEAR`, `*`, `:`).
+pub fn to_match_query(query: &str) -> Option<String> {
+    let terms: Vec<String> = query
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|t| !t.is_empty())
+        .map(|t| format!("\"{}\"", t))
+        .collect();
+    if terms.is_empty() {
+        return None;
+    }
+    Some(terms.join(" OR "))
+}
+
+/// Ranked full-text search over the chunks of one agency.
+pub async fn search(pool: &Pool<Sqlite>, agency_id: &str, query: &str, limit: u32) -> Result<Vec<SearchHit>, String> {
+    let Some(match_query) = to_match_query(query) else {
+        return Ok(Vec::new());
+    };
+
+    // bm25() is lower-is-better, so it is negated into a score
+    sqlx::query_as::<_, SearchHit>(
+        "SELECT f.document_id, f.path, d.offset_start, d.offset_end, d.page,
+                snippet(documents_fts, 0, ?, ?, '…', 16) AS snippet,
+                -bm25(documents_fts) AS score
+         FROM documents_fts f
+         JOIN documents d ON d.id = f.document_id
+         WHERE documents_fts MATCH ? AND f.agency_id = ?
+         ORDER BY bm25(documents_fts)
+         LIMIT ?"
+    )
+    .bind(HIGHLIGHT_OPEN)
+    .bind(HIGHLIGHT_CLOSE)
+    .bind(match_query)
+    .bind(agency_id)
+    .bind(limit.min(MAX_RESULTS))
+    .fetch_all(pool)
+    .await
+    .map_err(|e| e.to_string())
+}
+
+/// Searches the active agency's index and returns ranked snippets.
+#[tauri::command]
+pub async fn kora_knowledge_search(state: tauri::State<'_, AppState>, query: String, limit: Option<u32>) -> Result<Vec<SearchHit>, String> {
+    let agency_id = state.governance.get_active_agency_id();
+    let _ = audit::log_event(&state.db, "KNOWLEDGE_SEARCH", "RING_3", "QUERY_REDACTED", &agency_id).await;
+    search(&state.db, &agency_id, &query, limit.unwrap_or(20)).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_pool;
+    use crate::rag;
+
+    #[test]
+    fn test_match_query_escapes_fts_syntax() {
+        assert_eq!(to_match_query("bridge lock*").as_deref(), Some("\"bridge\" OR \"lock\""));
+        assert_eq!(to_match_query("NEAR(a:b)").as_deref(), Some("\"NEAR\" OR \"a\" OR \"b\""));
+        assert_eq!(to_match_query("  ?! "), None);
+    }
+
+    #[tokio::test]
+    async fn test_search_ranks_and_follows_reindex() {
+        let pool = test_pool().await;
+        let dir = std::env::temp_dir().join(format!("kora-fts-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let lock = dir.join("lock.md");
+        let engine = dir.join("engine.md");
+        std::fs::write(&lock, "The bridge lock blocks every IPC call while locked.").unwrap();
+        std::fs::write(&engine, "OpenClaw suspends when idle; the bridge wakes it.").unwrap();
+        rag::index_file(&pool, &lock.to_string_lossy(), "SYSTEM").await.unwrap();
+        rag::index_file(&pool, &engine.to_string_lossy(), "SYSTEM").await.unwrap();
+
+        let hits = search(&pool, "SYSTEM", "bridge lock", 10).await.unwrap();
+        assert_eq!(hits.len(), 2);
+        assert_eq!(hits[0].path, lock.to_string_lossy());
+        assert!(hits[0].snippet.contains("<mark>lock</mark>"));
+        assert!(hits[0].score > hits[1].score);
+        assert!(search(&pool, "OTHER", "bridge", 10).await.unwrap().is_empty());
+
+        // Re-indexing replaces the old text rather than adding to it
+        std::fs::write(&lock, "Nothing to see here.").unwrap();
+        rag::index_file(&pool, &lock.to_string_lossy(), "SYSTEM").await.unwrap();
+        let hits = search(&pool, "SYSTEM", "lock", 10).await.unwrap();
+        assert!(hits.is_empty());
+        let rows: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM documents_fts").fetch_one(&pool).await.unwrap();
+        assert_eq!(rows.0, 2);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -13,6 +13,9 @@ pub struct Secret {
 }
 
 /// An in-memory secure vault for managing ephemeral secrets and environment variables.
+///
+/// Clones share the same underlying store.
+#[derive(Clone)]
 pub struct SecretVault {
     #[allow(dead_code)]
     secrets: Arc<RwLock<HashMap<String, String>>>,
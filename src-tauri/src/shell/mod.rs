@@ -0,0 +1,509 @@
+pub mod parser;
+
+use sqlx::{Pool, Sqlite};
+use std::future::Future;
+use std::time::Instant;
+use sysinfo::System;
+use tauri::{AppHandle, Manager, State};
+use tokio::sync::broadcast;
+use crate::ai_engine::requests::RequestRegistry;
+use crate::ai_engine::{AiEngine, EngineEvent, EngineHost, EngineMessage};
+use crate::governance::agency;
+use crate::rag::{learn, organize, retrieval::Citation};
+use crate::{audit, jail, AppState};
+use parser::{Command, Flags, Invocation};
+
+// Colors of the Kernel Shell, as used by the UI
+const GOLD: &str = "\x1b[38;2;212;178;53m";
+const GRAY: &str = "\x1b[38;2;100;100;100m";
+const LABEL: &str = "\x1b[33m";
+const RED: &str = "\x1b[31m";
+const GREEN: &str = "\x1b[32m";
+const RESET: &str = "\x1b[0m";
+
+fn paint(color: &str, text: &str) -> String {
+    format!("{}{}{}", color, text, RESET)
+}
+
+fn row(label: &str, value: impl std::fmt::Display) -> String {
+    format!("{}{:<13}{} {}", LABEL, format!("{}:", label), RESET, value)
+}
+
+/// What a command produced.
+pub enum Outcome {
+    Lines(Vec<String>),
+    /// An engine request whose answer is streamed into the terminal.
+    Stream {
+        request_id: String,
+        events: broadcast::Receiver<EngineMessage>,
+        citations: Vec<Citation>,
+    },
+}
+
+/// Where the interpreter writes: a terminal session, or a buffer in tests.
+pub trait Console: Send {
+    fn print(&mut self, text: &str);
+    /// Gives the shell prompt back.
+    fn prompt(&mut self);
+    /// Reports how a `--silent` command ended.
+    fn notify(&mut self, title: String, body: String);
+}
+
+struct SessionConsole<'a> {
+    app: &'a AppHandle,
+    session_id: &'a str,
+}
+
+impl Console for SessionConsole<'_> {
+    fn print(&mut self, text: &str) {
+        let _ = self.app.state::<AppState>().pty.print(self.session_id, text);
+    }
+
+    fn prompt(&mut self) {
+        let _ = self.app.state::<AppState>().pty.prompt(self.session_id);
+    }
+
+    fn notify(&mut self, title: String, body: String) {
+        crate::drivers::notify::send_notification(self.app.clone(), title, body);
+    }
+}
+
+/// Writes below the command line. Lines are separated rather than ended,
+/// since the shell starts its next prompt on a new line itself.
+struct Printer<'a> {
+    console: &'a mut dyn Console,
+    silent: bool,
+    /// The cursor is after printed text rather than at the start of a line.
+    open: bool,
+}
+
+impl Printer<'_> {
+    fn line(&mut self, text: &str) {
+        let separator = if self.open { "\r\n" } else { "" };
+        self.text(&format!("{}{}", separator, text));
+    }
+
+    fn lines(&mut self, lines: &[String]) {
+        for line in lines {
+            self.line(line);
+        }
+    }
+
+    /// Streamed text, continuing the current line.
+    fn text(&mut self, text: &str) {
+        if !self.silent && !text.is_empty() {
+            let text = if text.contains('\r') { text.to_string() } else { text.replace('\n', "\r\n") };
+            self.console.print(&text);
+            self.open = true;
+        }
+    }
+}
+
+/// Who typed a line, and where.
+pub struct Context<'a> {
+    pub db: &'a Pool<Sqlite>,
+    pub requests: RequestRegistry,
+    pub agency_id: String,
+    pub session_id: String,
+}
+
+/// Runs a `kora` line typed in a terminal session and prints the result
+/// there, followed by a fresh shell prompt.
+pub async fn run(app: AppHandle, session_id: String, line: String) {
+    let state = app.state::<AppState>();
+    let context = Context {
+        db: &state.db,
+        requests: state.ai_engine.requests(),
+        agency_id: state.governance.get_active_agency_id(),
+        session_id: session_id.clone(),
+    };
+    let mut console = SessionConsole { app: &app, session_id: &session_id };
+    let (app, state, session) = (&app, &state, &session_id);
+    interpret(&context, &mut console, &line, |command, flags| async move {
+        let mut trace = Vec::new();
+        let outcome = execute(app, state.clone(), session, &command, flags, &mut trace).await;
+        (trace, outcome)
+    }).await;
+}
+
+/// Parses and audits a line, has `execute` run it and renders the outcome.
+///
+/// `execute` returns the kernel calls it made, shown with `--verbose`.
+/// `--silent` commands give the prompt back at once and report through a
+/// notification instead.
+pub async fn interpret<F, Fut>(context: &Context<'_>, console: &mut dyn Console, line: &str, execute: F)
+where
+    F: FnOnce(Command, Flags) -> Fut,
+    Fut: Future<Output = (Vec<String>, Result<Outcome, String>)>,
+{
+    let mut out = Printer { console, silent: false, open: false };
+    let Invocation { command, flags } = match parser::parse(line) {
+        Ok(invocation) => invocation,
+        Err(e) => {
+            out.lines(&[paint(RED, &format!("✖ {}", e)), paint(GRAY, "Run `kora help` for the command list")]);
+            out.console.prompt();
+            return;
+        }
+    };
+
+    let name = command.name();
+    let metadata = format!("command={} session={}", name.replace(' ', "_"), context.session_id);
+    let _ = audit::log_event(context.db, "KORA_SHELL", "RING_3", &metadata, &context.agency_id).await;
+    if flags.silent {
+        out.line(&paint(GRAY, &format!("[kora {} running in background]", name)));
+        out.console.prompt();
+        out.silent = true;
+    }
+
+    let started = Instant::now();
+    let (trace, outcome) = execute(command, flags).await;
+    if flags.verbose {
+        out.lines(&trace.iter().map(|t| paint(GRAY, &format!("→ {}", t))).collect::<Vec<_>>());
+    }
+
+    let result = match outcome {
+        Ok(Outcome::Lines(lines)) => {
+            out.lines(&lines);
+            Ok(())
+        }
+        Ok(Outcome::Stream { request_id, events, citations }) => {
+            let streamed = follow(&mut out, &context.requests, &request_id, events).await;
+            if streamed.is_ok() {
+                out.lines(&citations.iter().enumerate().map(|(i, c)| {
+                    let page = c.page.map(|p| format!("p. {}, ", p)).unwrap_or_default();
+                    paint(GRAY, &format!("[{}] {} ({}bytes {}-{})", i + 1, c.path, page, c.offset_start, c.offset_end))
+                }).collect::<Vec<_>>());
+            }
+            streamed
+        }
+        Err(e) => {
+            out.line(&paint(RED, &format!("✖ {}", e)));
+            Err(e)
+        }
+    };
+    if flags.verbose {
+        out.line(&paint(GRAY, &format!("→ done in {} ms", started.elapsed().as_millis())));
+    }
+
+    if flags.silent {
+        let body = match result {
+            Ok(()) => "Finished".to_string(),
+            Err(e) => format!("Failed: {}", e),
+        };
+        out.console.notify(format!("kora {}", name), body);
+    } else {
+        out.console.prompt();
+    }
+}
+
+/// Prints an engine answer as it streams, until it is done or cancelled (Ctrl+C).
+async fn follow(out: &mut Printer<'_>, requests: &RequestRegistry, request_id: &str, mut events: broadcast::Receiver<EngineMessage>) -> Result<(), String> {
+    loop {
+        let message = match events.recv().await {
+            Ok(message) => message,
+            Err(broadcast::error::RecvError::Lagged(_)) => continue,
+            Err(broadcast::error::RecvError::Closed) => return Err("Engine bus closed".to_string()),
+        };
+        if message.request_id.as_deref() != Some(request_id) {
+            continue;
+        }
+        match message.event {
+            EngineEvent::Output(_) | EngineEvent::Token(_) | EngineEvent::Error(_) if requests.is_cancelled(request_id) => {}
+            EngineEvent::Output(line) => out.line(&paint(GOLD, &line)),
+            EngineEvent::Token(token) => out.text(&paint(GOLD, &token)),
+            EngineEvent::Error(line) => out.line(&paint(RED, &format!("[AI ERR] {}", line))),
+            EngineEvent::Done => return Ok(()),
+            EngineEvent::Cancelled => {
+                out.line(&paint(GRAY, "[Cancelled]"));
+                return Err("Cancelled".to_string());
+            }
+            EngineEvent::Suspended => {}
+        }
+    }
+}
+
+/// Restarts the engine. Refused while one of the agency's requests runs, unless forced.
+async fn restart(db: &Pool<Sqlite>, engine: &EngineHost, agency_id: &str, force: bool, trace: &mut Vec<String>) -> Result<Vec<String>, String> {
+    if !force && engine.requests().latest(agency_id).is_some() {
+        return Err("An engine request is still running; add --force to restart anyway".to_string());
+    }
+    trace.push(format!("ai_engine::restart backend={}", engine.name()));
+    engine.restart()?;
+    let metadata = format!("backend={} forced={}", engine.name(), force);
+    let _ = audit::log_event(db, "ENGINE_RESTART", "RING_1", &metadata, agency_id).await;
+    Ok(vec![paint(GREEN, &format!("✔ Engine restarted ({})", engine.name()))])
+}
+
+/// Sends a workspace verb whose paths already passed the jail and streams the answer.
+async fn workspace(context: &Context<'_>, engine: &EngineHost, action: &str, request: &str, trace: &mut Vec<String>) -> Result<Outcome, String> {
+    let _ = audit::log_event(context.db, "KORA_WORKSPACE", "RING_3", &format!("action={}", action), &context.agency_id).await;
+    // Not `send_command`: it would take the disk paths for jail escapes
+    let events = engine.subscribe();
+    let request_id = engine.submit("WORKSPACE", &context.agency_id, Some(&context.session_id), request)?;
+    trace.push(format!("engine request {}", request_id));
+    Ok(Outcome::Stream { request_id, events, citations: Vec::new() })
+}
+
+async fn execute(
+    app: &AppHandle,
+    state: State<'_, AppState>,
+    session_id: &str,
+    command: &Command,
+    flags: Flags,
+    trace: &mut Vec<String>,
+) -> Result<Outcome, String> {
+    let agency_id = state.governance.get_active_agency_id();
+    let lines = match command {
+        Command::Help => parser::USAGE.lines().map(str::to_string).collect(),
+        Command::SystemStatus => {
+            trace.push("audit::validate_chain".to_string());
+            let integrity = match audit::validate_chain(&state.db).await? {
+                Some(_) => paint(GREEN, "GREEN"),
+                None => paint(RED, "RED"),
+            };
+            let busy = state.ai_engine.requests().latest(&agency_id).is_some();
+            let watch = state.watcher.status();
+            let watching = watch.roots.iter().filter(|r| r.watching).count();
+            let mut sys = System::new();
+            sys.refresh_memory();
+            let locked = state.bridge_locked.load(std::sync::atomic::Ordering::SeqCst);
+            vec![
+                paint(GOLD, "KORA SYSTEM STATUS"),
+                row("Agency", &agency_id),
+                row("Bridge", if locked { paint(RED, "LOCKED") } else { paint(GREEN, "OPEN") }),
+                row("Engine", format!("{} ({})", state.ai_engine.name(), if busy { "busy" } else { "idle" })),
+                row("Integrity", integrity),
+                row("Terminals", state.pty.list().len()),
+                row("Knowledge", format!("{}/{} roots watched, {} queued, {} indexing", watching, watch.roots.len(), watch.pending, watch.running)),
+                row("RAM", format!("{} / {} MB", sys.used_memory() / 1024 / 1024, sys.total_memory() / 1024 / 1024)),
+                row("Uptime", format!("{}s", state.boot_time.elapsed().as_secs())),
+            ]
+        }
+        Command::SystemRestart => restart(&state.db, &state.ai_engine, &agency_id, flags.force, trace).await?,
+        Command::SystemLogs { limit } => {
+            trace.push(format!("audit::get_logs limit={}", limit));
+            let logs = audit::get_logs(&state.db, *limit).await?;
+            logs.iter().rev().map(|log| {
+                let line = format!("{} {:<7} {:<24} {}", log.timestamp.get(..19).unwrap_or(&log.timestamp), log.user, log.action, log.metadata);
+                match flags.verbose {
+                    true => format!("{} {}", line, paint(GRAY, &format!("[{} {}]", log.agency_id, log.curr_hash.get(..12).unwrap_or(&log.curr_hash)))),
+                    false => line,
+                }
+            }).collect()
+        }
+        Command::SystemBenchmark => {
+            trace.push("kora_system_benchmark".to_string());
+            let report = crate::kora_system_benchmark(state).await?;
+            let on_off = |key: &str, on: &str, off: &str| if report[key].as_bool().unwrap_or(false) { on.to_string() } else { off.to_string() };
+            vec![
+                paint(GOLD, "KORA OS PERFORMANCE REPORT"),
+                row("Boot Latency", format!("{}ms", report["boot_ms"])),
+                row("Active RAM", format!("{}MB", report["ram_mb"])),
+                row("DB Latency", format!("{}µs", report["db_latency_us"])),
+                row("Zero-Copy RAG", on_off("zero_copy_rag", "ENABLED", "DISABLED")),
+                row("LTO/Hardening", on_off("lto", "ACTIVE", "INACTIVE")),
+            ]
+        }
+        Command::AgencyList => {
+            trace.push("kora_agency_list".to_string());
+            agency::kora_agency_list(state).await?.iter()
+                .map(|a| format!("{} {} ({})", if a.id == agency_id { "*" } else { "-" }, a.id, a.name))
+                .collect()
+        }
+        Command::AgencySwitch(id) => {
+            trace.push(format!("kora_agency_switch id={}", id));
+            vec![paint(GREEN, &format!("✔ {}", agency::kora_agency_switch(state, id.clone()).await?))]
+        }
+        Command::AgencyCreate(name) => {
+            trace.push("kora_agency_create".to_string());
+            vec![paint(GREEN, &format!("✔ {}", agency::kora_agency_create(app.clone(), state, name.clone()).await?))]
+        }
+        Command::KnowledgeLearn(source) => {
+            trace.push("kora_knowledge_learn".to_string());
+            let mut lines = Vec::new();
+            for doc in learn::kora_knowledge_learn(state, source.clone()).await? {
+                let note = if doc.written { "" } else { ", already archived" };
+                lines.push(paint(GREEN, &format!("✔ {} ({}{})", doc.path, doc.fetcher, note)));
+                if let Some(stats) = &doc.index {
+                    lines.push(paint(GRAY, &format!("  {}", stats)));
+                }
+                if let Some(e) = &doc.error {
+                    lines.push(paint(RED, &format!("  ✖ Not indexed: {}", e)));
+                }
+            }
+            lines
+        }
+        Command::KnowledgeQuery(question) => {
+            trace.push("kora_knowledge".to_string());
+            let events = state.ai_engine.subscribe();
+            let answer = crate::kora_knowledge(state, question.clone(), Some(session_id.to_string())).await?;
+            trace.push(format!("request {} with {} sources", answer.request_id, answer.citations.len()));
+            return Ok(Outcome::Stream { request_id: answer.request_id, events, citations: answer.citations });
+        }
+        Command::KnowledgeOrganize => {
+            trace.push(format!("kora_knowledge_organize apply={}", flags.force));
+            let plan = organize::kora_knowledge_organize(state, flags.force, None).await?;
+            let short = |path: &str| path.strip_prefix(&plan.root).unwrap_or(path).trim_start_matches('/').to_string();
+            let mut lines: Vec<String> = plan.moves.iter()
+                .map(|m| format!("{} → {} {}", short(&m.from), short(&m.to), paint(GRAY, &format!("({})", m.reason))))
+                .collect();
+            lines.extend(plan.unclassified.iter().map(|p| paint(GRAY, &format!("? {} (unclassified)", short(p)))));
+            lines.push(match plan.applied {
+                true => paint(GREEN, &format!("✔ {} files moved", plan.moves.len())),
+                false => paint(GRAY, &format!("Dry run: {} moves planned. Add --force to apply them.", plan.moves.len())),
+            });
+            lines
+        }
+        Command::WorkspaceBuild(_) | Command::WorkspaceAudit(_) | Command::WorkspaceExport(_) => {
+            let root = jail::resolve(&state, &format!("/workspace/{}", agency_id), "WORKSPACE").await?;
+            std::fs::create_dir_all(&root).map_err(|e| format!("Failed to create workspace: {}", e))?;
+            let (action, request) = match command {
+                Command::WorkspaceBuild(idea) => {
+                    crate::ai_engine::check_command_paths(&state, idea).await?;
+                    ("build", format!("WORKSPACE BUILD {} IN {}", idea, root.display()))
+                }
+                Command::WorkspaceAudit(path) => {
+                    let path = jail::resolve(&state, path, "WORKSPACE_AUDIT").await?;
+                    ("audit", format!("WORKSPACE AUDIT {}", path.display()))
+                }
+                Command::WorkspaceExport(format) => ("export", format!("WORKSPACE EXPORT {} FROM {}", format, root.display())),
+                _ => unreachable!(),
+            };
+            let context = Context { db: &state.db, requests: state.ai_engine.requests(), agency_id, session_id: session_id.to_string() };
+            return workspace(&context, &state.ai_engine, action, &request, trace).await;
+        }
+    };
+    Ok(Outcome::Lines(lines))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_pool;
+    use crate::security::vault::SecretVault;
+    use std::path::PathBuf;
+
+    /// Records what a session would show; `<prompt>` marks where the prompt came back.
+    #[derive(Default)]
+    struct Buffer {
+        text: String,
+        notes: Vec<(String, String)>,
+    }
+
+    impl Console for Buffer {
+        fn print(&mut self, text: &str) {
+            self.text.push_str(text);
+        }
+
+        fn prompt(&mut self) {
+            self.text.push_str("<prompt>");
+        }
+
+        fn notify(&mut self, title: String, body: String) {
+            self.notes.push((title, body));
+        }
+    }
+
+    fn context<'a>(db: &'a Pool<Sqlite>, engine: &EngineHost) -> Context<'a> {
+        Context { db, requests: engine.requests(), agency_id: "SYSTEM".to_string(), session_id: "pty-1".to_string() }
+    }
+
+    async fn actions(db: &Pool<Sqlite>) -> Vec<(String, String)> {
+        audit::get_logs(db, 50).await.unwrap().into_iter().map(|log| (log.action, log.metadata)).collect()
+    }
+
+    /// Runs a line with the engine-only part of the dispatcher.
+    async fn restart_line(context: &Context<'_>, engine: &EngineHost, line: &str) -> Buffer {
+        let mut console = Buffer::default();
+        interpret(context, &mut console, line, |_, flags| async move {
+            let mut trace = Vec::new();
+            let outcome = restart(context.db, engine, &context.agency_id, flags.force, &mut trace).await.map(Outcome::Lines);
+            (trace, outcome)
+        }).await;
+        console
+    }
+
+    #[tokio::test]
+    async fn test_lines_are_rendered_and_audited() {
+        let pool = test_pool().await;
+        let engine = EngineHost::new(PathBuf::new(), SecretVault::new(), true);
+        let context = context(&pool, &engine);
+
+        let mut console = Buffer::default();
+        interpret(&context, &mut console, "kora help", |command, _| async move {
+            assert_eq!(command, Command::Help);
+            (Vec::new(), Ok(Outcome::Lines(vec!["first".to_string(), "second".to_string()])))
+        }).await;
+        assert_eq!(console.text, "first\r\nsecond<prompt>");
+        assert!(console.notes.is_empty());
+        assert_eq!(actions(&pool).await, vec![("KORA_SHELL".to_string(), "command=help session=pty-1".to_string())]);
+
+        let mut console = Buffer::default();
+        interpret(&context, &mut console, "kora bogus", |_, _| async { unreachable!() }).await;
+        assert!(console.text.contains("Run `kora help`"));
+        assert!(console.text.ends_with("<prompt>"));
+        assert_eq!(actions(&pool).await.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_restart_needs_force_while_a_request_runs() {
+        let pool = test_pool().await;
+        let engine = EngineHost::new(PathBuf::new(), SecretVault::new(), true);
+        let context = context(&pool, &engine);
+        engine.requests().begin("KNOWLEDGE", "SYSTEM", Some("pty-1"));
+
+        let refused = restart_line(&context, &engine, "kora system restart").await;
+        assert!(refused.text.contains("add --force to restart anyway"));
+        assert!(refused.text.ends_with("<prompt>"));
+        assert!(!actions(&pool).await.iter().any(|(action, _)| action == "ENGINE_RESTART"));
+
+        let forced = restart_line(&context, &engine, "kora system restart --force --verbose").await;
+        assert!(forced.text.contains("Engine restarted (mock)"));
+        assert!(forced.text.contains("→ ai_engine::restart backend=mock"));
+        assert!(forced.text.contains("→ done in "));
+        assert!(actions(&pool).await.contains(&("ENGINE_RESTART".to_string(), "backend=mock forced=true".to_string())));
+    }
+
+    #[tokio::test]
+    async fn test_silent_commands_notify_instead_of_printing() {
+        let pool = test_pool().await;
+        let engine = EngineHost::new(PathBuf::new(), SecretVault::new(), true);
+        let context = context(&pool, &engine);
+
+        let finished = restart_line(&context, &engine, "kora system restart --silent").await;
+        assert_eq!(finished.text, format!("{}<prompt>", paint(GRAY, "[kora system restart running in background]")));
+        assert_eq!(finished.notes, vec![("kora system restart".to_string(), "Finished".to_string())]);
+
+        engine.requests().begin("KNOWLEDGE", "SYSTEM", Some("pty-1"));
+        let failed = restart_line(&context, &engine, "kora system restart --silent").await;
+        assert!(failed.text.ends_with("<prompt>"));
+        assert_eq!(failed.notes.len(), 1);
+        assert!(failed.notes[0].1.starts_with("Failed: An engine request is still running"));
+    }
+
+    #[tokio::test]
+    async fn test_workspace_answer_streams_from_the_engine() {
+        let pool = test_pool().await;
+        let engine = EngineHost::new(PathBuf::new(), SecretVault::new(), true);
+        let context = context(&pool, &engine);
+
+        let mut console = Buffer::default();
+        interpret(&context, &mut console, "kora workspace build landing page --verbose", |command, _| {
+            let (context, engine) = (&context, &engine);
+            async move {
+                let Command::WorkspaceBuild(idea) = command else { unreachable!() };
+                let mut trace = Vec::new();
+                let request = format!("WORKSPACE BUILD {} IN /tmp/workspace", idea);
+                let outcome = workspace(context, engine, "build", &request, &mut trace).await;
+                (trace, outcome)
+            }
+        }).await;
+        assert!(console.text.contains(&paint(GOLD, "Scaffolded project/BRIEF.md")));
+        assert!(console.text.contains("→ engine request "));
+        assert!(console.text.ends_with("<prompt>"));
+
+        let logged = actions(&pool).await;
+        assert!(logged.contains(&("KORA_WORKSPACE".to_string(), "action=build".to_string())));
+        assert!(logged.contains(&("KORA_SHELL".to_string(), "command=workspace_build session=pty-1".to_string())));
+    }
+}
@@ -0,0 +1,260 @@
+/// Global flags of the command dictionary.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Flags {
+    /// Show the kernel calls made and the engine request ids.
+    pub verbose: bool,
+    /// Run in the background and notify when done.
+    pub silent: bool,
+    /// Proceed past minor warnings.
+    pub force: bool,
+}
+
+/// A `kora [group] [action] [parameters]` command.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Command {
+    Help,
+    SystemStatus,
+    SystemRestart,
+    SystemLogs { limit: i64 },
+    SystemBenchmark,
+    AgencyList,
+    AgencySwitch(String),
+    AgencyCreate(String),
+    KnowledgeLearn(String),
+    KnowledgeQuery(String),
+    KnowledgeOrganize,
+    WorkspaceBuild(String),
+    WorkspaceAudit(String),
+    WorkspaceExport(String),
+}
+
+impl Command {
+    /// `group action`, as audited. Parameters are left out; they may hold PII.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Command::Help => "help",
+            Command::SystemStatus => "system status",
+            Command::SystemRestart => "system restart",
+            Command::SystemLogs { .. } => "system logs",
+            Command::SystemBenchmark => "system benchmark",
+            Command::AgencyList => "agency list",
+            Command::AgencySwitch(_) => "agency switch",
+            Command::AgencyCreate(_) => "agency create",
+            Command::KnowledgeLearn(_) => "knowledge learn",
+            Command::KnowledgeQuery(_) => "knowledge query",
+            Command::KnowledgeOrganize => "knowledge organize",
+            Command::WorkspaceBuild(_) => "workspace build",
+            Command::WorkspaceAudit(_) => "workspace audit",
+            Command::WorkspaceExport(_) => "workspace export",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Invocation {
+    pub command: Command,
+    pub flags: Flags,
+}
+
+pub const USAGE: &str = "\
+Usage: kora <group> <action> [parameters] [--verbose|-v] [--silent|-s] [--force|-f]
+
+  kora system status|restart|benchmark
+  kora system logs [count]
+  kora agency list
+  kora agency switch <agency>
+  kora agency create <name>
+  kora knowledge learn <url|path>
+  kora knowledge query <question>
+  kora knowledge organize          (--force applies the plan)
+  kora workspace build <idea>
+  kora workspace audit <path>
+  kora workspace export <json|md>";
+
+/// Manifest formats `kora workspace export` writes.
+const EXPORT_FORMATS: &[&str] = &["json", "md"];
+
+const DEFAULT_LOG_LINES: i64 = 20;
+const MAX_LOG_LINES: i64 = 500;
+
+/// Splits a line into words. Single or double quotes group words and a
+/// backslash escapes the next character.
+pub fn tokenize(line: &str) -> Result<Vec<String>, String> {
+    let mut words = Vec::new();
+    let mut word: Option<String> = None;
+    let mut quote: Option<char> = None;
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (None, '"' | '\'') => {
+                quote = Some(c);
+                word.get_or_insert_with(String::new);
+            }
+            (None, c) if c.is_whitespace() => words.extend(word.take()),
+            (_, '\\') => {
+                let escaped = chars.next().ok_or("Trailing backslash")?;
+                word.get_or_insert_with(String::new).push(escaped);
+            }
+            (_, c) => word.get_or_insert_with(String::new).push(c),
+        }
+    }
+    if let Some(q) = quote {
+        return Err(format!("Unterminated {} quote", q));
+    }
+    words.extend(word);
+    Ok(words)
+}
+
+/// Parses a `kora` line. Flags may appear anywhere after `kora`; `--` ends them.
+/// Inside free-text parameters other words starting with `-` are plain text.
+pub fn parse(line: &str) -> Result<Invocation, String> {
+    let mut words = tokenize(line)?.into_iter();
+    if words.next().as_deref() != Some("kora") {
+        return Err("Not a kora command".to_string());
+    }
+
+    let mut flags = Flags::default();
+    let mut args = Vec::new();
+    // First word that looks like a flag but is not one, with its position
+    let mut unknown = None;
+    let mut flags_done = false;
+    for word in words {
+        if flags_done || !word.starts_with('-') || word == "-" {
+            args.push(word);
+            continue;
+        }
+        match word.as_str() {
+            "--" => flags_done = true,
+            "--verbose" | "-v" => flags.verbose = true,
+            "--silent" | "-s" => flags.silent = true,
+            "--force" | "-f" => flags.force = true,
+            _ => {
+                unknown.get_or_insert((args.len(), word.clone()));
+                args.push(word);
+            }
+        }
+    }
+
+    let group = args.first().map(String::as_str).unwrap_or("help");
+    let action = args.get(1).map(String::as_str).unwrap_or("");
+    let free_text = matches!((group, action), ("agency", "create") | ("knowledge", "query") | ("workspace", "build"));
+    if let Some((_, word)) = unknown.filter(|(i, _)| *i < 2 || !free_text) {
+        return Err(format!("Unknown flag: {}", word));
+    }
+    let rest = args.get(2..).unwrap_or_default();
+    let command = match (group, action) {
+        ("help", _) => Command::Help,
+        ("system", "status") => none(rest, Command::SystemStatus)?,
+        ("system", "restart") => none(rest, Command::SystemRestart)?,
+        ("system", "benchmark") => none(rest, Command::SystemBenchmark)?,
+        ("system", "logs") => {
+            let limit = match rest {
+                [] => DEFAULT_LOG_LINES,
+                [n] => n.parse().ok().filter(|n| (1..=MAX_LOG_LINES).contains(n))
+                    .ok_or_else(|| format!("Log count must be between 1 and {}: {}", MAX_LOG_LINES, n))?,
+                _ => return Err("Usage: kora system logs [count]".to_string()),
+            };
+            Command::SystemLogs { limit }
+        }
+        ("agency", "list") => none(rest, Command::AgencyList)?,
+        ("agency", "switch") => Command::AgencySwitch(one(rest, "kora agency switch <agency>")?),
+        ("agency", "create") => Command::AgencyCreate(text(rest, "kora agency create <name>")?),
+        ("knowledge", "learn") => Command::KnowledgeLearn(one(rest, "kora knowledge learn <url|path>")?),
+        ("knowledge", "query") => Command::KnowledgeQuery(text(rest, "kora knowledge query <question>")?),
+        ("knowledge", "organize") => none(rest, Command::KnowledgeOrganize)?,
+        ("workspace", "build") => Command::WorkspaceBuild(text(rest, "kora workspace build <idea>")?),
+        ("workspace", "audit") => Command::WorkspaceAudit(one(rest, "kora workspace audit <path>")?),
+        ("workspace", "export") => {
+            let format = one(rest, "kora workspace export <json|md>")?;
+            if !EXPORT_FORMATS.contains(&format.as_str()) {
+                return Err(format!("Invalid export format: {} (expected {})", format, EXPORT_FORMATS.join(" or ")));
+            }
+            Command::WorkspaceExport(format)
+        }
+        ("system" | "agency" | "knowledge" | "workspace", "") => return Err(format!("Missing action for kora {}", group)),
+        ("system" | "agency" | "knowledge" | "workspace", _) => return Err(format!("Unknown action: kora {} {}", group, action)),
+        _ => return Err(format!("Unknown command group: {}", group)),
+    };
+    Ok(Invocation { command, flags })
+}
+
+fn none(rest: &[String], command: Command) -> Result<Command, String> {
+    match rest {
+        [] => Ok(command),
+        _ => Err(format!("kora {} takes no parameters", command.name())),
+    }
+}
+
+fn one(rest: &[String], usage: &str) -> Result<String, String> {
+    match rest {
+        [arg] => Ok(arg.clone()),
+        _ => Err(format!("Usage: {}", usage)),
+    }
+}
+
+fn text(rest: &[String], usage: &str) -> Result<String, String> {
+    match rest {
+        [] => Err(format!("Usage: {}", usage)),
+        words => Ok(words.join(" ")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dictionary_commands_and_flags() {
+        let parsed = |line: &str| parse(line).unwrap();
+        assert_eq!(parsed("kora system status").command, Command::SystemStatus);
+        assert_eq!(parsed("kora system logs").command, Command::SystemLogs { limit: 20 });
+        assert_eq!(parsed("kora system logs 5 -v").command, Command::SystemLogs { limit: 5 });
+        assert_eq!(parsed("kora agency switch RED_TEAM").command, Command::AgencySwitch("RED_TEAM".into()));
+        assert_eq!(parsed("kora agency create Red Team").command, Command::AgencyCreate("Red Team".into()));
+        assert_eq!(
+            parsed(r#"kora knowledge query "what's the \"bridge\" lock?""#).command,
+            Command::KnowledgeQuery(r#"what's the "bridge" lock?"#.into())
+        );
+        assert_eq!(parsed("kora knowledge learn '/knowledge/SYSTEM/my notes.md'").command, Command::KnowledgeLearn("/knowledge/SYSTEM/my notes.md".into()));
+        assert_eq!(parsed("kora").command, Command::Help);
+
+        let organize = parsed("kora --force knowledge organize -s");
+        assert_eq!(organize.command, Command::KnowledgeOrganize);
+        assert_eq!(organize.flags, Flags { verbose: false, silent: true, force: true });
+        assert_eq!(parsed("kora workspace build -- -v landing page").command, Command::WorkspaceBuild("-v landing page".into()));
+    }
+
+    #[test]
+    fn test_dashes_in_free_text_are_not_flags() {
+        let query = parse("kora knowledge query what does -rf do --verbose").unwrap();
+        assert_eq!(query.command, Command::KnowledgeQuery("what does -rf do".into()));
+        assert!(query.flags.verbose);
+        assert_eq!(parse("kora agency create Red --team").unwrap().command, Command::AgencyCreate("Red --team".into()));
+        // Reserved flags still apply; `--` keeps them as text
+        assert_eq!(parse("kora knowledge query -s why").unwrap().command, Command::KnowledgeQuery("why".into()));
+        for line in ["kora --loud knowledge query x", "kora knowledge -x query", "kora agency switch -x", "kora system logs -5"] {
+            assert!(parse(line).unwrap_err().contains("Unknown flag"), "{}", line);
+        }
+    }
+
+    #[test]
+    fn test_malformed_commands_are_rejected() {
+        for (line, error) in [
+            ("kora system", "Missing action"),
+            ("kora system reboot", "Unknown action"),
+            ("kora chat hello", "Unknown command group"),
+            ("kora system status now", "takes no parameters"),
+            ("kora system logs 0", "between 1 and 500"),
+            ("kora agency switch", "Usage"),
+            ("kora workspace audit a b", "Usage"),
+            ("kora workspace export ../x", "Invalid export format"),
+            ("kora workspace export pdf", "Invalid export format"),
+            ("kora knowledge query \"open", "Unterminated"),
+            ("kora system status --loud", "Unknown flag"),
+        ] {
+            let e = parse(line).unwrap_err();
+            assert!(e.contains(error), "{}: {}", line, e);
+        }
+    }
+}
@@ -1,6 +1,201 @@
 import { invoke } from "@tauri-apps/api/core";
 import { listen } from "@tauri-apps/api/event";
 
+export interface StreamChunk {
+  request_id: string;
+  kind: "output" | "token" | "error" | "done" | "cancelled";
+  text: string;
+}
+
+export interface Citation {
+  path: string;
+  offset_start: number;
+  offset_end: number;
+  page: number | null; // set for paginated documents (PDF, DOCX, EPUB)
+}
+
+export interface SearchHit {
+  document_id: string;
+  path: string;
+  offset_start: number;
+  offset_end: number;
+  page: number | null;
+  snippet: string; // matched terms wrapped in <mark></mark>
+  score: number;
+}
+
+export interface KnowledgeAnswer {
+  request_id: string;
+  citations: Citation[];
+}
+
+export interface IndexStats {
+  extractor: string;
+  strategy: string;
+  bytes: number;
+  hash: string;
+  unchanged: boolean;
+  chunks: number;
+  reused: number; // chunks kept from the previous index
+  added: number;
+  removed: number;
+}
+
+export interface DocumentInfo {
+  path: string;
+  size: number | null; // bytes at index time
+  hash: string;
+  chunks: number;
+  indexed_at: string;
+  extractor: string;
+  title: string | null;
+  author: string | null;
+  page_count: number | null;
+  tags: string[];
+  source: string | null; // URL or path it was learned from
+  fetched_at: string | null;
+}
+
+export interface DocumentDetail {
+  document: DocumentInfo;
+  chunks: { document_id: string; citation: Citation; text: string | null }[];
+}
+
+export interface LearnedDocument {
+  source: string;
+  path: string;
+  fetcher: string;
+  content_hash: string;
+  fetched_at: string;
+  written: boolean; // false when an identical copy was already archived
+  index: IndexStats | null;
+  error: string | null;
+}
+
+export interface OrganizePlan {
+  root: string;
+  moves: { from: string; to: string; category: "core" | "archive" | "dist"; reason: string }[];
+  unclassified: string[]; // orphans left in place
+  applied: boolean;
+}
+
+export interface ReindexReport {
+  checked: number;
+  unchanged: number;
+  reindexed: number;
+  removed: number; // files gone from disk
+  failed: number;
+  orphans: number;
+}
+
+export interface IndexProgress {
+  path: string;
+  state: "queued" | "indexing" | "indexed" | "removed" | "skipped" | "failed";
+  message: string | null;
+  pending: number;
+  running: number;
+}
+
+export interface WatchConfig {
+  root: string | null; // defaults to knowledge/{agency} under the app data dir
+  ignore: string[]; // .gitignore-style patterns
+  max_file_bytes: number;
+  backend: "native" | "poll"; // native falls back to polling when it cannot watch
+  poll_interval_secs: number;
+}
+
+export interface ScanReport {
+  files: number;
+  queued: number; // new or changed since indexed
+  missing: number;
+}
+
+export interface RootStatus {
+  agency_id: string;
+  path: string;
+  watching: boolean;
+  backend: "native" | "poll" | null;
+  error: string | null;
+  max_file_bytes: number;
+  ignore: string[];
+  events: number;
+  ignored: number;
+  oversized: number;
+  last_scan: ScanReport | null;
+}
+
+export interface WatchStatus {
+  roots: RootStatus[];
+  pending: number;
+  running: number;
+}
+
+export interface SessionInfo {
+  id: string;
+  agency_id: string;
+  shell: string;
+  cols: number;
+  rows: number;
+  raw: boolean; // output arrives as bytes on listenPtyRaw
+  record: boolean; // output is recorded as asciicast
+  created_at: string;
+}
+
+export type PtySignal = "SIGINT" | "SIGTERM" | "SIGTSTP" | "SIGKILL";
+
+// What a typed Ctrl+C / Ctrl+Z does; "cancel" stops the agency's engine request
+export type KeyAction = "forward" | "signal" | "cancel" | "ignore";
+
+export interface SignalConfig {
+  ctrl_c_during_request: KeyAction;
+  ctrl_c: KeyAction;
+  ctrl_z: KeyAction;
+}
+
+export interface RecordingInfo {
+  id: string; // id of the recorded session
+  agency_id: string;
+  path: string;
+  cols: number;
+  rows: number;
+  started_at: string;
+  duration_secs: number;
+  bytes: number;
+  hash: string; // sha256 of the .cast file
+  audit_hash: string | null; // audit entry holding `hash`
+}
+
+export interface CastHeader {
+  version: number;
+  width: number;
+  height: number;
+  timestamp: number;
+  title?: string;
+}
+
+// [seconds since start, "o" (output) | "r" (resize, "COLSxROWS"), data]
+export type CastEvent = [number, string, string];
+
+export interface Replay {
+  recording: RecordingInfo;
+  header: CastHeader;
+  events: CastEvent[];
+}
+
+// A running session with its buffered output, for views restored after a reload
+export interface Attachment {
+  session: SessionInfo;
+  history: string; // base64 for raw sessions
+  dropped: number; // bytes evicted from the scrollback; older output is lost
+}
+
+export interface PtyExit {
+  session_id: string;
+  code: number;
+  success: boolean;
+  status: string; // e.g. "Exited with code 3"
+}
+
 export interface BridgeStatus {
   pulse: "OK" | "FAIL";
   latency: number;
@@ -10,16 +205,84 @@ class KoraBridge {
   private heartbeatInterval: number | null = null;
   public status: BridgeStatus = { pulse: "FAIL", latency: 0 };
 
-  async ptyWrite(data: string) {
-    await invoke("pty_write", { data });
+  // Terminal sessions
+  async ptyCreate(cols: number, rows: number, raw = false, record = false): Promise<SessionInfo> {
+    return await invoke("pty_create", { cols, rows, raw, record });
+  }
+
+  async ptyAttach(sessionId: string): Promise<Attachment> {
+    return await invoke("pty_attach", { sessionId });
+  }
+
+  async ptyWrite(sessionId: string, data: string) {
+    await invoke("pty_write", { sessionId, data });
+  }
+
+  async ptyResize(sessionId: string, cols: number, rows: number) {
+    await invoke("pty_resize", { sessionId, cols, rows });
+  }
+
+  // Signals the session's foreground job; SIGKILL is audited
+  async ptySignal(sessionId: string, signal: PtySignal) {
+    await invoke("pty_signal", { sessionId, signal });
+  }
+
+  async ptySignalConfigGet(): Promise<SignalConfig> {
+    return await invoke("pty_signal_config_get");
+  }
+
+  async ptySignalConfigSet(config: SignalConfig): Promise<string> {
+    return await invoke("pty_signal_config_set", { config });
+  }
+
+  async ptyClose(sessionId: string) {
+    await invoke("pty_close", { sessionId });
+  }
+
+  async ptyList(): Promise<SessionInfo[]> {
+    return await invoke("pty_list");
+  }
+
+  // Recordings of the active agency; export returns the asciicast document
+  async ptyRecordingList(): Promise<RecordingInfo[]> {
+    return await invoke("pty_recording_list");
+  }
+
+  async ptyRecordingExport(id: string): Promise<string> {
+    return await invoke("pty_recording_export", { id });
+  }
+
+  async ptyRecordingReplay(id: string): Promise<Replay> {
+    return await invoke("pty_recording_replay", { id });
   }
 
   async setLock(locked: boolean) {
     await invoke("set_bridge_lock", { locked });
   }
 
-  async listenPty(callback: (data: string) => void) {
-    return await listen<string>("pty-data", (event) => {
+  async listenPty(sessionId: string, callback: (data: string) => void) {
+    return await listen<string>(`pty-data-${sessionId}`, (event) => {
+      callback(event.payload);
+    });
+  }
+
+  // Output of a raw session, undecoded
+  async listenPtyRaw(sessionId: string, callback: (data: Uint8Array) => void) {
+    return await listen<string>(`pty-raw-${sessionId}`, (event) => {
+      callback(Uint8Array.from(atob(event.payload), (c) => c.charCodeAt(0)));
+    });
+  }
+
+  // A recorded session's cast was stored and audited
+  async listenPtyRecorded(callback: (recording: RecordingInfo) => void) {
+    return await listen<RecordingInfo>("pty-recorded", (event) => {
+      callback(event.payload);
+    });
+  }
+
+  // Every session's exit, for views that must clean up
+  async listenPtyExit(callback: (exit: PtyExit) => void) {
+    return await listen<PtyExit>("pty-exit", (event) => {
       callback(event.payload);
     });
   }
@@ -28,7 +291,7 @@ class KoraBridge {
       await invoke("send_notification", { title, body });
   }
 
-  async indexFile(path: string): Promise<string> {
+  async indexFile(path: string): Promise<IndexStats> {
     return await invoke("index_file", { path });
   }
 
@@ -70,16 +333,75 @@ class KoraBridge {
   }
 
   // Phase 6 Commands
-  async koraSystem(action: string): Promise<string> {
-    return await invoke("kora_system", { action });
+  // `sessionId` ties the request to a terminal, so Ctrl+C there cancels it
+  async koraSystem(action: string, sessionId?: string): Promise<string> {
+    return await invoke("kora_system", { action, sessionId });
   }
 
   async koraSystemBenchmark(): Promise<any> {
     return await invoke("kora_system_benchmark");
   }
 
-  async koraKnowledge(query: string): Promise<string> {
-    return await invoke("kora_knowledge", { query });
+  async koraKnowledge(query: string, sessionId?: string): Promise<KnowledgeAnswer> {
+    return await invoke("kora_knowledge", { query, sessionId });
+  }
+
+  async koraKnowledgeSearch(query: string, limit?: number): Promise<SearchHit[]> {
+    return await invoke("kora_knowledge_search", { query, limit });
+  }
+
+  async koraKnowledgeReindex(): Promise<ReindexReport> {
+    return await invoke("kora_knowledge_reindex");
+  }
+
+  async koraKnowledgeList(): Promise<DocumentInfo[]> {
+    return await invoke("kora_knowledge_list");
+  }
+
+  async koraKnowledgeDocument(path: string): Promise<DocumentDetail> {
+    return await invoke("kora_knowledge_document", { path });
+  }
+
+  async koraKnowledgeRemove(path: string): Promise<number> {
+    return await invoke("kora_knowledge_remove", { path });
+  }
+
+  async koraKnowledgeTag(path: string, tags: string[]): Promise<string[]> {
+    return await invoke("kora_knowledge_tag", { path, tags });
+  }
+
+  async koraKnowledgeLearn(source: string): Promise<LearnedDocument[]> {
+    return await invoke("kora_knowledge_learn", { source });
+  }
+
+  // Dry run unless `apply`; `assisted` lets the engine place files the rules cannot
+  async koraKnowledgeOrganize(apply: boolean, assisted = false): Promise<OrganizePlan> {
+    return await invoke("kora_knowledge_organize", { apply, assisted });
+  }
+
+  async koraCancel(requestId: string): Promise<string> {
+    return await invoke("kora_cancel", { requestId });
+  }
+
+  // Per-request stream of a kora_system / kora_knowledge answer
+  async listenStream(requestId: string, callback: (chunk: StreamChunk) => void) {
+    return await listen<StreamChunk>(`kora-stream-${requestId}`, (event) => {
+      callback(event.payload);
+    });
+  }
+
+  // Every request's chunks, for callers that must subscribe before sending
+  async listenStreams(callback: (chunk: StreamChunk) => void) {
+    return await listen<StreamChunk>("kora-stream", (event) => {
+      callback(event.payload);
+    });
+  }
+
+  // Progress of the watcher's indexing queue
+  async listenIndexProgress(callback: (progress: IndexProgress) => void) {
+    return await listen<IndexProgress>("kora-index-progress", (event) => {
+      callback(event.payload);
+    });
   }
 
   // Phase 7: Governance & Multi-Tenancy
@@ -95,10 +417,40 @@ class KoraBridge {
     return await invoke("kora_agency_switch", { id });
   }
 
+  async koraAgencyArchive(id: string): Promise<string> {
+    return await invoke("kora_agency_archive", { id });
+  }
+
   async koraSecurityStatus(): Promise<string> {
     return await invoke("kora_kernel_integrity");
   }
 
+  // Engine Runtime Configuration
+  async koraEngineConfigGet(): Promise<any> {
+    return await invoke("kora_engine_config_get");
+  }
+
+  async koraEngineConfigSet(config: any): Promise<string> {
+    return await invoke("kora_engine_config_set", { config });
+  }
+
+  // Knowledge watcher
+  async koraWatchStatus(): Promise<WatchStatus> {
+    return await invoke("kora_watch_status");
+  }
+
+  async koraWatchConfigGet(): Promise<WatchConfig> {
+    return await invoke("kora_watch_config_get");
+  }
+
+  async koraWatchConfigSet(config: WatchConfig): Promise<string> {
+    return await invoke("kora_watch_config_set", { config });
+  }
+
+  async koraEngineSetApiKey(alias: string, key: string): Promise<string> {
+    return await invoke("kora_engine_set_api_key", { alias, key });
+  }
+
   async koraSafeExit(): Promise<void> {
     return await invoke("cmd_shutdown");
   }